@@ -3,8 +3,8 @@ use std::io::Result;
 fn main() -> Result<()> {
     tonic_build::configure()
         .build_server(false)
-        // derive serialize to support json
-        .type_attribute(".", "#[derive(serde::Serialize)]")
+        // derive serialize to support json, and deserialize to save parts in the records
+        .type_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]")
         .type_attribute(".", "#[serde(rename_all = \"PascalCase\")]")
         .compile(&["proto/runtime.proto"], &["proto"])?;
     Ok(())
@@ -5214,7 +5214,7 @@ impl std::convert::TryFrom<HeaderValue> for header::IntoHeaderValue<ComponentVer
 
 
 
-#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate, Default)]
 #[cfg_attr(feature = "conversion", derive(frunk::LabelledGeneric))]
 pub struct Config {
     #[serde(rename = "ArgsEscaped")]
@@ -8794,40 +8794,40 @@ impl std::convert::TryFrom<HeaderValue> for header::IntoHeaderValue<ContainerNet
 
 
 
-#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate)]
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate)]
 #[cfg_attr(feature = "conversion", derive(frunk::LabelledGeneric))]
 pub struct ContainerNetworkStats {
     #[serde(rename = "RxBytes")]
     #[serde(skip_serializing_if="Option::is_none")]
-    pub rx_bytes: Option<i32>,
+    pub rx_bytes: Option<u64>,
 
     #[serde(rename = "RxDropped")]
     #[serde(skip_serializing_if="Option::is_none")]
-    pub rx_dropped: Option<i32>,
+    pub rx_dropped: Option<u64>,
 
     #[serde(rename = "RxErrors")]
     #[serde(skip_serializing_if="Option::is_none")]
-    pub rx_errors: Option<i32>,
+    pub rx_errors: Option<u64>,
 
     #[serde(rename = "RxPackets")]
     #[serde(skip_serializing_if="Option::is_none")]
-    pub rx_packets: Option<i32>,
+    pub rx_packets: Option<u64>,
 
     #[serde(rename = "TxBytes")]
     #[serde(skip_serializing_if="Option::is_none")]
-    pub tx_bytes: Option<i32>,
+    pub tx_bytes: Option<u64>,
 
     #[serde(rename = "TxDropped")]
     #[serde(skip_serializing_if="Option::is_none")]
-    pub tx_dropped: Option<i32>,
+    pub tx_dropped: Option<u64>,
 
     #[serde(rename = "TxErrors")]
     #[serde(skip_serializing_if="Option::is_none")]
-    pub tx_errors: Option<i32>,
+    pub tx_errors: Option<u64>,
 
     #[serde(rename = "TxPackets")]
     #[serde(skip_serializing_if="Option::is_none")]
-    pub tx_packets: Option<i32>,
+    pub tx_packets: Option<u64>,
 
 }
 
@@ -8935,14 +8935,14 @@ impl std::str::FromStr for ContainerNetworkStats {
         #[derive(Default)]
         #[allow(dead_code)]
         struct IntermediateRep {
-            pub rx_bytes: Vec<i32>,
-            pub rx_dropped: Vec<i32>,
-            pub rx_errors: Vec<i32>,
-            pub rx_packets: Vec<i32>,
-            pub tx_bytes: Vec<i32>,
-            pub tx_dropped: Vec<i32>,
-            pub tx_errors: Vec<i32>,
-            pub tx_packets: Vec<i32>,
+            pub rx_bytes: Vec<u64>,
+            pub rx_dropped: Vec<u64>,
+            pub rx_errors: Vec<u64>,
+            pub rx_packets: Vec<u64>,
+            pub tx_bytes: Vec<u64>,
+            pub tx_dropped: Vec<u64>,
+            pub tx_errors: Vec<u64>,
+            pub tx_packets: Vec<u64>,
         }
 
         let mut intermediate_rep = IntermediateRep::default();
@@ -8961,21 +8961,21 @@ impl std::str::FromStr for ContainerNetworkStats {
                 #[allow(clippy::match_single_binding)]
                 match key {
                     #[allow(clippy::redundant_clone)]
-                    "RxBytes" => intermediate_rep.rx_bytes.push(<i32 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    "RxBytes" => intermediate_rep.rx_bytes.push(<u64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
-                    "RxDropped" => intermediate_rep.rx_dropped.push(<i32 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    "RxDropped" => intermediate_rep.rx_dropped.push(<u64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
-                    "RxErrors" => intermediate_rep.rx_errors.push(<i32 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    "RxErrors" => intermediate_rep.rx_errors.push(<u64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
-                    "RxPackets" => intermediate_rep.rx_packets.push(<i32 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    "RxPackets" => intermediate_rep.rx_packets.push(<u64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
-                    "TxBytes" => intermediate_rep.tx_bytes.push(<i32 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    "TxBytes" => intermediate_rep.tx_bytes.push(<u64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
-                    "TxDropped" => intermediate_rep.tx_dropped.push(<i32 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    "TxDropped" => intermediate_rep.tx_dropped.push(<u64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
-                    "TxErrors" => intermediate_rep.tx_errors.push(<i32 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    "TxErrors" => intermediate_rep.tx_errors.push(<u64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
-                    "TxPackets" => intermediate_rep.tx_packets.push(<i32 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    "TxPackets" => intermediate_rep.tx_packets.push(<u64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     _ => return std::result::Result::Err("Unexpected key while parsing ContainerNetworkStats".to_string())
                 }
             }
@@ -10083,7 +10083,7 @@ impl std::convert::TryFrom<HeaderValue> for header::IntoHeaderValue<ContainerSiz
 
 
 
-#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate, Default)]
 #[cfg_attr(feature = "conversion", derive(frunk::LabelledGeneric))]
 pub struct ContainerState {
     #[serde(rename = "Dead")]
@@ -10394,7 +10394,7 @@ impl std::convert::TryFrom<HeaderValue> for header::IntoHeaderValue<ContainerSta
 
 
 
-#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate)]
+#[derive(Debug, Default, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate)]
 #[cfg_attr(feature = "conversion", derive(frunk::LabelledGeneric))]
 pub struct ContainerStats {
     #[serde(rename = "AvgCPU")]
@@ -10403,11 +10403,11 @@ pub struct ContainerStats {
 
     #[serde(rename = "BlockInput")]
     #[serde(skip_serializing_if="Option::is_none")]
-    pub block_input: Option<i32>,
+    pub block_input: Option<u64>,
 
     #[serde(rename = "BlockOutput")]
     #[serde(skip_serializing_if="Option::is_none")]
-    pub block_output: Option<i32>,
+    pub block_output: Option<u64>,
 
     #[serde(rename = "CPU")]
     #[serde(skip_serializing_if="Option::is_none")]
@@ -10415,11 +10415,11 @@ pub struct ContainerStats {
 
     #[serde(rename = "CPUNano")]
     #[serde(skip_serializing_if="Option::is_none")]
-    pub cpu_nano: Option<i32>,
+    pub cpu_nano: Option<u64>,
 
     #[serde(rename = "CPUSystemNano")]
     #[serde(skip_serializing_if="Option::is_none")]
-    pub cpu_system_nano: Option<i32>,
+    pub cpu_system_nano: Option<u64>,
 
     #[serde(rename = "ContainerID")]
     #[serde(skip_serializing_if="Option::is_none")]
@@ -10427,11 +10427,11 @@ pub struct ContainerStats {
 
     #[serde(rename = "Duration")]
     #[serde(skip_serializing_if="Option::is_none")]
-    pub duration: Option<i32>,
+    pub duration: Option<u64>,
 
     #[serde(rename = "MemLimit")]
     #[serde(skip_serializing_if="Option::is_none")]
-    pub mem_limit: Option<i32>,
+    pub mem_limit: Option<u64>,
 
     #[serde(rename = "MemPerc")]
     #[serde(skip_serializing_if="Option::is_none")]
@@ -10439,7 +10439,7 @@ pub struct ContainerStats {
 
     #[serde(rename = "MemUsage")]
     #[serde(skip_serializing_if="Option::is_none")]
-    pub mem_usage: Option<i32>,
+    pub mem_usage: Option<u64>,
 
     #[serde(rename = "Name")]
     #[serde(skip_serializing_if="Option::is_none")]
@@ -10452,15 +10452,15 @@ pub struct ContainerStats {
 
     #[serde(rename = "PIDs")]
     #[serde(skip_serializing_if="Option::is_none")]
-    pub pids: Option<i32>,
+    pub pids: Option<u64>,
 
     #[serde(rename = "PerCPU")]
     #[serde(skip_serializing_if="Option::is_none")]
-    pub per_cpu: Option<Vec<i32>>,
+    pub per_cpu: Option<Vec<u64>>,
 
     #[serde(rename = "SystemNano")]
     #[serde(skip_serializing_if="Option::is_none")]
-    pub system_nano: Option<i32>,
+    pub system_nano: Option<u64>,
 
 /// A Duration represents the elapsed time between two instants as an int64 nanosecond count. The representation limits the largest representable duration to approximately 290 years.
     #[serde(rename = "UpTime")]
@@ -10650,21 +10650,21 @@ impl std::str::FromStr for ContainerStats {
         #[allow(dead_code)]
         struct IntermediateRep {
             pub avg_cpu: Vec<f64>,
-            pub block_input: Vec<i32>,
-            pub block_output: Vec<i32>,
+            pub block_input: Vec<u64>,
+            pub block_output: Vec<u64>,
             pub cpu: Vec<f64>,
-            pub cpu_nano: Vec<i32>,
-            pub cpu_system_nano: Vec<i32>,
+            pub cpu_nano: Vec<u64>,
+            pub cpu_system_nano: Vec<u64>,
             pub container_id: Vec<String>,
-            pub duration: Vec<i32>,
-            pub mem_limit: Vec<i32>,
+            pub duration: Vec<u64>,
+            pub mem_limit: Vec<u64>,
             pub mem_perc: Vec<f64>,
-            pub mem_usage: Vec<i32>,
+            pub mem_usage: Vec<u64>,
             pub name: Vec<String>,
             pub network: Vec<std::collections::HashMap<String, models::ContainerNetworkStats>>,
-            pub pids: Vec<i32>,
-            pub per_cpu: Vec<Vec<i32>>,
-            pub system_nano: Vec<i32>,
+            pub pids: Vec<u64>,
+            pub per_cpu: Vec<Vec<u64>>,
+            pub system_nano: Vec<u64>,
             pub up_time: Vec<i64>,
         }
 
@@ -10686,33 +10686,33 @@ impl std::str::FromStr for ContainerStats {
                     #[allow(clippy::redundant_clone)]
                     "AvgCPU" => intermediate_rep.avg_cpu.push(<f64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
-                    "BlockInput" => intermediate_rep.block_input.push(<i32 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    "BlockInput" => intermediate_rep.block_input.push(<u64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
-                    "BlockOutput" => intermediate_rep.block_output.push(<i32 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    "BlockOutput" => intermediate_rep.block_output.push(<u64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
                     "CPU" => intermediate_rep.cpu.push(<f64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
-                    "CPUNano" => intermediate_rep.cpu_nano.push(<i32 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    "CPUNano" => intermediate_rep.cpu_nano.push(<u64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
-                    "CPUSystemNano" => intermediate_rep.cpu_system_nano.push(<i32 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    "CPUSystemNano" => intermediate_rep.cpu_system_nano.push(<u64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
                     "ContainerID" => intermediate_rep.container_id.push(<String as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
-                    "Duration" => intermediate_rep.duration.push(<i32 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    "Duration" => intermediate_rep.duration.push(<u64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
-                    "MemLimit" => intermediate_rep.mem_limit.push(<i32 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    "MemLimit" => intermediate_rep.mem_limit.push(<u64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
                     "MemPerc" => intermediate_rep.mem_perc.push(<f64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
-                    "MemUsage" => intermediate_rep.mem_usage.push(<i32 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    "MemUsage" => intermediate_rep.mem_usage.push(<u64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
                     "Name" => intermediate_rep.name.push(<String as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     "Network" => return std::result::Result::Err("Parsing a container in this style is not supported in ContainerStats".to_string()),
                     #[allow(clippy::redundant_clone)]
-                    "PIDs" => intermediate_rep.pids.push(<i32 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    "PIDs" => intermediate_rep.pids.push(<u64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     "PerCPU" => return std::result::Result::Err("Parsing a container in this style is not supported in ContainerStats".to_string()),
                     #[allow(clippy::redundant_clone)]
-                    "SystemNano" => intermediate_rep.system_nano.push(<i32 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    "SystemNano" => intermediate_rep.system_nano.push(<u64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
                     "UpTime" => intermediate_rep.up_time.push(<i64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     _ => return std::result::Result::Err("Unexpected key while parsing ContainerStats".to_string())
@@ -12708,6 +12708,11 @@ pub struct CreateContainerConfig {
     #[serde(skip_serializing_if="Option::is_none")]
     pub env_merge: Option<Vec<String>>,
 
+/// Files with environment variables to read on the server, like Podman's `--env-file`
+    #[serde(rename = "EnvFiles")]
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub env_files: Option<Vec<String>>,
+
 /// PortSet is a collection of structs indexed by Port
     #[serde(rename = "ExposedPorts")]
     #[serde(skip_serializing_if="Option::is_none")]
@@ -12815,6 +12820,7 @@ impl CreateContainerConfig {
             entrypoint: None,
             env: None,
             env_merge: None,
+            env_files: None,
             exposed_ports: None,
             healthcheck: None,
             host_config: None,
@@ -12919,6 +12925,14 @@ impl std::fmt::Display for CreateContainerConfig {
                 ].join(",")
             }),
 
+
+            self.env_files.as_ref().map(|env_files| {
+                [
+                    "EnvFiles".to_string(),
+                    env_files.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(","),
+                ].join(",")
+            }),
+
             // Skipping ExposedPorts in query parameter serialization
             // Skipping ExposedPorts in query parameter serialization
 
@@ -13087,6 +13101,7 @@ impl std::str::FromStr for CreateContainerConfig {
             pub entrypoint: Vec<Vec<String>>,
             pub env: Vec<Vec<String>>,
             pub env_merge: Vec<Vec<String>>,
+            pub env_files: Vec<Vec<String>>,
             pub exposed_ports: Vec<std::collections::HashMap<String, crate::types::Object>>,
             pub healthcheck: Vec<models::HealthcheckConfig>,
             pub host_config: Vec<models::HostConfig>,
@@ -13140,6 +13155,7 @@ impl std::str::FromStr for CreateContainerConfig {
                     "Entrypoint" => return std::result::Result::Err("Parsing a container in this style is not supported in CreateContainerConfig".to_string()),
                     "Env" => return std::result::Result::Err("Parsing a container in this style is not supported in CreateContainerConfig".to_string()),
                     "EnvMerge" => return std::result::Result::Err("Parsing a container in this style is not supported in CreateContainerConfig".to_string()),
+                    "EnvFiles" => return std::result::Result::Err("Parsing a container in this style is not supported in CreateContainerConfig".to_string()),
                     "ExposedPorts" => return std::result::Result::Err("Parsing a container in this style is not supported in CreateContainerConfig".to_string()),
                     #[allow(clippy::redundant_clone)]
                     "Healthcheck" => intermediate_rep.healthcheck.push(<models::HealthcheckConfig as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
@@ -13197,6 +13213,7 @@ impl std::str::FromStr for CreateContainerConfig {
             entrypoint: intermediate_rep.entrypoint.into_iter().next(),
             env: intermediate_rep.env.into_iter().next(),
             env_merge: intermediate_rep.env_merge.into_iter().next(),
+            env_files: intermediate_rep.env_files.into_iter().next(),
             exposed_ports: intermediate_rep.exposed_ports.into_iter().next(),
             healthcheck: intermediate_rep.healthcheck.into_iter().next(),
             host_config: intermediate_rep.host_config.into_iter().next(),
@@ -17961,7 +17978,7 @@ impl std::convert::TryFrom<HeaderValue> for header::IntoHeaderValue<HistoryRespo
 
 
 
-#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate, Default)]
 #[cfg_attr(feature = "conversion", derive(frunk::LabelledGeneric))]
 pub struct HostConfig {
     #[serde(rename = "Annotations")]
@@ -20665,7 +20682,7 @@ impl std::convert::TryFrom<HeaderValue> for header::IntoHeaderValue<ImageBuildLi
 
 
 
-#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate, Default)]
 #[cfg_attr(feature = "conversion", derive(frunk::LabelledGeneric))]
 pub struct ImageConfig {
 /// ArgsEscaped  Deprecated: This field is present only for legacy compatibility with Docker and should not be used by new image builders.  It is used by Docker for Windows images to indicate that the `Entrypoint` or `Cmd` or both, contains only a single element array, that is a pre-escaped, and combined into a single string `CommandLine`. If `true` the value in `Entrypoint` or `Cmd` should be used as-is to avoid double escaping. https://github.com/opencontainers/image-spec/pull/892
@@ -20935,7 +20952,7 @@ impl std::convert::TryFrom<HeaderValue> for header::IntoHeaderValue<ImageConfig>
 
 
 
-#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate, Default)]
 #[cfg_attr(feature = "conversion", derive(frunk::LabelledGeneric))]
 pub struct ImageData {
     #[serde(rename = "Annotations")]
@@ -21659,7 +21676,7 @@ impl std::convert::TryFrom<HeaderValue> for header::IntoHeaderValue<ImageImportR
 
 
 
-#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate, Default)]
 #[cfg_attr(feature = "conversion", derive(frunk::LabelledGeneric))]
 pub struct ImageInspect {
 /// Architecture is the hardware CPU architecture that the image runs on.
@@ -23744,7 +23761,7 @@ impl std::convert::TryFrom<HeaderValue> for header::IntoHeaderValue<InspectBlkio
 
 
 
-#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate, Default)]
 #[cfg_attr(feature = "conversion", derive(frunk::LabelledGeneric))]
 pub struct InspectContainerConfig {
 /// Container annotations
@@ -24394,7 +24411,7 @@ impl std::convert::TryFrom<HeaderValue> for header::IntoHeaderValue<InspectConta
 
 
 
-#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate, Default)]
 #[cfg_attr(feature = "conversion", derive(frunk::LabelledGeneric))]
 pub struct InspectContainerData {
     #[serde(rename = "AppArmorProfile")]
@@ -25139,7 +25156,7 @@ impl std::convert::TryFrom<HeaderValue> for header::IntoHeaderValue<InspectConta
 
 
 
-#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate, Default)]
 #[cfg_attr(feature = "conversion", derive(frunk::LabelledGeneric))]
 pub struct InspectContainerHostConfig {
 /// Annotations are provided to the runtime when the container is started.
@@ -26384,7 +26401,7 @@ impl std::convert::TryFrom<HeaderValue> for header::IntoHeaderValue<InspectConta
 
 
 
-#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate, Default)]
 #[cfg_attr(feature = "conversion", derive(frunk::LabelledGeneric))]
 pub struct InspectContainerState {
     #[serde(rename = "CgroupPath")]
@@ -28880,6 +28897,11 @@ impl std::convert::TryFrom<HeaderValue> for header::IntoHeaderValue<InspectPodCo
 #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate)]
 #[cfg_attr(feature = "conversion", derive(frunk::LabelledGeneric))]
 pub struct InspectPodData {
+/// Annotations of the pod.
+    #[serde(rename = "Annotations")]
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub annotations: Option<std::collections::HashMap<String, String>>,
+
 /// CgroupParent is the parent of the pod's Cgroup.
     #[serde(rename = "CgroupParent")]
     #[serde(skip_serializing_if="Option::is_none")]
@@ -28959,6 +28981,10 @@ pub struct InspectPodData {
     #[serde(skip_serializing_if="Option::is_none")]
     pub namespace: Option<String>,
 
+    #[serde(rename = "NetworkSettings")]
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub network_settings: Option<models::InspectNetworkSettings>,
+
 /// NumContainers is the number of containers in the pod, including the infra container.
     #[serde(rename = "NumContainers")]
     #[serde(skip_serializing_if="Option::is_none")]
@@ -29061,6 +29087,7 @@ impl InspectPodData {
     #[allow(clippy::new_without_default, clippy::too_many_arguments)]
     pub fn new() -> InspectPodData {
         InspectPodData {
+            annotations: None,
             cgroup_parent: None,
             cgroup_path: None,
             containers: None,
@@ -29077,6 +29104,7 @@ impl InspectPodData {
             lock_number: None,
             name: None,
             namespace: None,
+            network_settings: None,
             num_containers: None,
             restart_policy: None,
             shared_namespaces: None,
@@ -29107,6 +29135,9 @@ impl std::fmt::Display for InspectPodData {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let params: Vec<Option<String>> = vec![
 
+            // Skipping Annotations in query parameter serialization
+
+
             self.cgroup_parent.as_ref().map(|cgroup_parent| {
                 [
                     "CgroupParent".to_string(),
@@ -29210,6 +29241,9 @@ impl std::fmt::Display for InspectPodData {
                 ].join(",")
             }),
 
+            // Skipping NetworkSettings in query parameter serialization
+
+
 
             self.num_containers.as_ref().map(|num_containers| {
                 [
@@ -29349,6 +29383,7 @@ impl std::str::FromStr for InspectPodData {
         #[derive(Default)]
         #[allow(dead_code)]
         struct IntermediateRep {
+            pub annotations: Vec<std::collections::HashMap<String, String>>,
             pub cgroup_parent: Vec<String>,
             pub cgroup_path: Vec<String>,
             pub containers: Vec<Vec<models::InspectPodContainerInfo>>,
@@ -29365,6 +29400,7 @@ impl std::str::FromStr for InspectPodData {
             pub lock_number: Vec<i32>,
             pub name: Vec<String>,
             pub namespace: Vec<String>,
+            pub network_settings: Vec<models::InspectNetworkSettings>,
             pub num_containers: Vec<i32>,
             pub restart_policy: Vec<String>,
             pub shared_namespaces: Vec<Vec<String>>,
@@ -29401,6 +29437,8 @@ impl std::str::FromStr for InspectPodData {
             if let Some(key) = key_result {
                 #[allow(clippy::match_single_binding)]
                 match key {
+                    #[allow(clippy::redundant_clone)]
+                    "Annotations" => return std::result::Result::Err("Parsing a container in this style is not supported in InspectPodData".to_string()),
                     #[allow(clippy::redundant_clone)]
                     "CgroupParent" => intermediate_rep.cgroup_parent.push(<String as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
@@ -29431,6 +29469,8 @@ impl std::str::FromStr for InspectPodData {
                     #[allow(clippy::redundant_clone)]
                     "Namespace" => intermediate_rep.namespace.push(<String as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
+                    "NetworkSettings" => intermediate_rep.network_settings.push(<models::InspectNetworkSettings as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    #[allow(clippy::redundant_clone)]
                     "NumContainers" => intermediate_rep.num_containers.push(<i32 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
                     "RestartPolicy" => intermediate_rep.restart_policy.push(<String as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
@@ -29470,6 +29510,7 @@ impl std::str::FromStr for InspectPodData {
 
         // Use the intermediate representation to return the struct
         std::result::Result::Ok(InspectPodData {
+            annotations: intermediate_rep.annotations.into_iter().next(),
             cgroup_parent: intermediate_rep.cgroup_parent.into_iter().next(),
             cgroup_path: intermediate_rep.cgroup_path.into_iter().next(),
             containers: intermediate_rep.containers.into_iter().next(),
@@ -29486,6 +29527,7 @@ impl std::str::FromStr for InspectPodData {
             lock_number: intermediate_rep.lock_number.into_iter().next(),
             name: intermediate_rep.name.into_iter().next(),
             namespace: intermediate_rep.namespace.into_iter().next(),
+            network_settings: intermediate_rep.network_settings.into_iter().next(),
             num_containers: intermediate_rep.num_containers.into_iter().next(),
             restart_policy: intermediate_rep.restart_policy.into_iter().next(),
             shared_namespaces: intermediate_rep.shared_namespaces.into_iter().next(),
@@ -36340,7 +36382,7 @@ impl std::convert::TryFrom<HeaderValue> for header::IntoHeaderValue<ListContaine
 
 
 
-#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate, Default)]
 #[cfg_attr(feature = "conversion", derive(frunk::LabelledGeneric))]
 pub struct ListPodContainer {
     #[serde(rename = "Id")]
@@ -39704,16 +39746,21 @@ pub struct Mount {
     #[serde(skip_serializing_if="Option::is_none")]
     pub consistency: Option<String>,
 
+/// Mount options, as in the OCI runtime spec mounts of libpod.
+    #[serde(rename = "Options", alias = "options")]
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub options: Option<Vec<String>>,
+
     #[serde(rename = "ReadOnly")]
     #[serde(skip_serializing_if="Option::is_none")]
     pub read_only: Option<bool>,
 
 /// Source specifies the name of the mount. Depending on mount type, this may be a volume name or a host path, or even ignored. Source is not supported for tmpfs (must be an empty value)
-    #[serde(rename = "Source")]
+    #[serde(rename = "Source", alias = "source")]
     #[serde(skip_serializing_if="Option::is_none")]
     pub source: Option<String>,
 
-    #[serde(rename = "Target")]
+    #[serde(rename = "Target", alias = "destination")]
     #[serde(skip_serializing_if="Option::is_none")]
     pub target: Option<String>,
 
@@ -39721,7 +39768,7 @@ pub struct Mount {
     #[serde(skip_serializing_if="Option::is_none")]
     pub tmpfs_options: Option<models::TmpfsOptions>,
 
-    #[serde(rename = "Type")]
+    #[serde(rename = "Type", alias = "type")]
     #[serde(skip_serializing_if="Option::is_none")]
     pub r#type: Option<String>,
 
@@ -39739,6 +39786,7 @@ impl Mount {
             bind_options: None,
             cluster_options: None,
             consistency: None,
+            options: None,
             read_only: None,
             source: None,
             target: None,
@@ -39768,6 +39816,14 @@ impl std::fmt::Display for Mount {
             }),
 
 
+            self.options.as_ref().map(|options| {
+                [
+                    "Options".to_string(),
+                    options.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(","),
+                ].join(",")
+            }),
+
+
             self.read_only.as_ref().map(|read_only| {
                 [
                     "ReadOnly".to_string(),
@@ -39823,6 +39879,7 @@ impl std::str::FromStr for Mount {
             pub bind_options: Vec<models::BindOptions>,
             pub cluster_options: Vec<crate::types::Object>,
             pub consistency: Vec<String>,
+            pub options: Vec<Vec<String>>,
             pub read_only: Vec<bool>,
             pub source: Vec<String>,
             pub target: Vec<String>,
@@ -39853,6 +39910,8 @@ impl std::str::FromStr for Mount {
                     #[allow(clippy::redundant_clone)]
                     "Consistency" => intermediate_rep.consistency.push(<String as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
+                    "Options" => return std::result::Result::Err("Parsing a container in this style is not supported in Mount".to_string()),
+                    #[allow(clippy::redundant_clone)]
                     "ReadOnly" => intermediate_rep.read_only.push(<bool as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
                     "Source" => intermediate_rep.source.push(<String as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
@@ -39877,6 +39936,7 @@ impl std::str::FromStr for Mount {
             bind_options: intermediate_rep.bind_options.into_iter().next(),
             cluster_options: intermediate_rep.cluster_options.into_iter().next(),
             consistency: intermediate_rep.consistency.into_iter().next(),
+            options: intermediate_rep.options.into_iter().next(),
             read_only: intermediate_rep.read_only.into_iter().next(),
             source: intermediate_rep.source.into_iter().next(),
             target: intermediate_rep.target.into_iter().next(),
@@ -54741,7 +54801,7 @@ pub struct PruneReport {
 
     #[serde(rename = "Size")]
     #[serde(skip_serializing_if="Option::is_none")]
-    pub size: Option<i32>,
+    pub size: Option<i64>,
 
 }
 
@@ -54806,7 +54866,7 @@ impl std::str::FromStr for PruneReport {
         struct IntermediateRep {
             pub err: Vec<String>,
             pub id: Vec<String>,
-            pub size: Vec<i32>,
+            pub size: Vec<i64>,
         }
 
         let mut intermediate_rep = IntermediateRep::default();
@@ -54829,7 +54889,7 @@ impl std::str::FromStr for PruneReport {
                     #[allow(clippy::redundant_clone)]
                     "Id" => intermediate_rep.id.push(<String as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     #[allow(clippy::redundant_clone)]
-                    "Size" => intermediate_rep.size.push(<i32 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    "Size" => intermediate_rep.size.push(<i64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     _ => return std::result::Result::Err("Unexpected key while parsing PruneReport".to_string())
                 }
             }
@@ -60238,6 +60298,11 @@ pub struct SpecGenerator {
     #[serde(skip_serializing_if="Option::is_none")]
     pub procfs_opts: Option<Vec<String>>,
 
+/// PullPolicy is the policy for pulling the image when the container is created: always, missing, never or newer. Defaults to missing. Optional.
+    #[serde(rename = "pull_policy")]
+    #[serde(skip_serializing_if="Option::is_none")]
+    pub pull_policy: Option<String>,
+
 /// PublishExposedPorts will publish ports specified in the image to random unused ports (guaranteed to be above 1024) on the host. This is based on ports set in Expose below, and any ports specified by the Image (if one is given). Only available if NetNS is set to Bridge or Slirp. Optional.
     #[serde(rename = "publish_image_ports")]
     #[serde(skip_serializing_if="Option::is_none")]
@@ -60559,6 +60624,7 @@ impl SpecGenerator {
             portmappings: None,
             privileged: None,
             procfs_opts: None,
+            pull_policy: None,
             publish_image_ports: None,
             r_limits: None,
             raw_image_name: None,
@@ -61034,6 +61100,14 @@ impl std::fmt::Display for SpecGenerator {
             }),
 
 
+            self.pull_policy.as_ref().map(|pull_policy| {
+                [
+                    "pull_policy".to_string(),
+                    pull_policy.to_string(),
+                ].join(",")
+            }),
+
+
             self.publish_image_ports.as_ref().map(|publish_image_ports| {
                 [
                     "publish_image_ports".to_string(),
@@ -61427,6 +61501,7 @@ impl std::str::FromStr for SpecGenerator {
             pub portmappings: Vec<Vec<models::PortMapping>>,
             pub privileged: Vec<bool>,
             pub procfs_opts: Vec<Vec<String>>,
+            pub pull_policy: Vec<String>,
             pub publish_image_ports: Vec<bool>,
             pub r_limits: Vec<Vec<models::PosixRlimit>>,
             pub raw_image_name: Vec<String>,
@@ -61601,6 +61676,8 @@ impl std::str::FromStr for SpecGenerator {
                     "privileged" => intermediate_rep.privileged.push(<bool as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     "procfs_opts" => return std::result::Result::Err("Parsing a container in this style is not supported in SpecGenerator".to_string()),
                     #[allow(clippy::redundant_clone)]
+                    "pull_policy" => intermediate_rep.pull_policy.push(<String as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    #[allow(clippy::redundant_clone)]
                     "publish_image_ports" => intermediate_rep.publish_image_ports.push(<bool as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     "r_limits" => return std::result::Result::Err("Parsing a container in this style is not supported in SpecGenerator".to_string()),
                     #[allow(clippy::redundant_clone)]
@@ -61762,6 +61839,7 @@ impl std::str::FromStr for SpecGenerator {
             portmappings: intermediate_rep.portmappings.into_iter().next(),
             privileged: intermediate_rep.privileged.into_iter().next(),
             procfs_opts: intermediate_rep.procfs_opts.into_iter().next(),
+            pull_policy: intermediate_rep.pull_policy.into_iter().next(),
             publish_image_ports: intermediate_rep.publish_image_ports.into_iter().next(),
             r_limits: intermediate_rep.r_limits.into_iter().next(),
             raw_image_name: intermediate_rep.raw_image_name.into_iter().next(),
@@ -63590,7 +63668,7 @@ impl std::convert::TryFrom<HeaderValue> for header::IntoHeaderValue<SystemCompon
 
 
 
-#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate, Default)]
 #[cfg_attr(feature = "conversion", derive(frunk::LabelledGeneric))]
 pub struct SystemDfContainerReport {
     #[serde(rename = "Command")]
@@ -64092,7 +64170,7 @@ impl std::convert::TryFrom<HeaderValue> for header::IntoHeaderValue<SystemDfImag
 
 
 
-#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate, Default)]
 #[cfg_attr(feature = "conversion", derive(frunk::LabelledGeneric))]
 pub struct SystemDfReport {
     #[serde(rename = "Containers")]
@@ -64252,7 +64330,7 @@ impl std::convert::TryFrom<HeaderValue> for header::IntoHeaderValue<SystemDfRepo
 
 
 
-#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize, validator::Validate, Default)]
 #[cfg_attr(feature = "conversion", derive(frunk::LabelledGeneric))]
 pub struct SystemDfVolumeReport {
     #[serde(rename = "Links")]
@@ -67424,18 +67502,22 @@ impl std::convert::TryFrom<HeaderValue> for header::IntoHeaderValue<VolumeConfig
 pub struct VolumeCreate {
 /// Name of the volume driver to use.
     #[serde(rename = "Driver")]
+    #[serde(default)]
     pub driver: String,
 
 /// A mapping of driver options and values. These options are passed directly to the driver and are driver specific.
     #[serde(rename = "DriverOpts")]
+    #[serde(default)]
     pub driver_opts: std::collections::HashMap<String, String>,
 
 /// User-defined key/value metadata.
     #[serde(rename = "Labels")]
+    #[serde(default)]
     pub labels: std::collections::HashMap<String, String>,
 
 /// The new volume's name. If not specified, Docker generates a name.
     #[serde(rename = "Name")]
+    #[serde(default)]
     pub name: String,
 
 }
@@ -68095,7 +68177,7 @@ impl std::convert::TryFrom<HeaderValue> for header::IntoHeaderValue<VolumeRmRepo
 pub struct VolumesPruneReport {
     #[serde(rename = "SpaceReclaimed")]
     #[serde(skip_serializing_if="Option::is_none")]
-    pub space_reclaimed: Option<i32>,
+    pub space_reclaimed: Option<i64>,
 
     #[serde(rename = "VolumesDeleted")]
     #[serde(skip_serializing_if="Option::is_none")]
@@ -68153,7 +68235,7 @@ impl std::str::FromStr for VolumesPruneReport {
         #[derive(Default)]
         #[allow(dead_code)]
         struct IntermediateRep {
-            pub space_reclaimed: Vec<i32>,
+            pub space_reclaimed: Vec<i64>,
             pub volumes_deleted: Vec<Vec<String>>,
         }
 
@@ -68173,7 +68255,7 @@ impl std::str::FromStr for VolumesPruneReport {
                 #[allow(clippy::match_single_binding)]
                 match key {
                     #[allow(clippy::redundant_clone)]
-                    "SpaceReclaimed" => intermediate_rep.space_reclaimed.push(<i32 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
+                    "SpaceReclaimed" => intermediate_rep.space_reclaimed.push(<i64 as std::str::FromStr>::from_str(val).map_err(|x| x.to_string())?),
                     "VolumesDeleted" => return std::result::Result::Err("Parsing a container in this style is not supported in VolumesPruneReport".to_string()),
                     _ => return std::result::Result::Err("Unexpected key while parsing VolumesPruneReport".to_string())
                 }
@@ -0,0 +1,42 @@
+//! Attach sessions.
+//!
+//! Podman clients resize the terminal of an attached container with a separate request,
+//! so we keep a handle to the resize channel of the session attached to each container.
+
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+};
+
+use tokio::sync::mpsc;
+
+use crate::streaming::TerminalSize;
+
+static SESSIONS: LazyLock<Mutex<HashMap<String, mpsc::Sender<TerminalSize>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Register the resize channel of the latest session attached to `container_id`.
+pub fn insert(container_id: String, resize: mpsc::Sender<TerminalSize>) {
+    SESSIONS.lock().unwrap().insert(container_id, resize);
+}
+
+/// Unregister the session owning `resize`, unless a newer session replaced it.
+pub fn remove(container_id: &str, resize: &mpsc::Sender<TerminalSize>) {
+    let mut sessions = SESSIONS.lock().unwrap();
+    if sessions
+        .get(container_id)
+        .is_some_and(|sender| sender.same_channel(resize))
+    {
+        sessions.remove(container_id);
+    }
+}
+
+/// Resize the terminal of the session attached to `container_id`.
+/// Returns `false` if no session is attached.
+pub async fn resize(container_id: &str, size: TerminalSize) -> bool {
+    let sender = SESSIONS.lock().unwrap().get(container_id).cloned();
+    match sender {
+        Some(sender) => sender.send(size).await.is_ok(),
+        None => false,
+    }
+}
@@ -0,0 +1,388 @@
+//! Registry credentials of the pulls made through the CRI.
+//!
+//! Clients send them in the `X-Registry-Auth` header, the base64 encoded JSON of a Docker
+//! `AuthConfig`, or in `X-Registry-Config`, a map of those by registry.
+//! Without them, like Podman, the credentials of the registry come from the auth file
+//! of the containers tools, in the `containers-auth.json` format.
+
+use std::collections::HashMap;
+
+use axum::http::HeaderMap;
+use base64::{engine::general_purpose, Engine};
+use serde::Deserialize;
+
+use crate::cri;
+use crate::error::Error;
+
+const AUTH_HEADER: &str = "X-Registry-Auth";
+const CONFIG_HEADER: &str = "X-Registry-Config";
+
+const DOCKER_HUB: &str = "docker.io";
+/// Legacy names of Docker Hub in auth files.
+const DOCKER_HUB_ALIASES: [&str; 2] = ["index.docker.io", "registry-1.docker.io"];
+
+/// Credentials of a registry, in the format of the headers and of the auth files.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default, rename_all = "lowercase")]
+struct AuthConfig {
+    username: String,
+    password: String,
+    /// Base64 encoded `username:password`.
+    auth: String,
+    serveraddress: String,
+    identitytoken: String,
+    registrytoken: String,
+}
+
+impl AuthConfig {
+    fn is_empty(&self) -> bool {
+        self.username.is_empty()
+            && self.password.is_empty()
+            && self.auth.is_empty()
+            && self.identitytoken.is_empty()
+            && self.registrytoken.is_empty()
+    }
+}
+
+impl From<AuthConfig> for cri::AuthConfig {
+    fn from(value: AuthConfig) -> Self {
+        cri::AuthConfig {
+            username: value.username,
+            password: value.password,
+            auth: value.auth,
+            server_address: value.serveraddress,
+            identity_token: value.identitytoken,
+            registry_token: value.registrytoken,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct AuthFile {
+    auths: HashMap<String, AuthConfig>,
+}
+
+/// The credentials sent by a client.
+#[derive(Debug, Default)]
+pub struct Credentials {
+    /// Credentials for any registry.
+    default: Option<AuthConfig>,
+    /// Credentials by registry, or by repository.
+    by_key: HashMap<String, AuthConfig>,
+}
+
+fn decode_header(headers: &HeaderMap, name: &str) -> Result<Option<serde_json::Value>, Error> {
+    let Some(value) = headers.get(name) else {
+        return Ok(None);
+    };
+    let invalid = || Error::bad_request(format!("failed to parse the {name} header"));
+    let value = value.to_str().map_err(|_| invalid())?.trim();
+    if value.is_empty() {
+        return Ok(None);
+    }
+
+    // clients use the URL safe alphabet, with or without padding
+    let content = general_purpose::URL_SAFE
+        .decode(value)
+        .or_else(|_| general_purpose::URL_SAFE_NO_PAD.decode(value))
+        .or_else(|_| general_purpose::STANDARD.decode(value))
+        .map_err(|_| invalid())?;
+    let value: serde_json::Value = serde_json::from_slice(&content).map_err(|_| invalid())?;
+    Ok((!value.is_null()).then_some(value))
+}
+
+/// Whether a JSON value is a map of credentials rather than credentials.
+fn is_map(value: &serde_json::Value) -> bool {
+    value
+        .as_object()
+        .is_some_and(|items| !items.is_empty() && items.values().all(|item| item.is_object()))
+}
+
+/// The key of a registry in the maps of credentials: without scheme nor API path,
+/// with the legacy names of Docker Hub replaced.
+fn normalize_key(key: &str) -> String {
+    let key = key
+        .strip_prefix("https://")
+        .or_else(|| key.strip_prefix("http://"))
+        .unwrap_or(key);
+    let key = ["/v1/", "/v2/", "/v1", "/v2"]
+        .iter()
+        .find_map(|suffix| key.strip_suffix(suffix))
+        .unwrap_or(key);
+    match key.split_once('/') {
+        Some((host, path)) if DOCKER_HUB_ALIASES.contains(&host) => format!("{DOCKER_HUB}/{path}"),
+        None if DOCKER_HUB_ALIASES.contains(&key) => DOCKER_HUB.to_string(),
+        _ => key.to_string(),
+    }
+}
+
+fn normalize_map(map: HashMap<String, AuthConfig>) -> HashMap<String, AuthConfig> {
+    map.into_iter()
+        .filter(|(_, config)| !config.is_empty())
+        .map(|(key, config)| (normalize_key(&key), config))
+        .collect()
+}
+
+/// The repository of an image reference, with its registry, Docker Hub for short names.
+fn repository(reference: &str) -> String {
+    let name = match reference.split_once('@') {
+        Some((name, _)) => name,
+        None => reference,
+    };
+    let name = match name.rsplit_once(':') {
+        Some((repository, tag)) if !tag.contains('/') => repository,
+        _ => name,
+    };
+    match name.split_once('/') {
+        Some((host, _)) if host.contains(['.', ':']) || host == "localhost" => name.to_string(),
+        Some(_) => format!("{DOCKER_HUB}/{name}"),
+        None => format!("{DOCKER_HUB}/library/{name}"),
+    }
+}
+
+/// The credentials of the map for an image reference, the most specific key first:
+/// `registry/namespace/repository`, ..., `registry`.
+fn lookup(map: &HashMap<String, AuthConfig>, reference: &str) -> Option<AuthConfig> {
+    let repository = repository(reference);
+    let mut key = repository.as_str();
+    loop {
+        if let Some(config) = map.get(key) {
+            return Some(config.clone());
+        }
+        key = key.rsplit_once('/')?.0;
+    }
+}
+
+/// The auth files, in the order used by the containers tools.
+fn auth_files() -> Vec<String> {
+    if let Ok(path) = std::env::var("REGISTRY_AUTH_FILE") {
+        return vec![path];
+    }
+    let mut paths = Vec::new();
+    match std::env::var("XDG_RUNTIME_DIR") {
+        Ok(dir) => paths.push(format!("{dir}/containers/auth.json")),
+        Err(_) => paths.push("/run/containers/0/auth.json".to_string()),
+    }
+    if let Ok(home) = std::env::var("HOME") {
+        paths.push(format!("{home}/.config/containers/auth.json"));
+        paths.push(format!("{home}/.docker/config.json"));
+    }
+    paths
+}
+
+/// The credentials of the auth files for an image reference.
+fn from_auth_files(reference: &str) -> Option<AuthConfig> {
+    for path in auth_files() {
+        let Ok(content) = std::fs::read(&path) else {
+            continue;
+        };
+        match serde_json::from_slice::<AuthFile>(&content) {
+            Ok(file) => {
+                if let Some(config) = lookup(&normalize_map(file.auths), reference) {
+                    tracing::debug!("using the credentials of {path} to pull {reference}");
+                    return Some(config);
+                }
+            }
+            Err(err) => tracing::warn!("ignoring invalid auth file {path}: {err}"),
+        }
+    }
+    None
+}
+
+impl Credentials {
+    /// The credentials of the `X-Registry-Auth` and `X-Registry-Config` headers.
+    pub fn from_headers(headers: &HeaderMap) -> Result<Self, Error> {
+        let mut credentials = Credentials::default();
+        let invalid = |name: &str| Error::bad_request(format!("failed to parse the {name} header"));
+
+        if let Some(value) = decode_header(headers, CONFIG_HEADER)? {
+            let map = serde_json::from_value(value).map_err(|_| invalid(CONFIG_HEADER))?;
+            credentials.by_key = normalize_map(map);
+        }
+        if let Some(value) = decode_header(headers, AUTH_HEADER)? {
+            // for backward compatibility, Podman also accepts a map in this header
+            if is_map(&value) {
+                let map = serde_json::from_value(value).map_err(|_| invalid(AUTH_HEADER))?;
+                credentials.by_key.extend(normalize_map(map));
+            } else {
+                let config: AuthConfig =
+                    serde_json::from_value(value).map_err(|_| invalid(AUTH_HEADER))?;
+                credentials.default = (!config.is_empty()).then_some(config);
+            }
+        }
+        Ok(credentials)
+    }
+
+    /// The credentials to pull an image reference: the ones sent by the client,
+    /// else the ones of the auth files.
+    pub fn for_reference(&self, reference: &str) -> Option<cri::AuthConfig> {
+        self.default
+            .clone()
+            .or_else(|| lookup(&self.by_key, reference))
+            .or_else(|| from_auth_files(reference))
+            .map(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(username: &str) -> AuthConfig {
+        AuthConfig {
+            username: username.to_string(),
+            password: "secret".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn lookup_user(map: &HashMap<String, AuthConfig>, reference: &str) -> Option<String> {
+        lookup(map, reference).map(|config| config.username)
+    }
+
+    fn headers(name: &'static str, json: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        let value = general_purpose::URL_SAFE.encode(json);
+        headers.insert(name, value.parse().unwrap());
+        headers
+    }
+
+    #[test]
+    fn keys_without_scheme_nor_api_path() {
+        assert_eq!(normalize_key("https://quay.io"), "quay.io");
+        assert_eq!(normalize_key("http://localhost:5000/v2/"), "localhost:5000");
+        assert_eq!(normalize_key("quay.io/team/app"), "quay.io/team/app");
+    }
+
+    #[test]
+    fn keys_of_docker_hub_aliases() {
+        assert_eq!(normalize_key("https://index.docker.io/v1/"), DOCKER_HUB);
+        assert_eq!(normalize_key("registry-1.docker.io"), DOCKER_HUB);
+        assert_eq!(
+            normalize_key("index.docker.io/library/alpine"),
+            "docker.io/library/alpine"
+        );
+        assert_eq!(normalize_key(DOCKER_HUB), DOCKER_HUB);
+    }
+
+    #[test]
+    fn repositories_of_references() {
+        assert_eq!(repository("alpine"), "docker.io/library/alpine");
+        assert_eq!(repository("alpine:3.20"), "docker.io/library/alpine");
+        assert_eq!(repository("team/app:1"), "docker.io/team/app");
+        assert_eq!(repository("quay.io/team/app:1"), "quay.io/team/app");
+        assert_eq!(repository("localhost/app"), "localhost/app");
+        assert_eq!(repository("localhost:5000/app:1"), "localhost:5000/app");
+        assert_eq!(
+            repository("localhost:5000/app@sha256:0123abcd"),
+            "localhost:5000/app"
+        );
+        assert_eq!(
+            repository("quay.io/team/app:1@sha256:0123abcd"),
+            "quay.io/team/app"
+        );
+    }
+
+    #[test]
+    fn lookup_most_specific_key_first() {
+        let map = normalize_map(HashMap::from([
+            ("https://index.docker.io/v1/".to_string(), config("hub")),
+            ("docker.io/team".to_string(), config("team")),
+            ("localhost:5000".to_string(), config("local")),
+            ("quay.io/team/app".to_string(), config("app")),
+        ]));
+
+        assert_eq!(lookup_user(&map, "alpine").as_deref(), Some("hub"));
+        assert_eq!(lookup_user(&map, "team/app:1").as_deref(), Some("team"));
+        assert_eq!(
+            lookup_user(&map, "docker.io/team/app@sha256:0123abcd").as_deref(),
+            Some("team")
+        );
+        assert_eq!(
+            lookup_user(&map, "localhost:5000/team/app:1").as_deref(),
+            Some("local")
+        );
+        assert_eq!(
+            lookup_user(&map, "quay.io/team/app:1").as_deref(),
+            Some("app")
+        );
+        // the repository key doesn't match the other repositories of the registry
+        assert_eq!(lookup_user(&map, "quay.io/team/other"), None);
+        assert_eq!(lookup_user(&map, "localhost/app"), None);
+    }
+
+    #[test]
+    fn empty_credentials_are_ignored() {
+        let map = normalize_map(HashMap::from([(
+            "quay.io".to_string(),
+            AuthConfig::default(),
+        )]));
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn headers_in_any_base64_alphabet() {
+        let json = r#"{"username":"user?","password":"secret>"}"#;
+        let encodings = [
+            general_purpose::URL_SAFE.encode(json),
+            general_purpose::URL_SAFE_NO_PAD.encode(json),
+            general_purpose::STANDARD.encode(json),
+        ];
+        for encoded in encodings {
+            let mut headers = HeaderMap::new();
+            headers.insert(AUTH_HEADER, encoded.parse().unwrap());
+            let value = decode_header(&headers, AUTH_HEADER).unwrap().unwrap();
+            assert_eq!(value["username"], "user?");
+        }
+    }
+
+    #[test]
+    fn empty_and_invalid_headers() {
+        assert!(decode_header(&HeaderMap::new(), AUTH_HEADER)
+            .unwrap()
+            .is_none());
+        assert!(decode_header(&headers(AUTH_HEADER, "null"), AUTH_HEADER)
+            .unwrap()
+            .is_none());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTH_HEADER, "not base64!".parse().unwrap());
+        let err = decode_header(&headers, AUTH_HEADER).unwrap_err();
+        assert_eq!(err.status(), axum::http::StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn credentials_of_the_headers() {
+        let credentials = Credentials::from_headers(&headers(
+            AUTH_HEADER,
+            r#"{"username":"user","password":"secret"}"#,
+        ))
+        .unwrap();
+        let config = credentials.for_reference("quay.io/team/app").unwrap();
+        assert_eq!(config.username, "user");
+
+        let credentials = Credentials::from_headers(&headers(
+            CONFIG_HEADER,
+            r#"{"https://index.docker.io/v1/":{"username":"hub","password":"secret"}}"#,
+        ))
+        .unwrap();
+        assert!(credentials.default.is_none());
+        assert_eq!(
+            lookup_user(&credentials.by_key, "alpine").as_deref(),
+            Some("hub")
+        );
+
+        // Podman also accepts a map in the auth header
+        let credentials = Credentials::from_headers(&headers(
+            AUTH_HEADER,
+            r#"{"quay.io":{"username":"quay","password":"secret"}}"#,
+        ))
+        .unwrap();
+        assert!(credentials.default.is_none());
+        assert_eq!(
+            lookup_user(&credentials.by_key, "quay.io/team/app").as_deref(),
+            Some("quay")
+        );
+    }
+}
@@ -3,9 +3,10 @@ use std::error::Error;
 use tokio::net::UnixStream;
 use tonic::transport::{Channel, Endpoint, Uri};
 
+use crate::cri::image_service_client::ImageServiceClient;
 use crate::cri::runtime_service_client::RuntimeServiceClient;
 
-async fn get_channel() -> Result<Channel, Box<dyn Error>> {
+async fn get_channel() -> Result<Channel, Box<dyn Error + Send + Sync>> {
     // We will ignore the http uri and connect to the Unix socket.
     let channel = Endpoint::try_from("http://[::]:50051")?
         .connect_with_connector(tower::service_fn(|_: Uri| {
@@ -18,8 +19,16 @@ async fn get_channel() -> Result<Channel, Box<dyn Error>> {
 }
 
 /// Get a client to connect to a CRI server (for example, CRI-O).
-pub async fn get_client() -> Result<RuntimeServiceClient<Channel>, Box<dyn Error>> {
+pub async fn get_client() -> Result<RuntimeServiceClient<Channel>, Box<dyn Error + Send + Sync>> {
     let channel = get_channel().await?;
     let client = RuntimeServiceClient::new(channel);
     Ok(client)
 }
+
+/// Get a client to the image service of the CRI server.
+pub async fn get_image_client() -> Result<ImageServiceClient<Channel>, Box<dyn Error + Send + Sync>>
+{
+    let channel = get_channel().await?;
+    let client = ImageServiceClient::new(channel);
+    Ok(client)
+}
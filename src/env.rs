@@ -0,0 +1,120 @@
+//! Environment of containers.
+//!
+//! Like Podman, the variables taken from the host and the env files are resolved when
+//! the container is created, and saved in its record with the others.
+
+use std::collections::HashMap;
+
+use podman_api::models::{CreateContainerConfig, SpecGenerator};
+
+use crate::cri;
+use crate::error::Error;
+
+/// Variables passed from the host with `httpproxy`, unless the container sets them.
+const PROXY_VARIABLES: [&str; 8] = [
+    "http_proxy",
+    "HTTP_PROXY",
+    "https_proxy",
+    "HTTPS_PROXY",
+    "ftp_proxy",
+    "FTP_PROXY",
+    "no_proxy",
+    "NO_PROXY",
+];
+
+/// A `KEY=VALUE` variable. A bare `KEY` takes the value of the host, `None` when it has none.
+fn parse(entry: &str) -> Result<Option<(String, String)>, Error> {
+    let (key, value) = match entry.split_once('=') {
+        Some((key, value)) => (key, Some(value.to_string())),
+        None => (entry, None),
+    };
+    if key.is_empty() || key.contains(char::is_whitespace) {
+        return Err(Error::bad_request(format!(
+            "invalid environment variable: \"{entry}\""
+        )));
+    }
+
+    let value = value.or_else(|| std::env::var(key).ok());
+    Ok(value.map(|value| (key.to_string(), value)))
+}
+
+/// The variables of an env file: `KEY=VALUE` or `KEY` lines, with `#` comments.
+fn read_file(path: &str) -> Result<Vec<String>, Error> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|err| Error::bad_request(format!("cannot read env file {path}: {err}")))?;
+    Ok(content
+        .lines()
+        .map(str::trim_start)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect())
+}
+
+/// Resolve the variables, the last value of a variable wins.
+fn resolve(entries: impl IntoIterator<Item = String>) -> Result<Vec<String>, Error> {
+    let mut keys: HashMap<String, usize> = HashMap::new();
+    let mut result: Vec<(String, String)> = Vec::new();
+    for entry in entries {
+        let Some((key, value)) = parse(&entry)? else {
+            continue;
+        };
+        match keys.get(&key) {
+            Some(index) => result[*index].1 = value,
+            None => {
+                keys.insert(key.clone(), result.len());
+                result.push((key, value));
+            }
+        }
+    }
+    Ok(result
+        .into_iter()
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect())
+}
+
+/// Resolve the env files and the variables of the host of a compat create request.
+pub fn resolve_create_config(config: &mut CreateContainerConfig) -> Result<(), Error> {
+    let mut entries = Vec::new();
+    for path in config.env_files.take().unwrap_or_default() {
+        entries.extend(read_file(&path)?);
+    }
+    entries.extend(config.env.take().unwrap_or_default());
+    config.env = Some(resolve(entries)?);
+    Ok(())
+}
+
+/// Add the variables of the host to a libpod create request, as asked by `env_host` and `httpproxy`.
+pub fn resolve_spec(spec: &mut SpecGenerator) -> Result<(), Error> {
+    let mut env = HashMap::new();
+    if spec.env_host.unwrap_or(false) {
+        env.extend(std::env::vars());
+    }
+    if spec.httpproxy.unwrap_or(false) {
+        for key in PROXY_VARIABLES {
+            if let Ok(value) = std::env::var(key) {
+                env.insert(key.to_string(), value);
+            }
+        }
+    }
+    for (key, value) in spec.env.take().unwrap_or_default() {
+        if key.is_empty() {
+            return Err(Error::bad_request(format!(
+                "invalid environment variable: \"={value}\""
+            )));
+        }
+        env.insert(key, value);
+    }
+    spec.env = Some(env);
+    Ok(())
+}
+
+/// The variables in the format of the CRI.
+pub fn key_values(entries: &[String]) -> Result<Vec<cri::KeyValue>, Error> {
+    let mut result = Vec::new();
+    for entry in entries {
+        if let Some((key, value)) = parse(entry)? {
+            result.push(cri::KeyValue { key, value });
+        }
+    }
+    Ok(result)
+}
@@ -0,0 +1,100 @@
+//! Errors returned by the API, in the format of Podman's `ErrorModel`.
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use podman_api::models::ErrorModel;
+
+#[derive(Debug)]
+pub struct Error {
+    status: StatusCode,
+    /// Root cause, formatted for automated parsing.
+    cause: String,
+    /// Error message, formatted for a human to read.
+    message: String,
+}
+
+impl Error {
+    pub fn new(status: StatusCode, cause: impl Into<String>, message: impl Into<String>) -> Self {
+        Error {
+            status,
+            cause: cause.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "bad parameter", message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, "internal error", message)
+    }
+
+    /// No object of `kind` (`container`, `pod`...) matches `name_or_id`.
+    pub fn no_such(kind: &str, name_or_id: &str) -> Self {
+        let cause = format!("no such {kind}");
+        let message = format!("no {kind} with name or ID \"{name_or_id}\" found: {cause}");
+        Self::new(StatusCode::NOT_FOUND, cause, message)
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Errors of the CRI runtime.
+impl From<tonic::Status> for Error {
+    fn from(value: tonic::Status) -> Self {
+        let status = match value.code() {
+            tonic::Code::NotFound => StatusCode::NOT_FOUND,
+            tonic::Code::AlreadyExists => StatusCode::CONFLICT,
+            tonic::Code::FailedPrecondition => StatusCode::CONFLICT,
+            tonic::Code::InvalidArgument | tonic::Code::OutOfRange => StatusCode::BAD_REQUEST,
+            tonic::Code::Unauthenticated => StatusCode::UNAUTHORIZED,
+            tonic::Code::PermissionDenied => StatusCode::FORBIDDEN,
+            tonic::Code::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+            tonic::Code::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            tonic::Code::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        Self::new(status, value.code().description(), value.message())
+    }
+}
+
+/// Failure to connect to the CRI runtime.
+impl From<Box<dyn std::error::Error + Send + Sync>> for Error {
+    fn from(value: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        Self::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "runtime unavailable",
+            format!("cannot connect to the CRI runtime: {value}"),
+        )
+    }
+}
+
+impl From<StatusCode> for Error {
+    fn from(value: StatusCode) -> Self {
+        let reason = value.canonical_reason().unwrap_or_default().to_lowercase();
+        Self::new(value, reason.clone(), reason)
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response {
+        let body = ErrorModel {
+            cause: Some(self.cause),
+            message: Some(self.message),
+            response: Some(self.status.as_u16().into()),
+        };
+        (self.status, Json(body)).into_response()
+    }
+}
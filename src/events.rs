@@ -0,0 +1,402 @@
+//! Events, see `GET /libpod/events`.
+//!
+//! The CRI runtime streams the lifecycle events of containers with `GetContainerEvents`,
+//! which are translated to Podman events. The CRI keeps no history, so past events are
+//! rebuilt from the timestamps of the containers, and those of removed containers are lost.
+//! When Podman serves the images, its image events are merged in.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use http_body_util::BodyExt;
+use serde::Serialize;
+use tokio::{io::AsyncBufReadExt, sync::mpsc};
+use tokio_util::io::StreamReader;
+
+use crate::cri;
+use crate::cri_clients::get_client;
+use crate::error::Error;
+use crate::filters::EventFilters;
+use crate::images;
+use crate::proxy;
+use crate::query;
+
+const NANOS_PER_SECOND: i64 = 1_000_000_000;
+/// Events waiting to be written to the client.
+const EVENTS_BUFFER: usize = 64;
+
+const CONTAINER: &str = "container";
+const IMAGE: &str = "image";
+
+/// An event, in the format of Docker's `events.Message` used by both APIs.
+#[derive(Clone, Debug, Serialize)]
+pub struct Event {
+    pub status: String,
+    pub id: String,
+    pub from: String,
+    #[serde(rename = "Type")]
+    pub r#type: String,
+    #[serde(rename = "Action")]
+    pub action: String,
+    #[serde(rename = "Actor")]
+    pub actor: Actor,
+    pub scope: String,
+    pub time: i64,
+    #[serde(rename = "timeNano")]
+    pub time_nano: i64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Actor {
+    #[serde(rename = "ID")]
+    pub id: String,
+    /// The name and the image of the container, along with its labels.
+    #[serde(rename = "Attributes")]
+    pub attributes: HashMap<String, String>,
+}
+
+/// What the events report about a container, kept to report its removal.
+#[derive(Clone, Debug, Default)]
+struct ContainerInfo {
+    name: String,
+    image: String,
+    labels: HashMap<String, String>,
+    pod_sandbox_id: String,
+}
+
+fn image_name(image: Option<&cri::ImageSpec>) -> String {
+    image
+        .map(|spec| {
+            if spec.user_specified_image.is_empty() {
+                spec.image.clone()
+            } else {
+                spec.user_specified_image.clone()
+            }
+        })
+        .unwrap_or_default()
+}
+
+impl From<&cri::Container> for ContainerInfo {
+    fn from(value: &cri::Container) -> Self {
+        ContainerInfo {
+            name: value.metadata.clone().unwrap_or_default().name,
+            image: image_name(value.image.as_ref()),
+            labels: value.labels.clone(),
+            pod_sandbox_id: value.pod_sandbox_id.clone(),
+        }
+    }
+}
+
+pub struct EventOptions {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub filters: EventFilters,
+    /// The `filters` parameter, passed to Podman.
+    pub raw_filters: Option<String>,
+    /// Keep streaming the events as they happen.
+    pub stream: bool,
+    /// Name the events like the compat API.
+    pub compat: bool,
+    /// Path of the events endpoint of Podman, for the image events.
+    pub podman_path: String,
+}
+
+impl EventOptions {
+    fn in_range(&self, nanos: i64) -> bool {
+        let time = DateTime::from_timestamp_nanos(nanos);
+        self.since.is_none_or(|since| time >= since) && self.until.is_none_or(|until| time <= until)
+    }
+}
+
+fn container_event(
+    action: &str,
+    id: &str,
+    info: &ContainerInfo,
+    nanos: i64,
+    exit_code: Option<i32>,
+    compat: bool,
+) -> Event {
+    // like Podman, the compat API names the `died` event `die`
+    let action = if compat && action == "died" {
+        "die"
+    } else {
+        action
+    };
+
+    let mut attributes = info.labels.clone();
+    attributes.insert("name".to_string(), info.name.clone());
+    attributes.insert("image".to_string(), info.image.clone());
+    if !info.pod_sandbox_id.is_empty() {
+        attributes.insert("podId".to_string(), info.pod_sandbox_id.clone());
+    }
+    if let Some(exit_code) = exit_code {
+        attributes.insert("containerExitCode".to_string(), exit_code.to_string());
+    }
+
+    Event {
+        status: action.to_string(),
+        id: id.to_string(),
+        from: info.image.clone(),
+        r#type: CONTAINER.to_string(),
+        action: action.to_string(),
+        actor: Actor {
+            id: id.to_string(),
+            attributes,
+        },
+        scope: "local".to_string(),
+        time: nanos / NANOS_PER_SECOND,
+        time_nano: nanos,
+    }
+}
+
+/// The past events of the existing containers, in the order they happened.
+async fn history(
+    containers: &[cri::Container],
+    options: &EventOptions,
+) -> Result<Vec<Event>, Error> {
+    let mut client = get_client().await?;
+    let mut events = Vec::new();
+    for container in containers {
+        let request = cri::ContainerStatusRequest {
+            container_id: container.id.clone(),
+            verbose: false,
+        };
+        let status = match client.container_status(request).await {
+            Ok(response) => response.into_inner().status.unwrap_or_default(),
+            // the container was removed since it was listed
+            Err(err) if err.code() == tonic::Code::NotFound => continue,
+            Err(err) => return Err(err.into()),
+        };
+
+        let info = ContainerInfo::from(container);
+        let mut push = |action, nanos, exit_code| {
+            if nanos > 0 && options.in_range(nanos) {
+                let event = container_event(
+                    action,
+                    &container.id,
+                    &info,
+                    nanos,
+                    exit_code,
+                    options.compat,
+                );
+                events.push(event);
+            }
+        };
+        push("create", status.created_at, None);
+        push("start", status.started_at, None);
+        push("died", status.finished_at, Some(status.exit_code));
+    }
+    events.sort_by_key(|event| event.time_nano);
+    Ok(events)
+}
+
+/// Translate an event of the CRI, keeping `containers` up to date.
+fn translate(
+    response: cri::ContainerEventResponse,
+    containers: &mut HashMap<String, ContainerInfo>,
+    compat: bool,
+) -> Event {
+    let id = response.container_id.clone();
+    let status = response
+        .containers_statuses
+        .iter()
+        .find(|status| status.id == id);
+    if let Some(status) = status {
+        let info = containers.entry(id.clone()).or_default();
+        info.name = status.metadata.clone().unwrap_or_default().name;
+        info.image = image_name(status.image.as_ref());
+        info.labels = status.labels.clone();
+        if let Some(pod) = &response.pod_sandbox_status {
+            info.pod_sandbox_id = pod.id.clone();
+        }
+    }
+
+    let (action, exit_code) = match response.container_event_type() {
+        cri::ContainerEventType::ContainerCreatedEvent => ("create", None),
+        cri::ContainerEventType::ContainerStartedEvent => ("start", None),
+        cri::ContainerEventType::ContainerStoppedEvent => {
+            ("died", status.map(|status| status.exit_code))
+        }
+        cri::ContainerEventType::ContainerDeletedEvent => ("remove", None),
+    };
+    let info = if action == "remove" {
+        containers.remove(&id).unwrap_or_default()
+    } else {
+        containers.get(&id).cloned().unwrap_or_default()
+    };
+    container_event(action, &id, &info, response.created_at, exit_code, compat)
+}
+
+/// Forward the events of the CRI, until `until` or until the client is gone.
+async fn forward_live_events(
+    mut stream: tonic::Streaming<cri::ContainerEventResponse>,
+    mut containers: HashMap<String, ContainerInfo>,
+    options: EventOptions,
+    sender: mpsc::Sender<serde_json::Value>,
+) {
+    let deadline = options.until.map(|until| {
+        let remaining = (until - Utc::now()).to_std().unwrap_or_default();
+        tokio::time::Instant::now() + remaining
+    });
+    let until = async {
+        match deadline {
+            Some(deadline) => tokio::time::sleep_until(deadline).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::pin!(until);
+
+    loop {
+        let response = tokio::select! {
+            response = stream.message() => response,
+            _ = &mut until => break,
+            _ = sender.closed() => break,
+        };
+        let response = match response {
+            Ok(Some(response)) => response,
+            Ok(None) => break,
+            Err(err) => {
+                tracing::error!("failed to receive the events of the CRI runtime: {err}");
+                break;
+            }
+        };
+
+        let event = translate(response, &mut containers, options.compat);
+        if !options.filters.matches(&event) {
+            continue;
+        }
+        let Ok(value) = serde_json::to_value(&event) else {
+            continue;
+        };
+        if sender.send(value).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// The query of the events endpoint of Podman, for the image events of the same period.
+fn podman_query(options: &EventOptions) -> Result<String, Error> {
+    let mut filters = query::parse_filters(options.raw_filters.as_deref().unwrap_or_default())
+        .ok_or_else(|| Error::bad_request("invalid filters"))?;
+    filters.insert("type".to_string(), vec![IMAGE.to_string()]);
+    let filters =
+        serde_json::to_string(&filters).map_err(|err| Error::internal(err.to_string()))?;
+
+    let mut params = vec![("filters", filters), ("stream", options.stream.to_string())];
+    if let Some(since) = options.since {
+        params.push(("since", since.timestamp().to_string()));
+    }
+    if let Some(until) = options.until {
+        params.push(("until", until.timestamp().to_string()));
+    }
+    serde_urlencoded::to_string(params).map_err(|err| Error::internal(err.to_string()))
+}
+
+/// Forward the image events of Podman, as they are.
+async fn forward_podman_events(path_query: String, sender: mpsc::Sender<serde_json::Value>) {
+    let body = match proxy::get(&path_query).await {
+        Ok(body) => body,
+        Err(err) => {
+            tracing::warn!("no image events: {err}");
+            return;
+        }
+    };
+    let stream = body.into_data_stream().map_err(std::io::Error::other);
+    let mut lines = StreamReader::new(stream).lines();
+
+    loop {
+        let line = tokio::select! {
+            line = lines.next_line() => line,
+            _ = sender.closed() => break,
+        };
+        let line = match line {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(err) => {
+                tracing::warn!("failed to receive the image events of Podman: {err}");
+                break;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(value) => {
+                if sender.send(value).await.is_err() {
+                    break;
+                }
+            }
+            Err(err) => tracing::warn!("ignoring invalid event of Podman: {err}"),
+        }
+    }
+}
+
+/// Subscribe to the events, past ones first. The events are received until the end
+/// of the period, or forever when streaming without `until`.
+pub async fn subscribe(options: EventOptions) -> Result<mpsc::Receiver<serde_json::Value>, Error> {
+    let wants_containers = options.filters.wants_type(CONTAINER);
+    let live = options.stream && options.until.is_none_or(|until| until > Utc::now());
+
+    let (stream, containers) = if wants_containers {
+        let mut client = get_client().await?;
+        // subscribe first, so that no event is lost between the history and the live events
+        let stream = if live {
+            let request = cri::GetEventsRequest {};
+            Some(client.get_container_events(request).await?.into_inner())
+        } else {
+            None
+        };
+        let request = cri::ListContainersRequest { filter: None };
+        let containers = client
+            .list_containers(request)
+            .await?
+            .into_inner()
+            .containers;
+        (stream, containers)
+    } else {
+        (None, Vec::new())
+    };
+    // without `since`, a stream only reports what happens from now on
+    let history = if wants_containers && (options.since.is_some() || !options.stream) {
+        history(&containers, &options).await?
+    } else {
+        Vec::new()
+    };
+    let podman_path_query = if !images::native() && options.filters.wants_type(IMAGE) {
+        Some(format!(
+            "{}?{}",
+            options.podman_path,
+            podman_query(&options)?
+        ))
+    } else {
+        None
+    };
+
+    let containers: HashMap<String, ContainerInfo> = containers
+        .iter()
+        .map(|container| (container.id.clone(), ContainerInfo::from(container)))
+        .collect();
+
+    let (sender, receiver) = mpsc::channel(EVENTS_BUFFER);
+    tokio::spawn(async move {
+        for event in history {
+            if !options.filters.matches(&event) {
+                continue;
+            }
+            let Ok(value) = serde_json::to_value(&event) else {
+                continue;
+            };
+            if sender.send(value).await.is_err() {
+                return;
+            }
+        }
+        if let Some(path_query) = podman_path_query {
+            tokio::spawn(forward_podman_events(path_query, sender.clone()));
+        }
+        if let Some(stream) = stream {
+            forward_live_events(stream, containers, options, sender).await;
+        }
+    });
+    Ok(receiver)
+}
@@ -0,0 +1,91 @@
+//! Exec sessions.
+//!
+//! Podman creates an exec session first and starts it with a separate request,
+//! while the CRI has a single `Exec` call. Sessions are kept in memory between the two steps,
+//! and afterwards to report the exit code to clients inspecting the session,
+//! until they expire.
+
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use podman_api::models::{InspectExecProcess, InspectExecSession};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::streaming::TerminalSize;
+
+static SESSIONS: LazyLock<Mutex<HashMap<String, ExecSession>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// How long sessions that are not running are kept, for clients to start or inspect them.
+const SESSION_EXPIRY: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Clone, Debug)]
+pub struct ExecSession {
+    pub id: String,
+    pub container_id: String,
+    pub cmd: Vec<String>,
+    pub tty: bool,
+    pub stdin: bool,
+    pub stdout: bool,
+    pub stderr: bool,
+    pub detach_keys: Option<String>,
+    pub running: bool,
+    /// The exit code of the process, once the session ran.
+    pub exit_code: Option<i64>,
+    /// Sends terminal sizes to the running session.
+    pub resize: Option<mpsc::Sender<TerminalSize>>,
+    /// When the session was created, or when it finished.
+    pub idle_since: Instant,
+}
+
+/// Generate a 64 hex digits ID, like the ones Podman uses.
+pub fn new_id() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+pub fn insert(session: ExecSession) {
+    let mut sessions = SESSIONS.lock().unwrap();
+    // drop the sessions nobody started or inspected in time
+    sessions.retain(|_, session| session.running || session.idle_since.elapsed() < SESSION_EXPIRY);
+    sessions.insert(session.id.clone(), session);
+}
+
+pub fn get(id: &str) -> Option<ExecSession> {
+    SESSIONS.lock().unwrap().get(id).cloned()
+}
+
+/// Apply `f` to the session `id`, if it exists, and return its result.
+pub fn update<T>(id: &str, f: impl FnOnce(&mut ExecSession) -> T) -> Option<T> {
+    SESSIONS.lock().unwrap().get_mut(id).map(f)
+}
+
+impl From<ExecSession> for InspectExecSession {
+    fn from(value: ExecSession) -> Self {
+        let mut cmd = value.cmd.into_iter();
+
+        InspectExecSession {
+            can_remove: Some(!value.running),
+            container_id: Some(value.container_id),
+            detach_keys: value.detach_keys,
+            // like Podman, sessions that never ran report 0
+            exit_code: Some(value.exit_code.unwrap_or_default()),
+            id: Some(value.id),
+            open_stderr: Some(value.stderr),
+            open_stdin: Some(value.stdin),
+            open_stdout: Some(value.stdout),
+            pid: Some(0),
+            process_config: Some(InspectExecProcess {
+                entrypoint: cmd.next(),
+                arguments: Some(cmd.collect()),
+                privileged: Some(false),
+                tty: Some(value.tty),
+                user: None,
+            }),
+            running: Some(value.running),
+        }
+    }
+}
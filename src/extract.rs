@@ -0,0 +1,94 @@
+//! Extractors of the handlers, like the ones of axum but rejecting malformed requests
+//! with Podman's `ErrorModel` rather than plain text.
+
+use axum::{
+    async_trait,
+    extract::{FromRequest, FromRequestParts, Request},
+    http::request::Parts,
+    response::{IntoResponse, Response},
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::error::Error;
+
+/// A JSON body, and a JSON response like `axum::Json`.
+pub struct Json<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for Json<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::Json::from_request(request, state).await {
+            Ok(axum::Json(value)) => Ok(Json(value)),
+            Err(rejection) => Err(Error::bad_request(rejection.body_text())),
+        }
+    }
+}
+
+impl<T: Serialize> IntoResponse for Json<T> {
+    fn into_response(self) -> Response {
+        axum::Json(self.0).into_response()
+    }
+}
+
+/// The query string of the request.
+pub struct Query<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::extract::Query::from_request_parts(parts, state).await {
+            Ok(axum::extract::Query(value)) => Ok(Query(value)),
+            Err(rejection) => Err(Error::bad_request(rejection.body_text())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode};
+    use std::collections::HashMap;
+
+    fn request(uri: &str, body: &'static str) -> Request {
+        Request::builder()
+            .uri(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn malformed_body_is_an_error_model() {
+        let result = Json::<HashMap<String, String>>::from_request(request("/", "{"), &()).await;
+        let response = result.err().expect("rejection").into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.headers()["content-type"], "application/json");
+    }
+
+    #[tokio::test]
+    async fn malformed_query_is_an_error_model() {
+        #[derive(Debug, serde::Deserialize)]
+        struct LimitQuery {
+            #[allow(dead_code)]
+            limit: i64,
+        }
+
+        let (mut parts, _) = request("/?limit=all", "").into_parts();
+        let result = Query::<LimitQuery>::from_request_parts(&mut parts, &()).await;
+        let response = result.err().expect("rejection").into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(response.headers()["content-type"], "application/json");
+    }
+}
@@ -0,0 +1,440 @@
+//! Filters of the list endpoints, see `GET /libpod/containers/json`, `GET /libpod/pods/json`
+//! and `GET /libpod/volumes/json`, and of the events.
+//!
+//! What the CRI can express is pushed down to the runtime, the rest is applied here.
+//! Like Podman, the values of a filter are alternatives, except for labels which must all match.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use podman_api::models::ListPodContainer;
+use regex::Regex;
+
+use crate::cri;
+use crate::error::Error;
+use crate::events::Event;
+use crate::query;
+use crate::store::VolumeRecord;
+
+/// Length of a full CRI ID, shorter ones are prefixes.
+const ID_LENGTH: usize = 64;
+
+fn invalid_filter(key: &str) -> Error {
+    Error::bad_request(format!("invalid filter \"{key}\""))
+}
+
+fn parse(filters: Option<&str>) -> Result<HashMap<String, Vec<String>>, Error> {
+    query::parse_filters(filters.unwrap_or_default())
+        .ok_or_else(|| Error::bad_request("invalid filters"))
+}
+
+/// Names are matched as regular expressions.
+fn parse_names(values: Vec<String>) -> Result<Vec<Regex>, Error> {
+    values
+        .iter()
+        .map(|name| {
+            Regex::new(name)
+                .map_err(|err| Error::bad_request(format!("invalid name filter: {err}")))
+        })
+        .collect()
+}
+
+fn matches_ids(ids: &[String], id: &str) -> bool {
+    ids.is_empty() || ids.iter().any(|wanted| id.starts_with(wanted.as_str()))
+}
+
+fn matches_names(names: &[Regex], name: &str) -> bool {
+    names.is_empty() || names.iter().any(|regex| regex.is_match(name))
+}
+
+/// Labels are `key` or `key=value`, all of them must match.
+fn matches_labels(wanted: &[String], labels: &HashMap<String, String>) -> bool {
+    wanted.iter().all(|label| match label.split_once('=') {
+        Some((key, value)) => labels.get(key).is_some_and(|actual| actual == value),
+        None => labels.contains_key(label),
+    })
+}
+
+/// The `key=value` labels, in the form of a CRI label selector.
+fn label_selector(labels: &[String]) -> HashMap<String, String> {
+    labels
+        .iter()
+        .filter_map(|label| label.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+/// A single full ID, which the runtime can look up.
+fn single_id(ids: &[String]) -> String {
+    match ids {
+        [id] if id.len() == ID_LENGTH => id.clone(),
+        _ => String::new(),
+    }
+}
+
+/// Whether the image `reference` of a container is the image `wanted`, which may omit its tag or registry.
+fn matches_image(reference: &str, wanted: &str) -> bool {
+    let with_tag = |name: &str| {
+        let last = name.rsplit('/').next().unwrap_or(name);
+        if last.contains([':', '@']) {
+            name.to_string()
+        } else {
+            format!("{name}:latest")
+        }
+    };
+    let reference = with_tag(reference);
+    let wanted = with_tag(wanted);
+    reference == wanted || reference.ends_with(&format!("/{wanted}"))
+}
+
+/// Filters of the container list.
+#[derive(Debug, Default)]
+pub struct ContainerFilters {
+    ids: Vec<String>,
+    names: Vec<Regex>,
+    labels: Vec<String>,
+    /// States to show, `None` for all of them.
+    /// Statuses without CRI equivalent, like `paused`, match no container.
+    states: Option<Vec<cri::ContainerState>>,
+    /// Names or IDs of pods, see [`ContainerFilters::set_pod_ids`].
+    pods: Vec<String>,
+    ancestors: Vec<String>,
+}
+
+impl ContainerFilters {
+    /// Parse the `filters` parameter. Without `all`, only running containers are shown,
+    /// unless a status is asked for.
+    pub fn parse(filters: Option<&str>, all: bool) -> Result<Self, Error> {
+        let mut result = ContainerFilters::default();
+        for (key, values) in parse(filters)? {
+            match key.as_str() {
+                "id" => result.ids = values,
+                "name" => result.names = parse_names(values)?,
+                "label" => result.labels = values,
+                "status" => {
+                    let mut states = Vec::new();
+                    for status in &values {
+                        match status.as_str() {
+                            "created" | "configured" => {
+                                states.push(cri::ContainerState::ContainerCreated)
+                            }
+                            "running" => states.push(cri::ContainerState::ContainerRunning),
+                            "exited" | "stopped" => {
+                                states.push(cri::ContainerState::ContainerExited)
+                            }
+                            "unknown" => states.push(cri::ContainerState::ContainerUnknown),
+                            "paused" | "restarting" | "removing" | "dead" => {}
+                            _ => {
+                                return Err(Error::bad_request(format!(
+                                    "invalid status filter: {status}"
+                                )))
+                            }
+                        }
+                    }
+                    result.states = Some(states);
+                }
+                "pod" => result.pods = values,
+                "ancestor" => result.ancestors = values,
+                _ => return Err(invalid_filter(&key)),
+            }
+        }
+
+        if !all && result.states.is_none() {
+            result.states = Some(vec![cri::ContainerState::ContainerRunning]);
+        }
+        Ok(result)
+    }
+
+    pub fn pods(&self) -> &[String] {
+        &self.pods
+    }
+
+    /// Replace the pod names or IDs of the filter by the full IDs they resolve to.
+    pub fn set_pod_ids(&mut self, ids: Vec<String>) {
+        self.pods = ids;
+    }
+
+    /// The part of the filters the CRI can apply.
+    pub fn cri_filter(&self) -> cri::ContainerFilter {
+        let state = match self.states.as_deref() {
+            Some([state]) => Some(cri::ContainerStateValue {
+                state: (*state).into(),
+            }),
+            _ => None,
+        };
+        let pod_sandbox_id = match self.pods.as_slice() {
+            [pod] => pod.clone(),
+            _ => String::new(),
+        };
+
+        cri::ContainerFilter {
+            id: single_id(&self.ids),
+            state,
+            pod_sandbox_id,
+            label_selector: label_selector(&self.labels),
+        }
+    }
+
+    /// Whether the container named `name` matches all the filters.
+    pub fn matches(&self, container: &cri::Container, name: &str) -> bool {
+        let image = container
+            .image
+            .as_ref()
+            .map(|image| image.image.as_str())
+            .unwrap_or_default();
+
+        matches_ids(&self.ids, &container.id)
+            && matches_names(&self.names, name)
+            && matches_labels(&self.labels, &container.labels)
+            && self
+                .states
+                .as_ref()
+                .is_none_or(|states| states.contains(&container.state()))
+            && (self.pods.is_empty() || self.pods.contains(&container.pod_sandbox_id))
+            && (self.ancestors.is_empty()
+                || self.ancestors.iter().any(|ancestor| {
+                    let id = ancestor.strip_prefix("sha256:").unwrap_or(ancestor);
+                    matches_image(image, ancestor)
+                        || matches_image(&container.image_ref, ancestor)
+                        || container
+                            .image_id
+                            .trim_start_matches("sha256:")
+                            .starts_with(id)
+                }))
+    }
+}
+
+/// Filters of the pod list.
+#[derive(Debug, Default)]
+pub struct PodFilters {
+    ids: Vec<String>,
+    names: Vec<Regex>,
+    labels: Vec<String>,
+    /// States to show, `None` for all of them.
+    states: Option<Vec<cri::PodSandboxState>>,
+    ctr_ids: Vec<String>,
+    ctr_names: Vec<Regex>,
+    ctr_status: Vec<String>,
+    ctr_number: Vec<usize>,
+}
+
+impl PodFilters {
+    pub fn parse(filters: Option<&str>) -> Result<Self, Error> {
+        let mut result = PodFilters::default();
+        for (key, values) in parse(filters)? {
+            match key.as_str() {
+                "id" => result.ids = values,
+                "name" => result.names = parse_names(values)?,
+                "label" => result.labels = values,
+                "status" => {
+                    let mut states = Vec::new();
+                    for status in &values {
+                        match status.as_str() {
+                            "running" | "ready" => states.push(cri::PodSandboxState::SandboxReady),
+                            "stopped" | "exited" | "dead" | "notready" => {
+                                states.push(cri::PodSandboxState::SandboxNotready)
+                            }
+                            "created" | "paused" | "degraded" | "error" => {}
+                            _ => {
+                                return Err(Error::bad_request(format!(
+                                    "invalid status filter: {status}"
+                                )))
+                            }
+                        }
+                    }
+                    result.states = Some(states);
+                }
+                "ctr-ids" => result.ctr_ids = values,
+                "ctr-names" => result.ctr_names = parse_names(values)?,
+                "ctr-status" => result.ctr_status = values,
+                "ctr-number" => {
+                    result.ctr_number = values
+                        .iter()
+                        .map(|number| number.parse())
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| invalid_filter(&key))?
+                }
+                _ => return Err(invalid_filter(&key)),
+            }
+        }
+        Ok(result)
+    }
+
+    /// The part of the filters the CRI can apply.
+    pub fn cri_filter(&self) -> cri::PodSandboxFilter {
+        let state = match self.states.as_deref() {
+            Some([state]) => Some(cri::PodSandboxStateValue {
+                state: (*state).into(),
+            }),
+            _ => None,
+        };
+
+        cri::PodSandboxFilter {
+            id: single_id(&self.ids),
+            state,
+            label_selector: label_selector(&self.labels),
+        }
+    }
+
+    /// Whether the pod matches the filters on the pod itself.
+    pub fn matches(&self, pod: &cri::PodSandbox) -> bool {
+        let name = pod
+            .metadata
+            .as_ref()
+            .map(|metadata| metadata.name.as_str())
+            .unwrap_or_default();
+
+        matches_ids(&self.ids, &pod.id)
+            && matches_names(&self.names, name)
+            && matches_labels(&self.labels, &pod.labels)
+            && self
+                .states
+                .as_ref()
+                .is_none_or(|states| states.contains(&pod.state()))
+    }
+
+    /// Whether the containers of a pod match the filters on containers.
+    pub fn matches_containers(&self, containers: &[ListPodContainer]) -> bool {
+        let any = |matches: &dyn Fn(&ListPodContainer) -> bool| containers.iter().any(matches);
+
+        (self.ctr_ids.is_empty()
+            || any(&|container| {
+                matches_ids(&self.ctr_ids, container.id.as_deref().unwrap_or_default())
+            }))
+            && (self.ctr_names.is_empty()
+                || any(&|container| {
+                    matches_names(
+                        &self.ctr_names,
+                        container.names.as_deref().unwrap_or_default(),
+                    )
+                }))
+            && (self.ctr_status.is_empty()
+                || any(&|container| {
+                    let status = container.status.as_deref().unwrap_or_default();
+                    self.ctr_status.iter().any(|wanted| wanted == status)
+                }))
+            && (self.ctr_number.is_empty() || self.ctr_number.contains(&containers.len()))
+    }
+}
+
+/// Filters of the volume list and prune.
+#[derive(Debug, Default)]
+pub struct VolumeFilters {
+    names: Vec<Regex>,
+    labels: Vec<String>,
+    /// Labels the volumes must not have, none of them.
+    not_labels: Vec<String>,
+    drivers: Vec<String>,
+    /// Whether the volumes must be unused, or used.
+    dangling: Option<bool>,
+    until: Option<DateTime<Utc>>,
+}
+
+impl VolumeFilters {
+    pub fn parse(filters: Option<&str>) -> Result<Self, Error> {
+        let mut result = VolumeFilters::default();
+        for (key, values) in parse(filters)? {
+            match key.as_str() {
+                "name" => result.names = parse_names(values)?,
+                "label" => result.labels = values,
+                "label!" => result.not_labels = values,
+                "driver" => result.drivers = values,
+                "dangling" => {
+                    let [value] = values.as_slice() else {
+                        return Err(invalid_filter(&key));
+                    };
+                    result.dangling =
+                        Some(query::parse_bool(value).ok_or_else(|| invalid_filter(&key))?);
+                }
+                "until" => {
+                    let [value] = values.as_slice() else {
+                        return Err(invalid_filter(&key));
+                    };
+                    result.until =
+                        Some(query::parse_time(value).ok_or_else(|| invalid_filter(&key))?);
+                }
+                _ => return Err(invalid_filter(&key)),
+            }
+        }
+        Ok(result)
+    }
+
+    /// Whether the volume matches the filters, `used` telling if a container uses it.
+    pub fn matches(&self, volume: &VolumeRecord, used: bool) -> bool {
+        matches_names(&self.names, &volume.name)
+            && matches_labels(&self.labels, &volume.labels)
+            && !self
+                .not_labels
+                .iter()
+                .any(|label| matches_labels(std::slice::from_ref(label), &volume.labels))
+            && (self.drivers.is_empty() || self.drivers.contains(&volume.driver))
+            && self.dangling.is_none_or(|dangling| dangling != used)
+            && self.until.is_none_or(|until| volume.created_at < until)
+    }
+}
+
+/// Filters of the events. Unlike the lists, events match when they match each kind of filter.
+#[derive(Debug, Default)]
+pub struct EventFilters {
+    types: Vec<String>,
+    events: Vec<String>,
+    /// Names or IDs of containers.
+    containers: Vec<String>,
+    images: Vec<String>,
+    labels: Vec<String>,
+    /// IDs of pods.
+    pods: Vec<String>,
+}
+
+impl EventFilters {
+    pub fn parse(filters: Option<&str>) -> Result<Self, Error> {
+        let mut result = EventFilters::default();
+        for (key, values) in parse(filters)? {
+            match key.as_str() {
+                "type" => result.types = values,
+                "event" | "status" => result.events = values,
+                "container" => result.containers = values,
+                "image" => result.images = values,
+                "label" => result.labels = values,
+                "pod" => result.pods = values,
+                _ => return Err(invalid_filter(&key)),
+            }
+        }
+        Ok(result)
+    }
+
+    /// Whether events of this type may match.
+    pub fn wants_type(&self, kind: &str) -> bool {
+        self.types.is_empty() || self.types.iter().any(|wanted| wanted == kind)
+    }
+
+    pub fn matches(&self, event: &Event) -> bool {
+        let attribute = |key: &str| {
+            event
+                .actor
+                .attributes
+                .get(key)
+                .map(String::as_str)
+                .unwrap_or_default()
+        };
+        // the compat API names the `died` event `die`
+        let action = |wanted: &String| {
+            *wanted == event.action
+                || (wanted == "died" || wanted == "die") && event.action.starts_with("die")
+        };
+
+        self.wants_type(&event.r#type)
+            && (self.events.is_empty() || self.events.iter().any(action))
+            && (self.containers.is_empty()
+                || self.containers.iter().any(|wanted| {
+                    event.actor.id.starts_with(wanted.as_str()) || attribute("name") == wanted
+                }))
+            && (self.images.is_empty()
+                || self
+                    .images
+                    .iter()
+                    .any(|wanted| matches_image(attribute("image"), wanted)))
+            && matches_labels(&self.labels, &event.actor.attributes)
+            && matches_ids(&self.pods, attribute("podId"))
+    }
+}
@@ -1,39 +1,127 @@
 use futures::future;
-use podman_api::types::Object;
-use std::collections::HashMap;
+use serde::Deserialize;
+use std::{
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant, SystemTime},
+};
 
-use axum::{extract::Path, http::StatusCode, Json};
+use axum::{
+    body::to_bytes,
+    extract::{ConnectInfo, OriginalUri, Path},
+    http::{HeaderMap, StatusCode, Uri},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use tokio::{io::AsyncWriteExt, sync::mpsc};
 use tonic::Request;
 use uuid::Uuid;
 
 use podman_api::models::{
-    Config, Container, ContainerCreateResponse, ContainerExecRequest, ContainerJson,
-    ContainerState, CreateContainerConfig, Health, IdResponse, ImageVolume, InspectContainerData,
-    InspectContainerState, ListContainer, ListPodContainer, ListPodsReport, Mount, PodRmReport,
-    PodSpecGenerator, PodStartReport, PodStopReport, SpecGenerator,
+    Address, Config, Container, ContainerCreateResponse, ContainerExecRequest, ContainerJson,
+    ContainerState, ContainerUpdateOkBody, ContainerWait200Response, CreateContainerConfig,
+    ExecStartLibpodRequest, Health, HostConfig, IdResponse, ImageInspect, InspectContainerConfig,
+    InspectContainerData, InspectContainerHostConfig, InspectContainerState, InspectExecSession,
+    InspectNetworkSettings, InspectPodContainerInfo, InspectPodData, InspectPodInfraConfig,
+    LibpodContainersRmReport, LibpodImageSummary, LibpodImagesPullReport, LibpodImagesRemoveReport,
+    LinuxResources, ListContainer, ListPodContainer, ListPodsReport, ListResponse, NetworkSettings,
+    PodRmReport, PodSpecGenerator, PodStartReport, PodStatsReport, PodStopReport, PortMapping,
+    PruneReport, SpecGenerator, SystemDfContainerReport, SystemDfReport, SystemDfVolumeReport,
+    Volume, VolumeConfigResponse, VolumeCreate, VolumeCreateOptions, VolumesPruneReport,
 };
 
+use crate::attach;
+use crate::auth::Credentials;
 use crate::cri;
 use crate::cri_clients::get_client;
+use crate::env;
+use crate::error::Error;
+use crate::events;
+use crate::exec;
+use crate::extract::{Json, Query};
+use crate::filters;
+use crate::images;
+use crate::logs;
+use crate::network::{self, NetworkOptions};
+use crate::ports;
+use crate::query;
+use crate::resources::Resources;
+use crate::security::SecurityOptions;
+use crate::stats;
+use crate::store;
+use crate::streaming::{self, ClientReader, ClientWriter, TerminalSize};
+use crate::unix::UdsConnectInfo;
+use crate::userns::UserNamespace;
+use crate::volumes::{self, ContainerVolumes};
 
 const LOCAL_RUNTIME_HANDLER: &str = "crun";
 const DEFAULT_RUNTIME_HANDLER: &str = "";
+const ATTACH_POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// How long an attached client waits for the container to start.
+const ATTACH_START_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+const DEFAULT_STOP_TIMEOUT: i64 = 10;
+/// Label of the sandboxes created implicitly for containers created outside of a pod.
+const AUTO_POD_LABEL: &str = "io.podman-cri.auto-pod";
+/// Timeout in seconds of the `kill` command run in containers.
+const KILL_TIMEOUT: i64 = 10;
+/// Timeout in seconds of the `env` command run to check that containers have it.
+const ENV_CHECK_TIMEOUT: i64 = 10;
+const NANOS_PER_SECOND: i64 = 1_000_000_000;
+/// Go's zero time, which Podman reports for unset timestamps.
+const ZERO_TIME: &str = "0001-01-01T00:00:00Z";
+/// Interval between the progress reports of pulls.
+const PULL_PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+/// Interval between the checks that the client of an event stream is still there.
+const EVENTS_CLIENT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
 
 impl From<cri::Container> for Container {
     fn from(value: cri::Container) -> Self {
+        let state = value.state();
+        let name = value.metadata.unwrap_or_default().name;
+
         Container {
-            created: Some(value.created_at),
+            created: Some(value.created_at / NANOS_PER_SECOND),
             id: Some(value.id),
             image: Some(value.image_ref),
             image_id: Some(value.image_id),
             labels: Some(value.labels),
+            names: Some(vec![format!("/{name}")]),
+            state: Some(state_to_string(state)),
+            status: Some(status_to_string(state)),
             ..Default::default()
         }
     }
 }
 
+/// Podman's name of a container state, for example `running`.
 fn state_to_string(state: cri::ContainerState) -> String {
-    state.as_str_name().to_lowercase().replace("_", " ")
+    state
+        .as_str_name()
+        .trim_start_matches("CONTAINER_")
+        .to_lowercase()
+}
+
+/// Human readable status of a container, as shown by `podman ps`.
+fn status_to_string(state: cri::ContainerState) -> String {
+    match state {
+        cri::ContainerState::ContainerCreated => "Created",
+        cri::ContainerState::ContainerRunning => "Up",
+        cri::ContainerState::ContainerExited => "Exited",
+        cri::ContainerState::ContainerUnknown => "Unknown",
+    }
+    .to_string()
+}
+
+/// Convert a CRI timestamp, in nanoseconds since the epoch. Zero means not set.
+fn nanos_to_datetime(nanos: i64) -> Option<DateTime<Utc>> {
+    (nanos > 0).then(|| DateTime::from_timestamp_nanos(nanos))
+}
+
+/// Format a CRI timestamp like Podman does, using Go's zero time when not set.
+fn nanos_to_rfc3339(nanos: i64) -> String {
+    nanos_to_datetime(nanos)
+        .map(|time| time.to_rfc3339_opts(SecondsFormat::Nanos, true))
+        .unwrap_or(ZERO_TIME.to_string())
 }
 
 impl From<cri::ContainerStatus> for ContainerState {
@@ -44,31 +132,185 @@ impl From<cri::ContainerStatus> for ContainerState {
             dead: Some(false),
             error: Some(value.message),
             exit_code: Some(value.exit_code.into()),
-            finished_at: Some(value.finished_at.to_string()),
+            finished_at: Some(nanos_to_rfc3339(value.finished_at)),
             health: Some(Health::new()),
             oom_killed: Some(value.reason == "OOMKilled"),
             paused: Some(false),
-            pid: Some(1234),
+            pid: None,
             restarting: Some(false),
             running: Some(state == cri::ContainerState::ContainerRunning),
-            started_at: Some(value.started_at.to_string()),
-            status: Some(state.as_str_name().to_string()),
+            started_at: Some(nanos_to_rfc3339(value.started_at)),
+            status: Some(state_to_string(state)),
         }
     }
 }
 
-impl From<cri::ContainerStatus> for ContainerJson {
-    fn from(value: cri::ContainerStatus) -> Self {
-        let state: ContainerState = value.clone().into();
+/// Everything we know about a container:
+/// its CRI status, the runtime specific information and the record of the request that created it.
+pub struct ContainerDetails {
+    status: cri::ContainerStatus,
+    info: serde_json::Value,
+    record: Option<store::ContainerRecord>,
+}
+
+impl ContainerDetails {
+    fn name(&self) -> String {
+        match &self.record {
+            Some(record) => record.name.clone(),
+            None => self.status.metadata.clone().unwrap_or_default().name,
+        }
+    }
+
+    fn pid(&self) -> Option<i64> {
+        self.info["pid"].as_i64()
+    }
+
+    /// The security options the container was created with.
+    fn security(&self) -> SecurityOptions {
+        let security = match self.record.as_ref().map(|record| &record.request) {
+            Some(store::CreateRequest::Libpod(spec)) => SecurityOptions::from_spec(spec),
+            Some(store::CreateRequest::Compat(config)) => {
+                SecurityOptions::from_create_config(config)
+            }
+            None => Ok(SecurityOptions::default()),
+        };
+        // the request was validated when the container was created
+        security.unwrap_or_default()
+    }
+
+    fn port_mappings(&self) -> &[PortMapping] {
+        self.record
+            .as_ref()
+            .map(|record| record.port_mappings.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+async fn container_details(name: String) -> Result<ContainerDetails, Error> {
+    let (status, info) = container_status_verbose(name).await?;
+    let record = store::get_container(&status.id).await;
+    Ok(ContainerDetails {
+        status,
+        info,
+        record,
+    })
+}
+
+/// Process settings of a container: from the stored create request when we have one,
+/// from the OCI runtime spec reported by the runtime otherwise.
+#[derive(Debug, Default)]
+struct ProcessSettings {
+    entrypoint: Vec<String>,
+    cmd: Vec<String>,
+    env: Vec<String>,
+    working_dir: String,
+    hostname: String,
+    user: Option<String>,
+    tty: bool,
+    stdin: bool,
+}
+
+impl From<&ContainerDetails> for ProcessSettings {
+    fn from(value: &ContainerDetails) -> Self {
+        let spec = &value.info["runtimeSpec"];
+        let process = &spec["process"];
+
+        let mut settings = ProcessSettings {
+            entrypoint: Vec::new(),
+            cmd: json_strings(&process["args"]),
+            // the runtime knows the complete environment, including the one of the image
+            env: json_strings(&process["env"]),
+            working_dir: process["cwd"].as_str().unwrap_or("/").to_string(),
+            hostname: spec["hostname"].as_str().unwrap_or_default().to_string(),
+            user: process["user"]["uid"].as_u64().map(|uid| uid.to_string()),
+            tty: process["terminal"].as_bool().unwrap_or(false),
+            stdin: false,
+        };
+
+        let (entrypoint, cmd, env) = match value.record.as_ref().map(|record| &record.request) {
+            Some(store::CreateRequest::Libpod(spec)) => {
+                settings.hostname = spec.hostname.clone().unwrap_or(settings.hostname);
+                settings.user = spec.user.clone().or(settings.user);
+                settings.working_dir = spec.work_dir.clone().unwrap_or(settings.working_dir);
+                settings.tty = spec.terminal.unwrap_or(settings.tty);
+                settings.stdin = spec.stdin.unwrap_or(false);
+                let env = spec.env.clone().map(|env| {
+                    env.into_iter()
+                        .map(|(key, value)| format!("{key}={value}"))
+                        .collect()
+                });
+                (spec.entrypoint.clone(), spec.command.clone(), env)
+            }
+            Some(store::CreateRequest::Compat(config)) => {
+                settings.hostname = config.hostname.clone().unwrap_or(settings.hostname);
+                settings.user = config.user.clone().or(settings.user);
+                settings.working_dir = config.working_dir.clone().unwrap_or(settings.working_dir);
+                settings.tty = config.tty.unwrap_or(settings.tty);
+                settings.stdin = config.open_stdin.unwrap_or(false);
+                (
+                    config.entrypoint.clone(),
+                    config.cmd.clone(),
+                    config.env.clone(),
+                )
+            }
+            None => (None, None, None),
+        };
+
+        // without an explicit command, the runtime spec tells what the image runs
+        if entrypoint.is_some() || cmd.is_some() {
+            settings.entrypoint = entrypoint.unwrap_or_default();
+            settings.cmd = cmd.unwrap_or_default();
+        }
+        if settings.env.is_empty() {
+            settings.env = env.unwrap_or_default();
+        }
 
-        // name, attempt
-        let metadata = value.metadata.unwrap();
-        // mem & cpu
-        // let resources = value.resources.unwrap().linux.unwrap();
-        // uid, gid, groups
-        // let user = value.user.unwrap().linux.unwrap();
+        settings
+    }
+}
+
+impl ProcessSettings {
+    /// The full command line of the process.
+    fn command(&self) -> Vec<String> {
+        [self.entrypoint.clone(), self.cmd.clone()].concat()
+    }
+}
 
-        // let mounts: Vec<MountPoint> = value.mounts.into();
+impl From<ContainerDetails> for ContainerJson {
+    fn from(value: ContainerDetails) -> Self {
+        let settings = ProcessSettings::from(&value);
+        let name = value.name();
+        let mut state: ContainerState = value.status.clone().into();
+        state.pid = value.pid();
+        let network_settings = NetworkSettings {
+            ports: Some(ports::to_port_bindings(value.port_mappings())),
+            ..NetworkSettings::new()
+        };
+        let security = value.security();
+        let host_config = HostConfig {
+            cap_add: Some(security.cap_add.clone()),
+            cap_drop: Some(security.cap_drop.clone()),
+            group_add: Some(security.groups.iter().map(i64::to_string).collect()),
+            privileged: Some(security.privileged),
+            readonly_rootfs: Some(security.readonly_rootfs),
+            security_opt: Some(security.security_opt()),
+            ..Default::default()
+        };
+        let runtime_spec = &value.info["runtimeSpec"];
+        let app_armor_profile = runtime_spec["process"]["apparmorProfile"]
+            .as_str()
+            .map(str::to_string);
+        let process_label = runtime_spec["process"]["selinuxLabel"]
+            .as_str()
+            .map(str::to_string);
+        let mount_label = runtime_spec["linux"]["mountLabel"]
+            .as_str()
+            .map(str::to_string);
+
+        let status = value.status;
+        let metadata = status.metadata.unwrap_or_default();
+        let image = status.image.map(|spec| spec.image);
+        let mut command = settings.command().into_iter();
 
         Self {
             config: Some(Config {
@@ -76,65 +318,54 @@ impl From<cri::ContainerStatus> for ContainerJson {
                 attach_stderr: Some(false),
                 attach_stdin: Some(false),
                 attach_stdout: Some(false),
-                cmd: Some(["cmd".to_string()].into()),
-                domainname: Some("domainname".to_string()),
-                entrypoint: Some(["entrypoint".to_string()].into()),
-                env: Some(["env".to_string()].into()),
-                exposed_ports: None,
-                healthcheck: None,
-                hostname: Some("hostname".to_string()),
-                image: Some(value.image_id),
-                labels: Some(value.labels),
-                mac_address: None,
+                cmd: Some(settings.cmd),
+                entrypoint: Some(settings.entrypoint),
+                env: Some(settings.env),
+                hostname: Some(settings.hostname),
+                image: image.clone(),
+                labels: Some(status.labels),
                 network_disabled: Some(false),
-                on_build: None,
-                open_stdin: Some(false),
-                shell: None,
+                open_stdin: Some(settings.stdin),
                 stdin_once: Some(false),
-                stop_signal: None,
-                stop_timeout: None,
-                tty: Some(false),
-                user: None,
-                volumes: None,
-                working_dir: Some("/".to_string()),
+                tty: Some(settings.tty),
+                user: settings.user,
+                working_dir: Some(settings.working_dir),
+                ..Default::default()
             }),
-            created: Some(value.created_at.to_string()),
-            id: Some(value.id.clone()),
-            image: value.image.map(|spec| spec.image),
-            name: Some(metadata.name),
+            created: Some(nanos_to_rfc3339(status.created_at)),
+            id: Some(status.id),
+            image,
+            log_path: Some(status.log_path),
+            name: Some(format!("/{name}")),
+            path: command.next(),
+            args: Some(command.collect()),
+            restart_count: Some(metadata.attempt.into()),
             state: Some(state),
-            app_armor_profile: None,
-            args: None,
-            driver: None,
-            exec_ids: None,
-            graph_driver: None,
-            host_config: None,
-            hostname_path: None,
-            hosts_path: None,
-            log_path: None,
-            mount_label: None,
-            mounts: None,
-            network_settings: None,
-            node: None,
-            path: None,
-            platform: None,
-            process_label: None,
-            resolv_conf_path: None,
-            restart_count: None,
-            size_root_fs: None,
-            size_rw: None,
+            network_settings: Some(network_settings),
+            host_config: Some(host_config),
+            app_armor_profile,
+            process_label,
+            mount_label,
+            ..Default::default()
         }
     }
 }
 
 impl From<cri::Container> for ListContainer {
     fn from(container: cri::Container) -> Self {
+        let state = container.state();
+        let name = container.metadata.unwrap_or_default().name;
+
         ListContainer {
             id: Some(container.id.clone()),
             image: Some(container.image_ref.clone()),
             image_id: Some(container.image_id.clone()),
-            created_at: Some(container.created_at.to_string()),
-            state: Some(state_to_string(container.state())),
+            created: nanos_to_datetime(container.created_at),
+            created_at: Some(nanos_to_rfc3339(container.created_at)),
+            names: Some(vec![name]),
+            pod: Some(container.pod_sandbox_id),
+            state: Some(state_to_string(state)),
+            status: Some(status_to_string(state)),
             labels: Some(container.labels),
             ..Default::default()
         }
@@ -145,393 +376,1175 @@ impl From<cri::Container> for ListPodContainer {
     fn from(value: cri::Container) -> Self {
         ListPodContainer {
             id: Some(value.id.clone()),
-            status: Some(value.state().as_str_name().to_string()),
-            names: Some(value.id),
+            status: Some(state_to_string(value.state())),
+            names: Some(value.metadata.unwrap_or_default().name),
             restart_count: Some(0),
         }
     }
 }
 
-async fn list_containers(filter: Option<cri::ContainerFilter>) -> Vec<cri::Container> {
+async fn list_containers(
+    filter: Option<cri::ContainerFilter>,
+) -> Result<Vec<cri::Container>, Error> {
     let client = get_client();
     let message = cri::ListContainersRequest { filter };
     let request = Request::new(message);
-    let response = client
-        .await
-        .unwrap()
-        .list_containers(request)
-        .await
-        .unwrap();
-    response.into_inner().containers
+    let response = client.await?.list_containers(request).await?;
+    Ok(response.into_inner().containers)
+}
+
+/// Pick the ID matching `name_or_id` among `(ID, name)` candidates of `kind`, like Podman does:
+/// a full ID first, then a name, then a unique ID prefix.
+fn resolve<'a>(
+    kind: &str,
+    name_or_id: &str,
+    candidates: impl Iterator<Item = (&'a str, &'a str)>,
+) -> Result<String, Error> {
+    // Docker clients may prefix names with a slash
+    let name_or_id = name_or_id.strip_prefix('/').unwrap_or(name_or_id);
+    if name_or_id.is_empty() {
+        return Err(Error::no_such(kind, name_or_id));
+    }
+    let candidates: Vec<(&str, &str)> = candidates.collect();
+
+    if let Some((id, _)) = candidates.iter().find(|(id, _)| *id == name_or_id) {
+        return Ok(id.to_string());
+    }
+
+    let by_name: Vec<&str> = candidates
+        .iter()
+        .filter(|(_, name)| *name == name_or_id)
+        .map(|(id, _)| *id)
+        .collect();
+    let matches = if by_name.is_empty() {
+        candidates
+            .iter()
+            .filter(|(id, _)| id.starts_with(name_or_id))
+            .map(|(id, _)| *id)
+            .collect()
+    } else {
+        by_name
+    };
+
+    match matches.as_slice() {
+        [] => Err(Error::no_such(kind, name_or_id)),
+        [id] => Ok(id.to_string()),
+        _ => Err(Error::new(
+            StatusCode::CONFLICT,
+            format!("more than one result for {kind} name or ID"),
+            format!("more than one result for {kind} name or ID \"{name_or_id}\""),
+        )),
+    }
+}
+
+/// Resolve a container name, full ID or unique ID prefix to its CRI container ID.
+async fn resolve_container(name_or_id: &str) -> Result<String, Error> {
+    let containers = list_containers(None).await?;
+    let candidates = containers.iter().map(|container| {
+        let name = container
+            .metadata
+            .as_ref()
+            .map(|metadata| metadata.name.as_str())
+            .unwrap_or_default();
+        (container.id.as_str(), name)
+    });
+    match resolve("container", name_or_id, candidates) {
+        Err(err) if err.status() == StatusCode::NOT_FOUND => {
+            // restarts recreate containers with new IDs, the records map the previous ones
+            let records = future::join_all(
+                containers
+                    .iter()
+                    .map(|container| store::get_container(&container.id)),
+            )
+            .await;
+            let matches: Vec<String> = records
+                .into_iter()
+                .flatten()
+                .filter(|record| {
+                    record
+                        .previous_ids
+                        .iter()
+                        .any(|id| !name_or_id.is_empty() && id.starts_with(name_or_id))
+                })
+                .map(|record| record.id)
+                .collect();
+            match matches.as_slice() {
+                [id] => Ok(id.clone()),
+                _ => Err(err),
+            }
+        }
+        result => result,
+    }
+}
+
+/// Podman's error for the name of a new object that another one has.
+fn name_in_use(kind: &str, name: &str, id: &str) -> Error {
+    Error::new(
+        StatusCode::CONFLICT,
+        "that name is already in use",
+        format!(
+            "the {kind} name \"{name}\" is already in use by {id}. \
+             You have to remove that {kind} to be able to reuse that name: that name is already in use"
+        ),
+    )
+}
+
+/// Check that no container has the name of a new one,
+/// the CRI only checks the names of the containers of a sandbox.
+async fn check_container_name(name: &str) -> Result<(), Error> {
+    let containers = list_containers(None).await?;
+    match containers.iter().find(|container| {
+        container
+            .metadata
+            .as_ref()
+            .is_some_and(|metadata| metadata.name == name)
+    }) {
+        Some(container) => Err(name_in_use("container", name, &container.id)),
+        None => Ok(()),
+    }
+}
+
+/// The command of a container, as shown when listing containers.
+fn record_command(record: &store::ContainerRecord) -> Vec<String> {
+    let (entrypoint, cmd) = match &record.request {
+        store::CreateRequest::Libpod(spec) => (spec.entrypoint.clone(), spec.command.clone()),
+        store::CreateRequest::Compat(config) => (config.entrypoint.clone(), config.cmd.clone()),
+    };
+    [entrypoint.unwrap_or_default(), cmd.unwrap_or_default()].concat()
+}
+
+/// The query of the container lists of both APIs.
+#[derive(Deserialize)]
+pub struct ListQuery {
+    #[serde(default, deserialize_with = "query::bool_param")]
+    all: bool,
+    filters: Option<String>,
+    /// Number of most recently created containers to list, used by the compat API.
+    limit: Option<i64>,
+    /// Number of most recently created containers to list, used by the libpod API.
+    last: Option<i64>,
+    // `size` and `sync` are accepted and ignored: the CRI reports no sizes and has nothing to sync.
+}
+
+/// List the containers matching the query along with their records, most recent first.
+/// When listing all the containers, drop the records of the containers that are gone.
+async fn list_containers_with_records(
+    query: ListQuery,
+) -> Result<Vec<(cri::Container, Option<store::ContainerRecord>)>, Error> {
+    let mut filters = filters::ContainerFilters::parse(query.filters.as_deref(), query.all)?;
+
+    let mut pod_ids = Vec::new();
+    for pod in filters.pods() {
+        match resolve_pod(pod).await {
+            Ok(id) => pod_ids.push(id),
+            // an unknown pod has no containers
+            Err(err) if err.status() == StatusCode::NOT_FOUND => pod_ids.push(pod.clone()),
+            Err(err) => return Err(err),
+        }
+    }
+    filters.set_pod_ids(pod_ids);
+
+    let cri_filter = filters.cri_filter();
+    let unfiltered = cri_filter == cri::ContainerFilter::default();
+    let listed_at = SystemTime::now();
+    let containers = list_containers(Some(cri_filter)).await?;
+
+    if unfiltered {
+        let live: HashSet<String> = containers
+            .iter()
+            .map(|container| container.id.clone())
+            .collect();
+        store::gc_containers(&live, listed_at).await;
+    }
+
+    let mut containers: Vec<_> = future::join_all(containers.into_iter().map(|container| async {
+        let record = store::get_container(&container.id).await;
+        (container, record)
+    }))
+    .await
+    .into_iter()
+    .filter(|(container, record)| {
+        let name = match record {
+            Some(record) => record.name.clone(),
+            None => container.metadata.clone().unwrap_or_default().name,
+        };
+        filters.matches(container, &name)
+    })
+    .collect();
+
+    containers.sort_by_key(|(container, _)| std::cmp::Reverse(container.created_at));
+    if let Some(limit) = query.last.or(query.limit).filter(|limit| *limit > 0) {
+        containers.truncate(limit as usize);
+    }
+    Ok(containers)
 }
 
-pub async fn container_list() -> Json<Vec<Container>> {
-    let cri_containers = list_containers(None).await;
-    let podman_containers: Vec<Container> = cri_containers
+/// container_list responds to GET `/containers/json`.
+pub async fn container_list(Query(query): Query<ListQuery>) -> Result<Json<Vec<Container>>, Error> {
+    let containers = list_containers_with_records(query).await?;
+    let podman_containers: Vec<Container> = containers
         .into_iter()
-        .map(|item: cri::Container| -> Container { item.into() })
+        .map(|(item, record)| {
+            let mut container: Container = item.into();
+            if let Some(record) = record {
+                container.names = Some(vec![format!("/{}", record.name)]);
+                container.command = Some(record_command(&record).join(" "));
+                container.ports = Some(ports::to_ports(&record.port_mappings));
+            }
+            container
+        })
         .collect();
-    Json(podman_containers)
+    Ok(Json(podman_containers))
 }
 
-pub async fn container_status(container_id: String) -> Result<cri::ContainerStatus, StatusCode> {
+pub async fn container_status(container_id: String) -> Result<cri::ContainerStatus, Error> {
     let request = cri::ContainerStatusRequest {
         container_id,
         verbose: false,
     };
-    let response = get_client()
-        .await
-        .unwrap()
-        .container_status(request)
-        .await
-        .unwrap();
+    let response = get_client().await?.container_status(request).await?;
 
     match response.into_inner().status {
         Some(status) => Ok(status),
-        None => Err(StatusCode::NOT_FOUND),
+        None => Err(StatusCode::NOT_FOUND.into()),
     }
 }
 
+/// The error of operations that need a running container.
+fn not_running(container_id: &str) -> Error {
+    Error::new(
+        StatusCode::CONFLICT,
+        "container state improper",
+        format!("container {container_id} is not running"),
+    )
+}
+
+/// Get the status of a container along with runtime specific information.
+/// CRI-O reports the OCI runtime spec, the PID and the sandbox ID of the container.
+async fn container_status_verbose(
+    container_id: String,
+) -> Result<(cri::ContainerStatus, serde_json::Value), Error> {
+    let request = cri::ContainerStatusRequest {
+        container_id,
+        verbose: true,
+    };
+    let response = get_client()
+        .await?
+        .container_status(request)
+        .await?
+        .into_inner();
+
+    let info = response
+        .info
+        .get("info")
+        .and_then(|info| serde_json::from_str(info).ok())
+        .unwrap_or_default();
+    let status = response.status.ok_or(StatusCode::NOT_FOUND)?;
+    Ok((status, info))
+}
+
 pub async fn container_inspect(
     Path(params): Path<HashMap<String, String>>,
-) -> Result<Json<ContainerJson>, StatusCode> {
-    let name = params.get("name").expect("container id").to_string();
-    let status = container_status(name).await?;
-    let container: ContainerJson = status.into();
+) -> Result<Json<ContainerJson>, Error> {
+    let name = resolve_container(params.get("name").expect("container id")).await?;
+    let details = container_details(name).await?;
+    let container: ContainerJson = details.into();
     Ok(Json(container))
 }
 
-impl From<cri::ContainerStatus> for InspectContainerState {
-    fn from(value: cri::ContainerStatus) -> Self {
+impl From<&ContainerDetails> for InspectContainerState {
+    fn from(value: &ContainerDetails) -> Self {
+        let state = value.status.state();
+
         Self {
-            cgroup_path: None,
-            checkpoint_log: None,
-            checkpoint_path: None,
-            checkpointed: None,
-            checkpointed_at: None,
-            conmon_pid: None,
-            dead: None,
-            error: None,
-            exit_code: None,
-            finished_at: None,
-            health: None,
-            oom_killed: None,
-            oci_version: None,
-            paused: None,
-            pid: None,
-            restarting: None,
-            restore_log: None,
-            restored: None,
-            restored_at: None,
-            running: None,
-            started_at: None,
-            status: None,
-            stopped_by_user: None,
+            dead: Some(false),
+            error: Some(value.status.message.clone()),
+            exit_code: Some(value.status.exit_code),
+            finished_at: nanos_to_datetime(value.status.finished_at),
+            oom_killed: Some(value.status.reason == "OOMKilled"),
+            paused: Some(false),
+            pid: value.pid(),
+            restarting: Some(false),
+            running: Some(state == cri::ContainerState::ContainerRunning),
+            started_at: nanos_to_datetime(value.status.started_at),
+            status: Some(state_to_string(state)),
+            ..Default::default()
         }
     }
 }
 
-impl From<cri::ContainerStatus> for InspectContainerData {
-    fn from(value: cri::ContainerStatus) -> Self {
-        // let state: InspectContainerState = value.clone().into();
+impl From<ContainerDetails> for InspectContainerData {
+    fn from(value: ContainerDetails) -> Self {
+        let settings = ProcessSettings::from(&value);
+        let state = InspectContainerState::from(&value);
+        let name = value.name();
+        let pod = value.info["sandboxID"].as_str().map(str::to_string);
+        let network_settings = InspectNetworkSettings {
+            ports: Some(ports::to_inspect_ports(value.port_mappings())),
+            ..InspectNetworkSettings::new()
+        };
+        let security = value.security();
+        let host_config = InspectContainerHostConfig {
+            cap_add: Some(security.cap_add.clone()),
+            cap_drop: Some(security.cap_drop.clone()),
+            group_add: Some(security.groups.iter().map(i64::to_string).collect()),
+            privileged: Some(security.privileged),
+            readonly_rootfs: Some(security.readonly_rootfs),
+            security_opt: Some(security.security_opt()),
+            ..Default::default()
+        };
+        // the effective values are the ones of the OCI runtime spec
+        let runtime_spec = &value.info["runtimeSpec"];
+        let capabilities = &runtime_spec["process"]["capabilities"];
+        let effective_caps = json_strings(&capabilities["effective"]);
+        let bounding_caps = json_strings(&capabilities["bounding"]);
+        let app_armor_profile = runtime_spec["process"]["apparmorProfile"]
+            .as_str()
+            .map(str::to_string);
+        let process_label = runtime_spec["process"]["selinuxLabel"]
+            .as_str()
+            .map(str::to_string);
+        let mount_label = runtime_spec["linux"]["mountLabel"]
+            .as_str()
+            .map(str::to_string);
+
+        let status = value.status;
+        let metadata = status.metadata.unwrap_or_default();
+        let image = status.image.map(|image| image.image);
+        let mut command = settings.command().into_iter();
 
         Self {
-            id: Some(value.id),
-            image: value.image.map(|image| image.image),
-            app_armor_profile: None,
-            args: None,
-            bounding_caps: None,
-            config: None,
-            conmon_pid_file: None,
-            created: None,
-            dependencies: None,
-            driver: None,
-            effective_caps: None,
-            exec_ids: None,
-            graph_driver: None,
-            host_config: None,
-            hostname_path: None,
-            hosts_path: None,
-            image_digest: None,
-            image_name: None,
-            is_infra: None,
-            is_service: None,
-            kube_exit_code_propagation: None,
-            mount_label: None,
-            mounts: None,
-            name: None,
-            namespace: None,
-            network_settings: None,
-            oci_config_path: None,
-            oci_runtime: None,
-            path: None,
-            pid_file: None,
-            pod: None,
-            process_label: None,
-            resolv_conf_path: None,
-            restart_count: None,
-            rootfs: None,
-            size_root_fs: None,
-            size_rw: None,
-            state: None,
-            static_dir: None,
-            lock_number: None,
+            id: Some(status.id),
+            image: Some(status.image_ref),
+            image_name: image.clone(),
+            name: Some(name),
+            pod,
+            created: nanos_to_datetime(status.created_at),
+            path: command.next(),
+            args: Some(command.collect()),
+            restart_count: Some(metadata.attempt as i32),
+            state: Some(state),
+            network_settings: Some(network_settings),
+            host_config: Some(host_config),
+            effective_caps: Some(effective_caps),
+            bounding_caps: Some(bounding_caps),
+            app_armor_profile,
+            process_label,
+            mount_label,
+            config: Some(InspectContainerConfig {
+                annotations: Some(status.annotations),
+                cmd: Some(settings.cmd),
+                entrypoint: Some(settings.entrypoint),
+                env: Some(settings.env),
+                hostname: Some(settings.hostname),
+                image,
+                labels: Some(status.labels),
+                open_stdin: Some(settings.stdin),
+                tty: Some(settings.tty),
+                user: settings.user,
+                working_dir: Some(settings.working_dir),
+                ..Default::default()
+            }),
+            ..Default::default()
         }
     }
 }
 
 pub async fn container_inspect_libpod(
     Path(params): Path<HashMap<String, String>>,
-) -> Result<Json<InspectContainerData>, StatusCode> {
-    let name = params.get("name").expect("container id").to_string();
-    let status = container_status(name).await?;
-    let container: InspectContainerData = status.into();
+) -> Result<Json<InspectContainerData>, Error> {
+    let name = resolve_container(params.get("name").expect("container id")).await?;
+    let details = container_details(name).await?;
+    let container: InspectContainerData = details.into();
     Ok(Json(container))
 }
 
-async fn start_container(container_id: String) -> Result<(), tonic::Status> {
+async fn start_container(container_id: String) -> Result<(), Error> {
     let client = get_client();
     let request = cri::StartContainerRequest { container_id };
-    client.await.unwrap().start_container(request).await?;
+    client.await?.start_container(request).await?;
     Ok(())
 }
 
-pub async fn container_start(Path(params): Path<HashMap<String, String>>) -> StatusCode {
-    let name = params.get("name").expect("container id").to_string();
-    start_container(name).await.unwrap();
+pub async fn container_start(
+    Path(params): Path<HashMap<String, String>>,
+) -> Result<StatusCode, Error> {
+    let name = resolve_container(params.get("name").expect("container id")).await?;
+    start_container(name).await?;
 
-    StatusCode::NO_CONTENT
+    Ok(StatusCode::NO_CONTENT)
 }
 
-// POST
-pub async fn container_stop() -> StatusCode {
-    StatusCode::NO_CONTENT
+async fn stop_container(container_id: String, timeout: i64) -> Result<(), Error> {
+    let client = get_client();
+    let request = cri::StopContainerRequest {
+        container_id,
+        timeout,
+    };
+    client.await?.stop_container(request).await?;
+    Ok(())
 }
 
-pub async fn container_list_libpod() -> Json<Vec<ListContainer>> {
-    let cri_containers = list_containers(None).await;
-    let podman_containers: Vec<ListContainer> = cri_containers
-        .into_iter()
-        .map(|item: cri::Container| -> ListContainer { item.into() })
-        .collect();
-    Json(podman_containers)
+async fn remove_container(container_id: String) -> Result<(), Error> {
+    let client = get_client();
+    let request = cri::RemoveContainerRequest { container_id };
+    client.await?.remove_container(request).await?;
+    Ok(())
 }
 
-impl From<Mount> for cri::Mount {
-    fn from(value: Mount) -> Self {
-        cri::Mount {
-            host_path: value.source.expect("mount source"),
-            container_path: value.target.expect("mount target"),
-            readonly: value.read_only.unwrap_or(false),
-            ..Default::default()
-        }
-    }
+#[derive(Deserialize)]
+pub struct StopQuery {
+    /// Timeout in seconds, used by the compat API.
+    t: Option<i64>,
+    /// Timeout in seconds, used by the libpod API.
+    timeout: Option<i64>,
 }
 
-impl From<(String, Object)> for cri::Mount {
-    fn from(value: (String, Object)) -> Self {
-        let mut split = value.0.split(":");
-        cri::Mount {
-            host_path: split.next().expect("mount source").to_string(),
-            container_path: split.next().expect("mount target").to_string(),
-            ..Default::default()
-        }
+impl StopQuery {
+    fn timeout(&self) -> i64 {
+        self.timeout.or(self.t).unwrap_or(DEFAULT_STOP_TIMEOUT)
     }
 }
 
-impl From<ImageVolume> for cri::Mount {
-    fn from(value: ImageVolume) -> Self {
-        let image = cri::ImageSpec {
-            image: value.source.expect("mount source"),
-            ..Default::default()
-        };
-
-        cri::Mount {
-            image: Some(image),
-            container_path: value.destination.expect("mount target"),
-            ..Default::default()
-        }
+/// container_stop responds to POST `/containers/:name/stop` and `/libpod/containers/:name/stop`.
+pub async fn container_stop(
+    Path(params): Path<HashMap<String, String>>,
+    Query(query): Query<StopQuery>,
+) -> Result<StatusCode, Error> {
+    let name = resolve_container(params.get("name").expect("container id")).await?;
+    let status = container_status(name).await?;
+    if status.state() != cri::ContainerState::ContainerRunning {
+        return Ok(StatusCode::NOT_MODIFIED);
     }
+
+    stop_container(status.id, query.timeout()).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[derive(Deserialize)]
+pub struct RemoveQuery {
+    #[serde(default, deserialize_with = "query::bool_param")]
+    force: bool,
+    /// Ignore the error if the container doesn't exist, used by the libpod API.
+    #[serde(default, deserialize_with = "query::bool_param")]
+    ignore: bool,
+    /// Timeout in seconds to stop the container when forced, used by the libpod API.
+    timeout: Option<i64>,
+    /// Remove the anonymous volumes of the container.
+    #[serde(default, deserialize_with = "query::bool_param")]
+    v: bool,
 }
 
-impl From<String> for cri::KeyValue {
-    fn from(env: String) -> Self {
-        let (key, value) = env.split_once('=').expect("env key/value delimiter");
-        cri::KeyValue {
-            key: key.to_string(),
-            value: value.to_string(),
+/// Remove a container, stopping it first if `force` is set, and return its ID.
+/// Its anonymous volumes are removed too if `volumes` is set.
+async fn rm_container(
+    name: String,
+    force: bool,
+    timeout: i64,
+    volumes: bool,
+) -> Result<String, Error> {
+    let status = container_status(name).await?;
+    let container_id = status.id.clone();
+
+    if status.state() == cri::ContainerState::ContainerRunning {
+        if !force {
+            return Err(Error::new(
+                StatusCode::CONFLICT,
+                "container state improper",
+                format!("cannot remove container {container_id} as it is running - running or paused containers cannot be removed without force"),
+            ));
         }
+        stop_container(container_id.clone(), timeout).await?;
     }
-}
 
-async fn get_sandbox_config(pod_sandbox_id: String) -> cri::PodSandboxConfig {
-    let filter = cri::PodSandboxFilter {
-        id: pod_sandbox_id,
-        ..Default::default()
-    };
+    let record = store::get_container(&container_id).await;
 
-    let pod_sandbox_list = list_pod_sandbox(Some(filter)).await;
-    let pod_sandbox = pod_sandbox_list.first().expect("pod_sandbox");
+    remove_container(container_id.clone()).await?;
+    store::remove_container(&container_id).await;
 
-    cri::PodSandboxConfig {
-        metadata: pod_sandbox.metadata.clone(),
-        ..Default::default()
+    if let Some(record) = record {
+        record.volumes.remove(volumes).await;
+        if record.auto_pod {
+            remove_auto_pod(record.pod_sandbox_id).await;
+        }
     }
-}
-
-async fn create_container(
-    config: cri::ContainerConfig,
-    pod_sandbox_id: String,
-) -> cri::CreateContainerResponse {
-    let client = get_client();
-
-    // the CRI requires the sandbox config to be passed in the request "for easy reference" :shrug:
-    let sandbox_config = get_sandbox_config(pod_sandbox_id.clone()).await;
 
-    let message = cri::CreateContainerRequest {
-        pod_sandbox_id,
-        config: Some(config),
-        sandbox_config: Some(sandbox_config),
-    };
+    Ok(container_id)
+}
 
-    let request = Request::new(message);
+/// container_delete responds to DELETE `/containers/:name`.
+pub async fn container_delete(
+    Path(params): Path<HashMap<String, String>>,
+    Query(query): Query<RemoveQuery>,
+) -> Result<StatusCode, Error> {
+    let name = resolve_container(params.get("name").expect("container id")).await?;
+    // like Docker, kill the container when forced
+    rm_container(name, query.force, 0, query.v).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
 
-    client
-        .await
-        .unwrap()
-        .create_container(request)
-        .await
-        .unwrap()
-        .into_inner()
+/// container_delete_libpod responds to DELETE `/libpod/containers/:name`.
+pub async fn container_delete_libpod(
+    Path(params): Path<HashMap<String, String>>,
+    Query(query): Query<RemoveQuery>,
+) -> Result<Json<Vec<LibpodContainersRmReport>>, Error> {
+    let name = resolve_container(params.get("name").expect("container id")).await?;
+    let timeout = query.timeout.unwrap_or(DEFAULT_STOP_TIMEOUT);
+
+    match rm_container(name, query.force, timeout, query.v).await {
+        Ok(id) => Ok(Json(vec![LibpodContainersRmReport {
+            err: None,
+            id: Some(id),
+        }])),
+        Err(err) if err.status() == StatusCode::NOT_FOUND && query.ignore => Ok(Json(Vec::new())),
+        Err(err) => Err(err),
+    }
 }
 
-async fn create_container_response(
-    config: cri::ContainerConfig,
-    pod_sandbox_id: String,
-) -> (StatusCode, Json<ContainerCreateResponse>) {
-    let response = create_container(config, pod_sandbox_id).await;
+#[derive(Deserialize)]
+pub struct KillQuery {
+    signal: Option<String>,
+}
 
-    let id = response.container_id;
-    let warnings = Vec::new();
-    let response = ContainerCreateResponse { id, warnings };
+/// Normalize a signal name or number, for example `SIGTERM`, `TERM` or `15`.
+/// Returns `None` for `SIGKILL`.
+fn normalize_signal(signal: &str) -> Result<Option<String>, Error> {
+    let signal = signal.to_uppercase();
+    let signal = signal.strip_prefix("SIG").unwrap_or(&signal);
+    match signal {
+        "KILL" | "9" => Ok(None),
+        _ if signal.chars().all(|c| c.is_ascii_alphanumeric()) => Ok(Some(signal.to_string())),
+        _ => Err(Error::bad_request(format!("invalid signal: {signal}"))),
+    }
+}
 
-    // TODO save the config for future reference,
-    // it's not possible to retrieve it from the CRI
+/// container_kill responds to POST `/containers/:name/kill` and `/libpod/containers/:name/kill`.
+///
+/// The CRI can't send signals: `SIGKILL` stops the container without grace period,
+/// other signals are sent by running `kill` inside the container.
+pub async fn container_kill(
+    Path(params): Path<HashMap<String, String>>,
+    Query(query): Query<KillQuery>,
+) -> Result<StatusCode, Error> {
+    let name = resolve_container(params.get("name").expect("container id")).await?;
+    let signal = normalize_signal(query.signal.as_deref().unwrap_or("SIGKILL"))?;
 
-    (StatusCode::CREATED, Json(response))
-}
+    let status = container_status(name).await?;
+    if status.state() != cri::ContainerState::ContainerRunning {
+        return Err(not_running(&status.id));
+    }
 
-/// Cleans input from Podman Desktop.
-/// Podman Desktop sometimes passes a garbage "sha256:" at the beginning of the image ID.
-fn clean_image(image: String) -> String {
-    match image.split_once(":") {
-        Some((s1, s2)) => {
-            if s1.starts_with("sha") {
-                s2.to_string()
-            } else {
-                image
+    match signal {
+        None => stop_container(status.id, 0).await?,
+        Some(signal) => {
+            let request = cri::ExecSyncRequest {
+                container_id: status.id,
+                cmd: vec!["kill".into(), "-s".into(), signal, "1".into()],
+                timeout: KILL_TIMEOUT,
+            };
+            let response = get_client().await?.exec_sync(request).await?.into_inner();
+            if response.exit_code != 0 {
+                let stderr = String::from_utf8_lossy(&response.stderr);
+                return Err(Error::internal(format!("failed to send signal: {stderr}")));
             }
         }
-        None => image,
     }
-}
-
-impl From<CreateContainerConfig> for cri::ContainerConfig {
-    fn from(value: CreateContainerConfig) -> Self {
-        let metadata = cri::ContainerMetadata {
-            name: value.name.unwrap_or_else(get_random_string),
-            ..Default::default()
-        };
 
-        let image = clean_image(value.image.expect("image"));
+    Ok(StatusCode::NO_CONTENT)
+}
 
-        let image_spec = cri::ImageSpec {
-            image,
-            ..Default::default()
-        };
+fn json_strings(value: &serde_json::Value) -> Vec<String> {
+    value
+        .as_array()
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default()
+}
 
-        cri::ContainerConfig {
-            metadata: Some(metadata),
-            image: Some(image_spec),
-            command: value.entrypoint.unwrap_or_default(),
-            args: value.cmd.unwrap_or_default(),
-            working_dir: value.working_dir.unwrap_or_default(),
-            envs: value
-                .env
-                .unwrap_or_default()
-                .into_iter()
-                .map(|item| -> cri::KeyValue { item.into() })
-                .collect(),
-            labels: value.labels.unwrap_or_default(),
-            mounts: value
-                .volumes
-                .unwrap_or_default()
+/// The CRI can't start an exited container again, so we create it again in the same sandbox,
+/// from the stored create request, or from the status and the runtime spec of the old one.
+/// Returns the ID of the new container.
+async fn recreate_container(details: ContainerDetails) -> Result<String, Error> {
+    let ContainerDetails {
+        status,
+        info,
+        record,
+    } = details;
+
+    let pod_sandbox_id = info["sandboxID"]
+        .as_str()
+        .ok_or_else(|| Error::internal("the runtime doesn't report the sandbox of the container"))?
+        .to_string();
+    let process = &info["runtimeSpec"]["process"];
+
+    let mut metadata = status.metadata.unwrap_or_default();
+    metadata.attempt += 1;
+
+    let mut config = match record.as_ref().map(|record| record.request.clone()) {
+        Some(store::CreateRequest::Libpod(spec)) => cri::ContainerConfig::try_from(*spec)?,
+        Some(store::CreateRequest::Compat(config)) => cri::ContainerConfig::try_from(*config)?,
+        None => cri::ContainerConfig {
+            image: status.image,
+            command: json_strings(&process["args"]),
+            working_dir: process["cwd"].as_str().unwrap_or_default().to_string(),
+            envs: json_strings(&process["env"])
                 .into_iter()
-                .map(|item| -> cri::Mount { item.into() })
+                .filter_map(|env| {
+                    env.split_once('=').map(|(key, value)| cri::KeyValue {
+                        key: key.to_string(),
+                        value: value.to_string(),
+                    })
+                })
                 .collect(),
+            mounts: status.mounts,
+            labels: status.labels,
+            annotations: status.annotations,
+            tty: process["terminal"].as_bool().unwrap_or(false),
             ..Default::default()
+        },
+    };
+    config.metadata = Some(metadata);
+    if let Some(resources) = record.as_ref().and_then(|record| record.resources.clone()) {
+        config.linux.get_or_insert_with(Default::default).resources = Some(resources);
+    }
+
+    // the attempt differs, so the new container can be created before the old one is removed,
+    // which is kept when the create fails
+    let container_id = create_container(config, pod_sandbox_id).await?.container_id;
+    if let Err(err) = remove_container(status.id.clone()).await {
+        if let Err(err) = remove_container(container_id.clone()).await {
+            tracing::error!("failed to remove container {container_id}: {err}");
         }
+        return Err(err);
+    }
+    if let Some(mut record) = record {
+        let previous_id = std::mem::replace(&mut record.id, container_id.clone());
+        record.previous_ids.push(previous_id);
+        store::save_container(&record).await;
     }
+    store::remove_container(&status.id).await;
+    Ok(container_id)
 }
 
-// POST /containers/create
-pub async fn container_create(
-    Json(params): Json<CreateContainerConfig>,
-) -> (StatusCode, Json<ContainerCreateResponse>) {
-    let config: cri::ContainerConfig = params.into();
+/// container_restart responds to POST `/containers/:name/restart` and `/libpod/containers/:name/restart`.
+/// Note that the restarted container gets a new ID, its previous IDs still resolve to it.
+pub async fn container_restart(
+    Path(params): Path<HashMap<String, String>>,
+    Query(query): Query<StopQuery>,
+) -> Result<StatusCode, Error> {
+    let name = resolve_container(params.get("name").expect("container id")).await?;
+    let status = container_status(name).await?;
 
-    let runtime_handler;
-    if config.labels.contains_key("peer-pods-service") {
-        runtime_handler = LOCAL_RUNTIME_HANDLER;
-    } else {
-        runtime_handler = DEFAULT_RUNTIME_HANDLER;
+    if status.state() == cri::ContainerState::ContainerRunning {
+        stop_container(status.id.clone(), query.timeout()).await?;
     }
 
-    let pod_sandbox_id = create_pod_default(runtime_handler).await;
+    let container_id = match status.state() {
+        // a container that never started can simply be started
+        cri::ContainerState::ContainerCreated => status.id,
+        _ => {
+            let details = container_details(status.id).await?;
+            recreate_container(details).await?
+        }
+    };
+    start_container(container_id).await?;
 
-    create_container_response(config, pod_sandbox_id).await
+    Ok(StatusCode::NO_CONTENT)
 }
 
-impl From<podman_api::models::LinuxDevice> for cri::Device {
-    fn from(value: podman_api::models::LinuxDevice) -> Self {
-        let path = value.path.expect("device path");
-        cri::Device {
-            container_path: path.clone(),
-            host_path: path,
-            permissions: "rw".to_string(),
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum WaitCondition {
+    NotRunning,
+    NextExit,
+    Removed,
+    State(cri::ContainerState),
+}
+
+impl std::str::FromStr for WaitCondition {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "not-running" => Ok(Self::NotRunning),
+            "next-exit" => Ok(Self::NextExit),
+            "removed" => Ok(Self::Removed),
+            "configured" | "created" => Ok(Self::State(cri::ContainerState::ContainerCreated)),
+            "running" => Ok(Self::State(cri::ContainerState::ContainerRunning)),
+            "exited" | "stopped" => Ok(Self::State(cri::ContainerState::ContainerExited)),
+            _ => Err(Error::bad_request(format!("invalid condition: {value}"))),
         }
     }
 }
 
-impl From<SpecGenerator> for cri::ContainerConfig {
-    fn from(value: SpecGenerator) -> Self {
-        let metadata = cri::ContainerMetadata {
-            name: value.name.unwrap_or_else(get_random_string),
-            ..Default::default()
-        };
+#[derive(Deserialize)]
+pub struct WaitQuery {
+    condition: Option<String>,
+    /// Time between checks of the container status, used by the libpod API.
+    interval: Option<String>,
+}
 
-        let image = clean_image(value.image.expect("image"));
+/// Wait for the container to meet `condition`, and return its exit code.
+async fn wait_container(
+    name: String,
+    condition: WaitCondition,
+    interval: Duration,
+) -> Result<i64, Error> {
+    let status = container_status(name).await?;
+    let container_id = status.id.clone();
+    let mut exit_code = status.exit_code.into();
+    // the next exit is the first one that finishes after now, even if no poll saw the container run
+    let finished_at = status.finished_at;
+    let mut status = Some(status);
+
+    loop {
+        if let Some(status) = status.take() {
+            let state = status.state();
+            exit_code = status.exit_code.into();
+
+            let done = match condition {
+                WaitCondition::NotRunning => state != cri::ContainerState::ContainerRunning,
+                WaitCondition::NextExit => {
+                    state == cri::ContainerState::ContainerExited
+                        && status.finished_at != finished_at
+                }
+                WaitCondition::Removed => false,
+                WaitCondition::State(wanted) => state == wanted,
+            };
+            if done {
+                return Ok(exit_code);
+            }
+        }
 
-        let image_spec = cri::ImageSpec {
-            image,
-            ..Default::default()
+        tokio::time::sleep(interval).await;
+        status = match container_status(container_id.clone()).await {
+            Ok(status) => Some(status),
+            Err(err)
+                if err.status() == StatusCode::NOT_FOUND && condition == WaitCondition::Removed =>
+            {
+                return Ok(exit_code)
+            }
+            Err(err) => return Err(err),
         };
+    }
+}
 
-        let image_mounts_iter = value
-            .image_volumes
-            .unwrap_or_default()
-            .into_iter()
-            .map(|image_volume| -> cri::Mount { image_volume.into() });
+/// container_wait responds to POST `/containers/:name/wait`.
+pub async fn container_wait(
+    Path(params): Path<HashMap<String, String>>,
+    Query(query): Query<WaitQuery>,
+) -> Result<Json<ContainerWait200Response>, Error> {
+    let name = resolve_container(params.get("name").expect("container id")).await?;
+    let condition = match query.condition.as_deref() {
+        None | Some("") => WaitCondition::NotRunning,
+        Some(condition) => condition.parse()?,
+    };
 
-        let mounts_iter = value
-            .mounts
-            .unwrap_or_default()
-            .into_iter()
-            .map(|item| -> cri::Mount { item.into() });
+    let exit_code = wait_container(name, condition, WAIT_POLL_INTERVAL).await?;
+    Ok(Json(ContainerWait200Response {
+        error: None,
+        status_code: Some(exit_code),
+    }))
+}
 
-        let mounts: Vec<cri::Mount> = mounts_iter.chain(image_mounts_iter).collect();
+/// container_wait_libpod responds to POST `/libpod/containers/:name/wait`.
+pub async fn container_wait_libpod(
+    Path(params): Path<HashMap<String, String>>,
+    Query(query): Query<WaitQuery>,
+) -> Result<Json<i64>, Error> {
+    let name = resolve_container(params.get("name").expect("container id")).await?;
+    let condition = match query.condition.as_deref() {
+        None | Some("") => WaitCondition::State(cri::ContainerState::ContainerExited),
+        Some(condition) => condition.parse()?,
+    };
+    let interval = match query.interval.as_deref() {
+        None | Some("") => WAIT_POLL_INTERVAL,
+        Some(interval) => query::parse_duration(interval)
+            .and_then(|interval| interval.to_std().ok())
+            .ok_or_else(|| Error::bad_request(format!("invalid interval: {interval}")))?,
+    };
 
-        cri::ContainerConfig {
+    let exit_code = wait_container(name, condition, interval).await?;
+    Ok(Json(exit_code))
+}
+
+/// Update the resources of a running container.
+/// The settings that are not set keep their current value.
+/// The resulting resources are saved in the record, for the container to keep them when recreated.
+async fn update_container(container_id: String, resources: Resources) -> Result<(), Error> {
+    let current = container_status(container_id.clone())
+        .await?
+        .resources
+        .and_then(|resources| resources.linux)
+        .unwrap_or_default();
+    let linux = resources.update(current);
+
+    let request = cri::UpdateContainerResourcesRequest {
+        container_id: container_id.clone(),
+        linux: Some(linux.clone()),
+        ..Default::default()
+    };
+    get_client()
+        .await?
+        .update_container_resources(request)
+        .await?;
+
+    if let Some(mut record) = store::get_container(&container_id).await {
+        record.resources = Some(linux);
+        store::save_container(&record).await;
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct UpdateQuery {
+    #[serde(rename = "restartPolicy")]
+    restart_policy: Option<String>,
+}
+
+/// Body of the libpod update: the API documents the resources in `Resources`,
+/// Podman sends them inline.
+#[derive(Deserialize)]
+pub struct UpdateEntities {
+    #[serde(rename = "Resources")]
+    resources: Option<LinuxResources>,
+    #[serde(flatten)]
+    inline: LinuxResources,
+}
+
+const RESTART_POLICY_WARNING: &str = "restart policies are not supported and were ignored";
+
+/// container_update_libpod responds to POST `/libpod/containers/:name/update`.
+pub async fn container_update_libpod(
+    Path(params): Path<HashMap<String, String>>,
+    Query(query): Query<UpdateQuery>,
+    Json(payload): Json<UpdateEntities>,
+) -> Result<Json<ContainerUpdateOkBody>, Error> {
+    let name = resolve_container(params.get("name").expect("container id")).await?;
+    let resources =
+        Resources::from_linux_resources(payload.resources.as_ref().unwrap_or(&payload.inline));
+    let mut warnings = resources.warnings.clone();
+    if query
+        .restart_policy
+        .is_some_and(|policy| !policy.is_empty())
+    {
+        warnings.push(RESTART_POLICY_WARNING.to_string());
+    }
+
+    update_container(name, resources).await?;
+    Ok(Json(ContainerUpdateOkBody { warnings }))
+}
+
+/// container_update responds to POST `/containers/:name/update`.
+/// The body has the resource fields of `HostConfig` and the restart policy.
+pub async fn container_update(
+    Path(params): Path<HashMap<String, String>>,
+    Json(payload): Json<HostConfig>,
+) -> Result<Json<ContainerUpdateOkBody>, Error> {
+    let name = resolve_container(params.get("name").expect("container id")).await?;
+    let resources = Resources::from_host_config(&payload);
+    let mut warnings = resources.warnings.clone();
+    let restart_policy = payload.restart_policy.and_then(|policy| policy.name);
+    if restart_policy.is_some_and(|policy| !policy.is_empty() && policy != "no") {
+        warnings.push(RESTART_POLICY_WARNING.to_string());
+    }
+
+    update_container(name, resources).await?;
+    Ok(Json(ContainerUpdateOkBody { warnings }))
+}
+
+/// container_list_libpod responds to GET `/libpod/containers/json`.
+pub async fn container_list_libpod(
+    Query(query): Query<ListQuery>,
+) -> Result<Json<Vec<ListContainer>>, Error> {
+    let containers = list_containers_with_records(query).await?;
+    let podman_containers: Vec<ListContainer> = containers
+        .into_iter()
+        .map(|(item, record)| {
+            let mut container: ListContainer = item.into();
+            if let Some(record) = record {
+                container.names = Some(vec![record.name.clone()]);
+                container.command = Some(record_command(&record));
+                container.ports = Some(record.port_mappings);
+            }
+            container
+        })
+        .collect();
+    Ok(Json(podman_containers))
+}
+
+async fn get_sandbox_config(pod_sandbox_id: String) -> Result<cri::PodSandboxConfig, Error> {
+    let filter = cri::PodSandboxFilter {
+        id: pod_sandbox_id.clone(),
+        ..Default::default()
+    };
+
+    let pod_sandbox_list = list_pod_sandbox(Some(filter)).await?;
+    let pod_sandbox = pod_sandbox_list
+        .first()
+        .ok_or_else(|| Error::no_such("pod", &pod_sandbox_id))?;
+
+    Ok(cri::PodSandboxConfig {
+        metadata: pod_sandbox.metadata.clone(),
+        ..Default::default()
+    })
+}
+
+async fn create_container(
+    config: cri::ContainerConfig,
+    pod_sandbox_id: String,
+) -> Result<cri::CreateContainerResponse, Error> {
+    let client = get_client();
+
+    // the CRI requires the sandbox config to be passed in the request "for easy reference" :shrug:
+    let sandbox_config = get_sandbox_config(pod_sandbox_id.clone()).await?;
+
+    let mut config = config;
+    let mounts_hosts = config
+        .mounts
+        .iter()
+        .any(|mount| mount.container_path == network::HOSTS_PATH);
+    if !mounts_hosts {
+        if let Some(path) = store::hosts_path(&pod_sandbox_id).await {
+            config.mounts.push(cri::Mount {
+                container_path: network::HOSTS_PATH.to_string(),
+                host_path: path.to_string_lossy().into_owned(),
+                ..Default::default()
+            });
+        }
+    }
+
+    let message = cri::CreateContainerRequest {
+        pod_sandbox_id,
+        config: Some(config),
+        sandbox_config: Some(sandbox_config),
+    };
+
+    let request = Request::new(message);
+
+    let response = client.await?.create_container(request).await?;
+    Ok(response.into_inner())
+}
+
+/// Create the volumes and the container in its sandbox, removing them along with an auto pod
+/// if that fails, and save the record of the container.
+async fn create_container_response(
+    config: cri::ContainerConfig,
+    pod_sandbox_id: String,
+    auto_pod: bool,
+    request: store::CreateRequest,
+    port_mappings: Vec<PortMapping>,
+    mut volumes: ContainerVolumes,
+    mut warnings: Vec<String>,
+) -> Result<(StatusCode, Json<ContainerCreateResponse>), Error> {
+    let name = config.metadata.clone().unwrap_or_default().name;
+    warnings.append(&mut volumes.warnings);
+    let created = async {
+        volumes.create().await?;
+        create_container(config, pod_sandbox_id.clone()).await
+    };
+    let response = match created.await {
+        Ok(response) => response,
+        Err(err) => {
+            volumes.remove(true).await;
+            if auto_pod {
+                discard_pod(pod_sandbox_id).await;
+            }
+            return Err(err);
+        }
+    };
+
+    let id = response.container_id;
+
+    // save the request for future reference, it's not possible to retrieve it from the CRI
+    let record = store::ContainerRecord {
+        id: id.clone(),
+        name,
+        pod_sandbox_id,
+        auto_pod,
+        request,
+        port_mappings,
+        volumes,
+        resources: None,
+        previous_ids: Vec::new(),
+    };
+    store::save_container(&record).await;
+
+    let response = ContainerCreateResponse { id, warnings };
+
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+impl TryFrom<CreateContainerConfig> for cri::ContainerConfig {
+    type Error = Error;
+
+    fn try_from(value: CreateContainerConfig) -> Result<Self, Self::Error> {
+        let resources = value
+            .host_config
+            .as_ref()
+            .map(Resources::from_host_config)
+            .unwrap_or_default();
+        let security = SecurityOptions::from_create_config(&value)?;
+        let mounts = volumes::create_config_mounts(&value)?;
+        let metadata = cri::ContainerMetadata {
+            name: value.name.unwrap_or_else(get_random_string),
+            ..Default::default()
+        };
+
+        let image = value
+            .image
+            .ok_or_else(|| Error::bad_request("no image given"))?;
+        let image = images::clean_image(&image).to_string();
+
+        let image_spec = cri::ImageSpec {
+            image,
+            ..Default::default()
+        };
+
+        Ok(cri::ContainerConfig {
+            metadata: Some(metadata),
+            image: Some(image_spec),
+            command: value.entrypoint.unwrap_or_default(),
+            args: value.cmd.unwrap_or_default(),
+            working_dir: value.working_dir.unwrap_or_default(),
+            envs: env::key_values(value.env.as_deref().unwrap_or_default())?,
+            labels: value.labels.unwrap_or_default(),
+            mounts,
+            tty: value.tty.unwrap_or(false),
+            stdin: value.open_stdin.unwrap_or(false),
+            stdin_once: value.stdin_once.unwrap_or(false),
+            linux: Some(cri::LinuxContainerConfig {
+                resources: resources.linux(),
+                security_context: Some(security.container_context()),
+            }),
+            ..Default::default()
+        })
+    }
+}
+
+// POST /containers/create
+#[derive(Deserialize)]
+pub struct CreateQuery {
+    /// Name of the container, Docker sends it in the query rather than in the body.
+    name: Option<String>,
+    /// Platform of the image, `os/arch[/variant]`.
+    platform: Option<String>,
+}
+
+pub async fn container_create(
+    ConnectInfo(client): ConnectInfo<UdsConnectInfo>,
+    Query(query): Query<CreateQuery>,
+    headers: HeaderMap,
+    Json(mut params): Json<CreateContainerConfig>,
+) -> Result<(StatusCode, Json<ContainerCreateResponse>), Error> {
+    let credentials = Credentials::from_headers(&headers)?;
+    let platform = images::Platform::parse(query.platform.as_deref().unwrap_or_default())?;
+    if let Some(name) = query.name.filter(|name| !name.is_empty()) {
+        params.name = Some(name);
+    }
+    if let Some(name) = &params.name {
+        check_container_name(name).await?;
+    }
+    env::resolve_create_config(&mut params)?;
+    let volumes = ContainerVolumes::prepare_create_config(&mut params)?;
+    let request = store::CreateRequest::Compat(Box::new(params.clone()));
+    let warnings = params
+        .host_config
+        .as_ref()
+        .map(|host_config| Resources::from_host_config(host_config).warnings)
+        .unwrap_or_default();
+    let security = SecurityOptions::from_create_config(&params)?;
+    let userns = UserNamespace::parse(
+        params
+            .host_config
+            .as_ref()
+            .and_then(|host_config| host_config.userns_mode.as_deref())
+            .unwrap_or_default(),
+        &client.peer_cred,
+    )?;
+    let network = NetworkOptions::from_create_config(&params)?;
+    let port_mappings = ports::from_port_bindings(
+        &params
+            .host_config
+            .as_ref()
+            .and_then(|host_config| host_config.port_bindings.clone())
+            .unwrap_or_default(),
+    )?;
+    let mut config = cri::ContainerConfig::try_from(params)?;
+    let image = config.image.take().unwrap_or_default();
+    config.image = Some(
+        images::ensure(
+            &image.image,
+            images::PullPolicy::Missing,
+            &platform,
+            &credentials,
+        )
+        .await?,
+    );
+
+    let runtime_handler = if config.labels.contains_key("peer-pods-service") {
+        LOCAL_RUNTIME_HANDLER
+    } else {
+        DEFAULT_RUNTIME_HANDLER
+    };
+
+    let pod_config = auto_pod_config(port_mappings.clone(), &security, &userns, &network)?;
+    let pod_sandbox_id = create_auto_pod(runtime_handler, pod_config, &network).await?;
+    let port_mappings = port_mappings.iter().map(ports::to_port_mapping).collect();
+
+    create_container_response(
+        config,
+        pod_sandbox_id,
+        true,
+        request,
+        port_mappings,
+        volumes,
+        warnings,
+    )
+    .await
+}
+
+impl TryFrom<podman_api::models::LinuxDevice> for cri::Device {
+    type Error = Error;
+
+    fn try_from(value: podman_api::models::LinuxDevice) -> Result<Self, Self::Error> {
+        let path = value
+            .path
+            .ok_or_else(|| Error::bad_request("device without path"))?;
+        Ok(cri::Device {
+            container_path: path.clone(),
+            host_path: path,
+            permissions: "rw".to_string(),
+        })
+    }
+}
+
+impl TryFrom<SpecGenerator> for cri::ContainerConfig {
+    type Error = Error;
+
+    fn try_from(value: SpecGenerator) -> Result<Self, Self::Error> {
+        let resources = Resources::from_spec(&value);
+        let security = SecurityOptions::from_spec(&value)?;
+        let mounts = volumes::spec_mounts(&value)?;
+        let metadata = cri::ContainerMetadata {
+            name: value.name.unwrap_or_else(get_random_string),
+            ..Default::default()
+        };
+
+        let image = value
+            .image
+            .ok_or_else(|| Error::bad_request("no image given"))?;
+        let image = images::clean_image(&image).to_string();
+
+        let image_spec = cri::ImageSpec {
+            image,
+            ..Default::default()
+        };
+
+        Ok(cri::ContainerConfig {
             metadata: Some(metadata),
             image: Some(image_spec),
             command: value.entrypoint.unwrap_or_default(),
@@ -552,85 +1565,403 @@ impl From<SpecGenerator> for cri::ContainerConfig {
                 .devices
                 .unwrap_or_default()
                 .into_iter()
-                .map(|item| item.into())
-                .collect(),
+                .map(cri::Device::try_from)
+                .collect::<Result<_, _>>()?,
+            linux: Some(cri::LinuxContainerConfig {
+                resources: resources.linux(),
+                security_context: Some(security.container_context()),
+            }),
             ..Default::default()
-        }
+        })
     }
 }
 
+/// The sandbox of a new container.
+enum ContainerSandbox {
+    /// The sandbox of the pod of the container.
+    Pod(String),
+    /// A sandbox to create for the container alone.
+    Auto(Box<cri::PodSandboxConfig>),
+}
+
 // POST /libpod/containers/create
 pub async fn container_create_libpod(
-    Json(params): Json<SpecGenerator>,
-) -> (StatusCode, Json<ContainerCreateResponse>) {
-    let pod_sandbox_id = match &params.pod {
-        Some(pod) => pod.clone(),
-        None => create_pod_default(DEFAULT_RUNTIME_HANDLER).await,
+    ConnectInfo(client): ConnectInfo<UdsConnectInfo>,
+    headers: HeaderMap,
+    Json(mut params): Json<SpecGenerator>,
+) -> Result<(StatusCode, Json<ContainerCreateResponse>), Error> {
+    let credentials = Credentials::from_headers(&headers)?;
+    if let Some(name) = &params.name {
+        check_container_name(name).await?;
+    }
+    env::resolve_spec(&mut params)?;
+    let volumes = ContainerVolumes::prepare_spec(&mut params)?;
+    let requested_ports = params.portmappings.clone().unwrap_or_default();
+    let userns = UserNamespace::from_spec(
+        params.userns.as_ref(),
+        params.idmappings.as_ref(),
+        &client.peer_cred,
+    )?;
+    let network = NetworkOptions::from_spec(&params)?;
+    let pull_policy = images::PullPolicy::parse(params.pull_policy.as_deref())?;
+    let image = params
+        .image
+        .as_deref()
+        .ok_or_else(|| Error::bad_request("no image given"))?;
+    let platform = images::Platform::from_spec(&params);
+    let image = images::ensure(image, pull_policy, &platform, &credentials).await?;
+    let (sandbox, port_mappings) = match &params.pod {
+        // the ports, the user namespace and the name resolution of a pod are those of its sandbox,
+        // set when the pod was created
+        Some(_) if !requested_ports.is_empty() => {
+            return Err(Error::bad_request(
+                "published ports must be defined when the pod is created",
+            ))
+        }
+        Some(_) if !userns.is_host() => {
+            return Err(Error::bad_request(
+                "the user namespace must be defined when the pod is created",
+            ))
+        }
+        Some(_) if !network.is_empty() => {
+            return Err(Error::bad_request(
+                "the hostname, DNS and hosts must be defined when the pod is created",
+            ))
+        }
+        Some(pod) => {
+            let pod_sandbox_id = resolve_pod(pod).await?;
+            let port_mappings = store::get_pod(&pod_sandbox_id)
+                .await
+                .map(|record| record.port_mappings)
+                .unwrap_or_default();
+            (ContainerSandbox::Pod(pod_sandbox_id), port_mappings)
+        }
+        None => {
+            let port_mappings = ports::from_port_mappings(&requested_ports)?;
+            let security = SecurityOptions::from_spec(&params)?;
+            let pod_config = auto_pod_config(port_mappings.clone(), &security, &userns, &network)?;
+            let port_mappings = port_mappings.iter().map(ports::to_port_mapping).collect();
+            (ContainerSandbox::Auto(Box::new(pod_config)), port_mappings)
+        }
+    };
+    let request = store::CreateRequest::Libpod(Box::new(params.clone()));
+    let warnings = Resources::from_spec(&params).warnings;
+    let mut config = cri::ContainerConfig::try_from(params)?;
+    config.image = Some(image);
+
+    // the sandbox is created once the request is known to be valid, so that it isn't left behind
+    let (pod_sandbox_id, auto_pod) = match sandbox {
+        ContainerSandbox::Pod(pod_sandbox_id) => (pod_sandbox_id, false),
+        ContainerSandbox::Auto(pod_config) => {
+            let pod_sandbox_id =
+                create_auto_pod(DEFAULT_RUNTIME_HANDLER, *pod_config, &network).await?;
+            (pod_sandbox_id, true)
+        }
     };
-    let config: cri::ContainerConfig = params.into();
 
-    create_container_response(config, pod_sandbox_id).await
+    create_container_response(
+        config,
+        pod_sandbox_id,
+        auto_pod,
+        request,
+        port_mappings,
+        volumes,
+        warnings,
+    )
+    .await
 }
 
-async fn get_pod_containers(pod_sandbox_id: String) -> Vec<ListPodContainer> {
+async fn get_pod_containers(pod_sandbox_id: String) -> Result<Vec<ListPodContainer>, Error> {
     let filter = cri::ContainerFilter {
         pod_sandbox_id,
         ..Default::default()
     };
-    let containers = list_containers(Some(filter)).await;
-    containers
+    let containers = list_containers(Some(filter)).await?;
+    Ok(containers
         .into_iter()
         .map(|value| -> ListPodContainer { value.into() })
-        .collect()
-}
-
-async fn convert_pod(pod: cri::PodSandbox) -> ListPodsReport {
-    let metadata = pod.metadata.unwrap();
-    let containers = get_pod_containers(pod.id.clone()).await;
-    ListPodsReport {
-        id: Some(pod.id),
-        name: Some(metadata.name.clone()),
-        namespace: Some(metadata.namespace.clone()),
-        status: Some(match cri::PodSandboxState::try_from(pod.state).unwrap() {
-            cri::PodSandboxState::SandboxReady => "Ready".to_string(),
-            cri::PodSandboxState::SandboxNotready => "NotReady".to_string(),
-        }),
+        .collect())
+}
+
+fn pod_state_to_string(state: cri::PodSandboxState) -> String {
+    match state {
+        cri::PodSandboxState::SandboxReady => "Ready",
+        cri::PodSandboxState::SandboxNotready => "NotReady",
+    }
+    .to_string()
+}
+
+async fn convert_pod(pod: cri::PodSandbox) -> Result<ListPodsReport, Error> {
+    let state = pod.state();
+    let metadata = pod.metadata.unwrap_or_default();
+    let containers = get_pod_containers(pod.id.clone()).await?;
+    Ok(ListPodsReport {
+        id: Some(pod.id.clone()),
+        name: Some(metadata.name),
+        namespace: Some(metadata.namespace),
+        status: Some(pod_state_to_string(state)),
         cgroup: None,
         containers: Some(containers),
-        created: None,
-        infra_id: Some(metadata.namespace.clone()),
+        created: nanos_to_datetime(pod.created_at),
+        // the sandbox is the infra container of the pod
+        infra_id: Some(pod.id),
         labels: Some(pod.labels),
         networks: None,
-    }
+    })
 }
 
-async fn list_pod_sandbox(filter: Option<cri::PodSandboxFilter>) -> Vec<cri::PodSandbox> {
+async fn list_pod_sandbox(
+    filter: Option<cri::PodSandboxFilter>,
+) -> Result<Vec<cri::PodSandbox>, Error> {
     let client = get_client();
 
     let request = cri::ListPodSandboxRequest { filter };
-    let response = client
-        .await
-        .unwrap()
-        .list_pod_sandbox(request)
-        .await
-        .unwrap();
+    let response = client.await?.list_pod_sandbox(request).await?;
+
+    Ok(response.into_inner().items)
+}
+
+/// Resolve a pod name, full ID or unique ID prefix to its CRI pod sandbox ID.
+async fn resolve_pod(name_or_id: &str) -> Result<String, Error> {
+    let pods = list_pod_sandbox(None).await?;
+    let candidates = pods.iter().map(|pod| {
+        let name = pod
+            .metadata
+            .as_ref()
+            .map(|metadata| metadata.name.as_str())
+            .unwrap_or_default();
+        (pod.id.as_str(), name)
+    });
+    resolve("pod", name_or_id, candidates)
+}
+
+/// Check that no pod has the name of a new one.
+async fn check_pod_name(name: &str) -> Result<(), Error> {
+    let pods = list_pod_sandbox(None).await?;
+    match pods.iter().find(|pod| {
+        pod.metadata
+            .as_ref()
+            .is_some_and(|metadata| metadata.name == name)
+    }) {
+        Some(pod) => Err(name_in_use("pod", name, &pod.id)),
+        None => Ok(()),
+    }
+}
 
-    response.into_inner().items
+#[derive(Deserialize)]
+pub struct PodListQuery {
+    filters: Option<String>,
 }
 
 /// pod_list_libpod responds to `GET /libpod/pods/json`.
-pub async fn pod_list_libpod() -> Json<Vec<ListPodsReport>> {
-    let cri_pods = list_pod_sandbox(None).await;
-    let pods: Vec<ListPodsReport> = future::join_all(cri_pods.into_iter().map(convert_pod)).await;
+pub async fn pod_list_libpod(
+    Query(query): Query<PodListQuery>,
+) -> Result<Json<Vec<ListPodsReport>>, Error> {
+    let filters = filters::PodFilters::parse(query.filters.as_deref())?;
+
+    let cri_filter = filters.cri_filter();
+    let unfiltered = cri_filter == cri::PodSandboxFilter::default();
+    let listed_at = SystemTime::now();
+    let cri_pods = list_pod_sandbox(Some(cri_filter)).await?;
+
+    if unfiltered {
+        let live: HashSet<String> = cri_pods.iter().map(|pod| pod.id.clone()).collect();
+        store::gc_pods(&live, listed_at).await;
+    }
+
+    let cri_pods = cri_pods.into_iter().filter(|pod| filters.matches(pod));
+    let pods: Vec<ListPodsReport> = future::join_all(cri_pods.map(convert_pod))
+        .await
+        .into_iter()
+        .collect::<Result<_, _>>()?;
+    let pods = pods
+        .into_iter()
+        .filter(|pod| filters.matches_containers(pod.containers.as_deref().unwrap_or_default()))
+        .collect();
+
+    Ok(Json(pods))
+}
+
+async fn pod_status_verbose(
+    pod_sandbox_id: String,
+) -> Result<(cri::PodSandboxStatus, serde_json::Value), Error> {
+    let request = cri::PodSandboxStatusRequest {
+        pod_sandbox_id,
+        verbose: true,
+    };
+    let response = get_client()
+        .await?
+        .pod_sandbox_status(request)
+        .await?
+        .into_inner();
+
+    let info = response
+        .info
+        .get("info")
+        .and_then(|info| serde_json::from_str(info).ok())
+        .unwrap_or_default();
+    let status = response.status.ok_or(StatusCode::NOT_FOUND)?;
+    Ok((status, info))
+}
+
+/// The addresses of a pod, like the network settings of a container.
+fn pod_network_settings(
+    status: &cri::PodSandboxStatus,
+    port_mappings: &[PortMapping],
+) -> InspectNetworkSettings {
+    let network = status.network.clone().unwrap_or_default();
+    let additional_ips = network.additional_ips.into_iter().map(|ip| ip.ip);
+    let (ipv6, ipv4): (Vec<String>, Vec<String>) = std::iter::once(network.ip)
+        .chain(additional_ips)
+        .filter(|ip| !ip.is_empty())
+        .partition(|ip| ip.contains(':'));
+    let addresses = |ips: &[String]| -> Vec<Address> {
+        ips.iter()
+            .map(|ip| Address {
+                addr: Some(ip.clone()),
+                prefix_length: None,
+            })
+            .collect()
+    };
+
+    InspectNetworkSettings {
+        ip_address: ipv4.first().cloned(),
+        global_ipv6_address: ipv6.first().cloned(),
+        secondary_ip_addresses: Some(addresses(ipv4.get(1..).unwrap_or_default())),
+        secondary_ipv6_addresses: Some(addresses(ipv6.get(1..).unwrap_or_default())),
+        ports: Some(ports::to_inspect_ports(port_mappings)),
+        sandbox_id: Some(status.id.clone()),
+        ..InspectNetworkSettings::new()
+    }
+}
+
+/// The namespaces the containers of a pod share, as in Podman's `SharedNamespaces`.
+fn shared_namespaces(options: &cri::NamespaceOption) -> Vec<String> {
+    // the containers of a sandbox always share its UTS namespace
+    let mut shared = vec!["uts".to_string()];
+    let namespaces = [
+        ("net", options.network()),
+        ("ipc", options.ipc()),
+        ("pid", options.pid()),
+    ];
+    for (name, mode) in namespaces {
+        if mode == cri::NamespaceMode::Pod {
+            shared.push(name.to_string());
+        }
+    }
+    if options
+        .userns_options
+        .as_ref()
+        .is_some_and(|userns| userns.mode() == cri::NamespaceMode::Pod)
+    {
+        shared.push("user".to_string());
+    }
+    shared
+}
+
+fn namespace_mode(mode: cri::NamespaceMode) -> String {
+    match mode {
+        cri::NamespaceMode::Node => "host",
+        _ => "private",
+    }
+    .to_string()
+}
+
+/// pod_inspect_libpod responds to GET `/libpod/pods/:name/json`.
+pub async fn pod_inspect_libpod(
+    Path(params): Path<HashMap<String, String>>,
+) -> Result<Json<InspectPodData>, Error> {
+    let name = resolve_pod(params.get("name").expect("pod id")).await?;
+    let (status, info) = pod_status_verbose(name).await?;
+    let record = store::get_pod(&status.id).await;
+    let spec = record
+        .as_ref()
+        .and_then(|record| record.spec.clone())
+        .unwrap_or_else(PodSpecGenerator::new);
+    let port_mappings = record
+        .as_ref()
+        .map(|record| record.port_mappings.as_slice())
+        .unwrap_or_default();
+
+    let filter = cri::ContainerFilter {
+        pod_sandbox_id: status.id.clone(),
+        ..Default::default()
+    };
+    let containers: Vec<InspectPodContainerInfo> = list_containers(Some(filter))
+        .await?
+        .into_iter()
+        .map(|container| InspectPodContainerInfo {
+            state: Some(state_to_string(container.state())),
+            name: container.metadata.map(|metadata| metadata.name),
+            id: Some(container.id),
+        })
+        .collect();
+
+    let namespaces = status
+        .linux
+        .as_ref()
+        .and_then(|linux| linux.namespaces.as_ref())
+        .and_then(|namespaces| namespaces.options.clone())
+        .unwrap_or_default();
+    let runtime_spec = &info["runtimeSpec"];
+    let hostname = runtime_spec["hostname"]
+        .as_str()
+        .map(str::to_string)
+        .or(spec.hostname.clone());
+
+    let infra_config = InspectPodInfraConfig {
+        dns_option: spec.dns_option.clone(),
+        dns_search: spec.dns_search.clone(),
+        dns_server: spec.dns_server.clone(),
+        host_add: spec.hostadd.clone(),
+        host_network: Some(namespaces.network() == cri::NamespaceMode::Node),
+        no_manage_hosts: spec.no_manage_hosts,
+        port_bindings: Some(ports::to_inspect_ports(port_mappings)),
+        pid_ns: Some(namespace_mode(namespaces.pid())),
+        userns: Some(
+            namespaces
+                .userns_options
+                .as_ref()
+                .map(|userns| namespace_mode(userns.mode()))
+                .unwrap_or_else(|| namespace_mode(cri::NamespaceMode::Node)),
+        ),
+        ..InspectPodInfraConfig::new()
+    };
 
-    Json(pods)
+    let metadata = status.metadata.clone().unwrap_or_default();
+    Ok(Json(InspectPodData {
+        id: Some(status.id.clone()),
+        name: Some(
+            record
+                .as_ref()
+                .map(|record| record.name.clone())
+                .unwrap_or(metadata.name),
+        ),
+        namespace: Some(metadata.namespace),
+        created: nanos_to_datetime(status.created_at),
+        state: Some(pod_state_to_string(status.state())),
+        hostname,
+        labels: Some(status.labels.clone()),
+        annotations: Some(status.annotations.clone()),
+        cgroup_path: runtime_spec["linux"]["cgroupsPath"]
+            .as_str()
+            .map(str::to_string),
+        create_infra: Some(true),
+        infra_container_id: Some(status.id.clone()),
+        infra_config: Some(infra_config),
+        shared_namespaces: Some(shared_namespaces(&namespaces)),
+        network_settings: Some(pod_network_settings(&status, port_mappings)),
+        num_containers: Some(i32::try_from(containers.len()).unwrap_or(i32::MAX)),
+        containers: Some(containers),
+        ..InspectPodData::new()
+    }))
 }
 
 fn get_random_string() -> String {
     Uuid::new_v4().to_string().split_at(8).0.to_string()
 }
 
-async fn create_pod(config: cri::PodSandboxConfig, runtime_handler: &str) -> String {
+async fn create_pod(config: cri::PodSandboxConfig, runtime_handler: &str) -> Result<String, Error> {
     let client = get_client();
     let message = cri::RunPodSandboxRequest {
         config: Some(config),
@@ -638,39 +1969,146 @@ async fn create_pod(config: cri::PodSandboxConfig, runtime_handler: &str) -> Str
     };
 
     let request = Request::new(message);
-    let response = client
-        .await
-        .unwrap()
-        .run_pod_sandbox(request)
-        .await
-        .unwrap()
-        .into_inner();
+    let response = client.await?.run_pod_sandbox(request).await?.into_inner();
 
-    response.pod_sandbox_id
+    Ok(response.pod_sandbox_id)
 }
 
-async fn create_pod_default(runtime_handler: &str) -> String {
-    let metadata = cri::PodSandboxMetadata {
-        name: get_random_string(),
+/// Create the sandbox of a container created outside of a pod.
+/// `config` has the sandbox settings the container needs, the metadata and labels are set here.
+async fn create_pod_default(
+    runtime_handler: &str,
+    mut config: cri::PodSandboxConfig,
+) -> Result<String, Error> {
+    let name = get_random_string();
+    if config.hostname.is_empty() {
+        config.hostname = name.clone();
+    }
+    config.metadata = Some(cri::PodSandboxMetadata {
+        name,
         uid: get_random_string(),
         namespace: "default".to_string(),
         attempt: 0,
-    };
+    });
+    config.labels = HashMap::from([(AUTO_POD_LABEL.to_string(), "true".to_string())]);
+    create_pod(config, runtime_handler).await
+}
+
+/// Create the sandbox of a container created outside of a pod, along with its hosts file.
+async fn create_auto_pod(
+    runtime_handler: &str,
+    config: cri::PodSandboxConfig,
+    network: &NetworkOptions,
+) -> Result<String, Error> {
+    let pod_sandbox_id = create_pod_default(runtime_handler, config).await?;
+    if let Err(err) = save_hosts_file(&pod_sandbox_id, network).await {
+        discard_pod(pod_sandbox_id).await;
+        return Err(err);
+    }
+    Ok(pod_sandbox_id)
+}
+
+/// The settings of the sandbox of a container created outside of a pod.
+fn auto_pod_config(
+    port_mappings: Vec<cri::PortMapping>,
+    security: &SecurityOptions,
+    userns: &UserNamespace,
+    network: &NetworkOptions,
+) -> Result<cri::PodSandboxConfig, Error> {
+    if security.privileged && !userns.is_host() {
+        return Err(Error::bad_request(
+            "privileged containers can't run in a user namespace",
+        ));
+    }
 
-    let config = cri::PodSandboxConfig {
-        metadata: Some(metadata),
+    let mut config = cri::PodSandboxConfig {
+        port_mappings,
+        linux: Some(cri::LinuxPodSandboxConfig {
+            security_context: Some(security.sandbox_context()),
+            ..Default::default()
+        }),
         ..Default::default()
     };
-    create_pod(config, runtime_handler).await
+    userns.apply(&mut config);
+    network.apply(&mut config);
+    Ok(config)
+}
+
+/// Save the `/etc/hosts` file of a new pod, when it has extra hosts.
+async fn save_hosts_file(pod_sandbox_id: &str, network: &NetworkOptions) -> Result<(), Error> {
+    if !network.has_hosts() {
+        return Ok(());
+    }
+    // like the kubelet, the file resolves the hostname of the pod to its addresses
+    let (status, info) = pod_status_verbose(pod_sandbox_id.to_string()).await?;
+    let hostname = info["runtimeSpec"]["hostname"].as_str().unwrap_or_default();
+    let pod_network = status.network.unwrap_or_default();
+    let ips: Vec<String> = std::iter::once(pod_network.ip)
+        .chain(pod_network.additional_ips.into_iter().map(|ip| ip.ip))
+        .filter(|ip| !ip.is_empty())
+        .collect();
+    store::save_hosts(pod_sandbox_id, &network.hosts_file(hostname, &ips))
+        .await
+        .map_err(|err| {
+            Error::internal(format!(
+                "cannot write the hosts file of pod {pod_sandbox_id}: {err}"
+            ))
+        })?;
+    Ok(())
+}
+
+async fn remove_pod_sandbox(pod_sandbox_id: String) -> Result<(), Error> {
+    let mut client = get_client().await?;
+    let request = cri::StopPodSandboxRequest {
+        pod_sandbox_id: pod_sandbox_id.clone(),
+    };
+    client.stop_pod_sandbox(request).await?;
+    let request = cri::RemovePodSandboxRequest {
+        pod_sandbox_id: pod_sandbox_id.clone(),
+    };
+    client.remove_pod_sandbox(request).await?;
+    store::remove_pod(&pod_sandbox_id).await;
+    Ok(())
+}
+
+/// Remove the sandbox created implicitly for a container, once its last container is gone.
+async fn remove_auto_pod(pod_sandbox_id: String) {
+    let result = match get_pod_containers(pod_sandbox_id.clone()).await {
+        Ok(containers) if !containers.is_empty() => return,
+        Ok(_) => remove_pod_sandbox(pod_sandbox_id.clone()).await,
+        Err(err) => Err(err),
+    };
+
+    if let Err(err) = result {
+        tracing::error!("failed to remove pod {pod_sandbox_id}: {err}");
+    }
+}
+
+/// Remove a sandbox created for a request that failed afterwards.
+async fn discard_pod(pod_sandbox_id: String) {
+    if let Err(err) = remove_pod_sandbox(pod_sandbox_id.clone()).await {
+        tracing::error!("failed to remove pod {pod_sandbox_id}: {err}");
+    }
 }
 
 /// pod_create_libpod responds to POST `/libpod/pods/create`.
 pub async fn pod_create_libpod(
+    ConnectInfo(client): ConnectInfo<UdsConnectInfo>,
     Json(payload): Json<PodSpecGenerator>,
-) -> (StatusCode, Json<IdResponse>) {
+) -> Result<(StatusCode, Json<IdResponse>), Error> {
+    let spec = payload.clone();
     let name = payload.name.unwrap_or_else(get_random_string);
-
-    let config = cri::PodSandboxConfig {
+    check_pod_name(&name).await?;
+    let port_mappings =
+        ports::from_port_mappings(payload.portmappings.as_deref().unwrap_or_default())?;
+    let userns = UserNamespace::from_spec(
+        payload.userns.as_ref(),
+        payload.idmappings.as_ref(),
+        &client.peer_cred,
+    )?;
+    let network = NetworkOptions::from_pod_spec(&spec)?;
+
+    let mut config = cri::PodSandboxConfig {
         metadata: Some(cri::PodSandboxMetadata {
             name: name.clone(),
             uid: get_random_string(),
@@ -679,21 +2117,38 @@ pub async fn pod_create_libpod(
         }),
         hostname: payload.hostname.unwrap_or(name.clone()),
         log_directory: "/var/log/pods/".to_string(),
-        port_mappings: Vec::new(),
+        port_mappings: port_mappings.clone(),
         labels: payload.labels.unwrap_or_default(),
         annotations: HashMap::new(),
         ..Default::default()
     };
+    userns.apply(&mut config);
+    network.apply(&mut config);
+
+    let id = create_pod(config, DEFAULT_RUNTIME_HANDLER).await?;
+    if let Err(err) = save_hosts_file(&id, &network).await {
+        discard_pod(id).await;
+        return Err(err);
+    }
+
+    let record = store::PodRecord {
+        id: id.clone(),
+        name,
+        spec: Some(spec),
+        port_mappings: port_mappings.iter().map(ports::to_port_mapping).collect(),
+    };
+    store::save_pod(&record).await;
 
-    let id = create_pod(config, DEFAULT_RUNTIME_HANDLER).await;
     let response = IdResponse::new(id);
 
-    (StatusCode::CREATED, Json(response))
+    Ok((StatusCode::CREATED, Json(response)))
 }
 
 /// Start all containers in a pod.
-pub async fn pod_start_libpod(Path(params): Path<HashMap<String, String>>) -> Json<PodStartReport> {
-    let name = params.get("name").expect("container id").to_string();
+pub async fn pod_start_libpod(
+    Path(params): Path<HashMap<String, String>>,
+) -> Result<Json<PodStartReport>, Error> {
+    let name = resolve_pod(params.get("name").expect("pod id")).await?;
     let filter_state = cri::ContainerStateValue {
         state: cri::ContainerState::ContainerCreated.into(),
     };
@@ -704,7 +2159,7 @@ pub async fn pod_start_libpod(Path(params): Path<HashMap<String, String>>) -> Js
         ..Default::default()
     };
 
-    let containers = list_containers(Some(filter)).await;
+    let containers = list_containers(Some(filter)).await?;
 
     let futures = containers
         .into_iter()
@@ -717,7 +2172,7 @@ pub async fn pod_start_libpod(Path(params): Path<HashMap<String, String>>) -> Js
         .into_iter()
         .filter_map(|result| match result {
             Ok(_) => None,
-            Err(status) => Some(status.message().to_string()),
+            Err(err) => Some(err.to_string()),
         })
         .collect();
 
@@ -729,95 +2184,1557 @@ pub async fn pod_start_libpod(Path(params): Path<HashMap<String, String>>) -> Js
 
     // TODO statuscode 409 if error_messages > 0
 
-    Json(report)
+    Ok(Json(report))
 }
 
 /// pod_stop_libpod responds to POST `/libpod/pods/:name/stop`.
-pub async fn pod_stop_libpod(Path(params): Path<HashMap<String, String>>) -> Json<PodStopReport> {
-    let name = params.get("name").expect("container id").to_string();
+pub async fn pod_stop_libpod(
+    Path(params): Path<HashMap<String, String>>,
+) -> Result<Json<PodStopReport>, Error> {
+    let name = resolve_pod(params.get("name").expect("pod id")).await?;
     let client = get_client();
     let request = Request::new(cri::StopPodSandboxRequest {
         pod_sandbox_id: name.clone(),
     });
-    let _response = client
-        .await
-        .unwrap()
-        .stop_pod_sandbox(request)
-        .await
-        .unwrap()
-        .into_inner();
+    client.await?.stop_pod_sandbox(request).await?;
     let report = PodStopReport {
         id: Some(name),
         ..Default::default()
     };
-    Json(report)
+    Ok(Json(report))
 }
 
 /// pod_delete_libpod responds to DELETE `/libpod/pods/:name`.
-pub async fn pod_delete_libpod(Path(params): Path<HashMap<String, String>>) -> Json<PodRmReport> {
-    let name = params.get("name").expect("container id").to_string();
+pub async fn pod_delete_libpod(
+    Path(params): Path<HashMap<String, String>>,
+) -> Result<Json<PodRmReport>, Error> {
+    let name = resolve_pod(params.get("name").expect("pod id")).await?;
+    let containers = get_pod_containers(name.clone()).await?;
     let client = get_client();
     let request = Request::new(cri::RemovePodSandboxRequest {
         pod_sandbox_id: name.clone(),
     });
-    let _response = client
-        .await
-        .unwrap()
-        .remove_pod_sandbox(request)
-        .await
-        .unwrap()
-        .into_inner();
-    let report = PodRmReport {
+    client.await?.remove_pod_sandbox(request).await?;
+    store::remove_pod(&name).await;
+
+    // the runtime removed the containers of the pod along with it
+    for id in containers.into_iter().filter_map(|container| container.id) {
+        if let Some(record) = store::get_container(&id).await {
+            store::remove_container(&id).await;
+            record.volumes.remove(false).await;
+        }
+    }
+    let report = PodRmReport {
         id: Some(name),
         ..Default::default()
     };
-    Json(report)
+    Ok(Json(report))
+}
+
+/// The containers using each volume, according to their records.
+async fn volume_users() -> Result<HashMap<String, Vec<cri::Container>>, Error> {
+    let mut users: HashMap<String, Vec<cri::Container>> = HashMap::new();
+    for container in list_containers(None).await? {
+        let Some(record) = store::get_container(&container.id).await else {
+            continue;
+        };
+        for name in record.volumes.volumes {
+            users.entry(name).or_default().push(container.clone());
+        }
+    }
+    Ok(users)
+}
+
+fn running_count(containers: &[cri::Container]) -> usize {
+    containers
+        .iter()
+        .filter(|container| container.state() == cri::ContainerState::ContainerRunning)
+        .count()
+}
+
+/// List the volumes matching the filters along with the containers using them, sorted by name.
+async fn list_volumes(
+    filters: Option<&str>,
+) -> Result<Vec<(store::VolumeRecord, Vec<cri::Container>)>, Error> {
+    let filters = filters::VolumeFilters::parse(filters)?;
+    let mut users = volume_users().await?;
+
+    let mut volumes: Vec<_> = store::list_volumes()
+        .await
+        .into_iter()
+        .map(|record| {
+            let containers = users.remove(&record.name).unwrap_or_default();
+            (record, containers)
+        })
+        .filter(|(record, containers)| filters.matches(record, !containers.is_empty()))
+        .collect();
+    volumes.sort_by(|(a, _), (b, _)| a.name.cmp(&b.name));
+    Ok(volumes)
+}
+
+/// Remove the unused volumes matching the filters, returns their names with their sizes.
+async fn prune_volumes(filters: Option<&str>) -> Result<Vec<(String, Result<i64, Error>)>, Error> {
+    let mut pruned = Vec::new();
+    for (record, containers) in list_volumes(filters).await? {
+        if !containers.is_empty() {
+            continue;
+        }
+        let size = volumes::size(&record.name).await;
+        let result = volumes::remove(&record.name).await.map(|_| size);
+        pruned.push((record.name, result));
+    }
+    Ok(pruned)
+}
+
+/// Remove a volume. While containers use it, it is an error unless `force` is set,
+/// and then the containers are removed first.
+async fn rm_volume(name: &str, force: bool) -> Result<(), Error> {
+    let record = volumes::get(name).await?;
+    let users = volume_users()
+        .await?
+        .remove(&record.name)
+        .unwrap_or_default();
+
+    if !users.is_empty() && !force {
+        let ids: Vec<_> = users
+            .iter()
+            .map(|container| container.id.as_str())
+            .collect();
+        return Err(Error::new(
+            StatusCode::CONFLICT,
+            "volume is being used",
+            format!(
+                "volume {} is being used by the following container(s): {}: volume is being used",
+                record.name,
+                ids.join(", ")
+            ),
+        ));
+    }
+    for container in users {
+        rm_container(container.id, true, DEFAULT_STOP_TIMEOUT, false).await?;
+    }
+
+    volumes::remove(&record.name).await
+}
+
+#[derive(Deserialize)]
+pub struct VolumeListQuery {
+    filters: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct VolumeRemoveQuery {
+    /// Remove the containers using the volume too.
+    #[serde(default, deserialize_with = "query::bool_param")]
+    force: bool,
+}
+
+/// volume_create_libpod responds to POST `/libpod/volumes/create`.
+pub async fn volume_create_libpod(
+    Json(options): Json<VolumeCreateOptions>,
+) -> Result<(StatusCode, Json<VolumeConfigResponse>), Error> {
+    // `Label` is the legacy name of `Labels`
+    let mut labels = options.label.unwrap_or_default();
+    labels.extend(options.labels.unwrap_or_default());
+    let record = volumes::create(
+        options.name,
+        options.driver,
+        labels,
+        options.options.unwrap_or_default(),
+        options.ignore_if_exists.unwrap_or(false),
+    )
+    .await?;
+    Ok((
+        StatusCode::CREATED,
+        Json(volumes::to_volume_config(record, 0)),
+    ))
+}
+
+/// volume_list_libpod responds to GET `/libpod/volumes/json`.
+pub async fn volume_list_libpod(
+    Query(query): Query<VolumeListQuery>,
+) -> Result<Json<Vec<VolumeConfigResponse>>, Error> {
+    let volumes = list_volumes(query.filters.as_deref())
+        .await?
+        .into_iter()
+        .map(|(record, containers)| volumes::to_volume_config(record, running_count(&containers)))
+        .collect();
+    Ok(Json(volumes))
+}
+
+/// volume_inspect_libpod responds to GET `/libpod/volumes/:name/json`.
+pub async fn volume_inspect_libpod(
+    Path(params): Path<HashMap<String, String>>,
+) -> Result<Json<VolumeConfigResponse>, Error> {
+    let record = volumes::get(params.get("name").expect("volume name")).await?;
+    let users = volume_users()
+        .await?
+        .remove(&record.name)
+        .unwrap_or_default();
+    Ok(Json(volumes::to_volume_config(
+        record,
+        running_count(&users),
+    )))
+}
+
+/// volume_exists_libpod responds to GET `/libpod/volumes/:name/exists`.
+pub async fn volume_exists_libpod(
+    Path(params): Path<HashMap<String, String>>,
+) -> Result<StatusCode, Error> {
+    volumes::get(params.get("name").expect("volume name")).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// volume_delete responds to DELETE `/volumes/:name` and `/libpod/volumes/:name`.
+pub async fn volume_delete(
+    Path(params): Path<HashMap<String, String>>,
+    Query(query): Query<VolumeRemoveQuery>,
+) -> Result<StatusCode, Error> {
+    rm_volume(params.get("name").expect("volume name"), query.force).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// volume_prune_libpod responds to POST `/libpod/volumes/prune`.
+pub async fn volume_prune_libpod(
+    Query(query): Query<VolumeListQuery>,
+) -> Result<Json<Vec<PruneReport>>, Error> {
+    let reports = prune_volumes(query.filters.as_deref())
+        .await?
+        .into_iter()
+        .map(|(name, result)| match result {
+            Ok(size) => PruneReport {
+                err: None,
+                id: Some(name),
+                size: Some(size),
+            },
+            Err(err) => PruneReport {
+                err: Some(err.to_string()),
+                id: Some(name),
+                size: None,
+            },
+        })
+        .collect();
+    Ok(Json(reports))
+}
+
+/// volume_create responds to POST `/volumes/create`.
+pub async fn volume_create(
+    Json(options): Json<VolumeCreate>,
+) -> Result<(StatusCode, Json<Volume>), Error> {
+    // like Docker, creating an existing volume returns it
+    let record = volumes::create(
+        Some(options.name),
+        Some(options.driver),
+        options.labels,
+        options.driver_opts,
+        true,
+    )
+    .await?;
+    Ok((StatusCode::CREATED, Json(volumes::to_volume(record, None))))
+}
+
+/// The volume in the format of the compat API, with its usage.
+/// volume_list responds to GET `/volumes`.
+pub async fn volume_list(
+    Query(query): Query<VolumeListQuery>,
+) -> Result<Json<ListResponse>, Error> {
+    // like Docker, the usage of the volumes is only computed by `system df`
+    let volumes = list_volumes(query.filters.as_deref())
+        .await?
+        .into_iter()
+        .map(|(record, _)| volumes::to_volume(record, None))
+        .collect();
+    Ok(Json(ListResponse {
+        volumes: Some(volumes),
+        warnings: None,
+    }))
+}
+
+/// volume_inspect responds to GET `/volumes/:name`.
+pub async fn volume_inspect(
+    Path(params): Path<HashMap<String, String>>,
+) -> Result<Json<Volume>, Error> {
+    let record = volumes::get(params.get("name").expect("volume name")).await?;
+    Ok(Json(volumes::to_volume(record, None)))
+}
+
+/// volume_prune responds to POST `/volumes/prune`.
+pub async fn volume_prune(
+    Query(query): Query<VolumeListQuery>,
+) -> Result<Json<VolumesPruneReport>, Error> {
+    let mut volumes_deleted = Vec::new();
+    let mut space_reclaimed: i64 = 0;
+    for (name, result) in prune_volumes(query.filters.as_deref()).await? {
+        match result {
+            Ok(size) => {
+                space_reclaimed += size;
+                volumes_deleted.push(name);
+            }
+            Err(err) => tracing::warn!("failed to prune volume {name}: {err}"),
+        }
+    }
+    Ok(Json(VolumesPruneReport {
+        space_reclaimed: Some(space_reclaimed),
+        volumes_deleted: Some(volumes_deleted),
+    }))
+}
+
+/// The ID of the image of a container, without the `sha256:` prefix of some runtimes.
+fn container_image_id(container: &cri::Container) -> String {
+    let id = if container.image_id.is_empty() {
+        &container.image_ref
+    } else {
+        &container.image_id
+    };
+    images::clean_image(id).to_string()
+}
+
+/// The containers using each image, by image ID.
+async fn image_users() -> Result<HashMap<String, Vec<cri::Container>>, Error> {
+    let mut users: HashMap<String, Vec<cri::Container>> = HashMap::new();
+    for container in list_containers(None).await? {
+        users
+            .entry(container_image_id(&container))
+            .or_default()
+            .push(container);
+    }
+    Ok(users)
+}
+
+/// List the images with their configuration and the containers using them.
+async fn list_images() -> Result<Vec<(cri::Image, images::OciImage, Vec<cri::Container>)>, Error> {
+    let mut users = image_users().await?;
+    let list = images::list().await?;
+    let inspected = future::join_all(list.iter().map(|image| images::inspect(&image.id))).await;
+
+    let mut result = Vec::new();
+    for inspect in inspected {
+        match inspect {
+            Ok((image, config)) => {
+                let containers = users.remove(&image.id).unwrap_or_default();
+                result.push((image, config, containers));
+            }
+            // the image was removed since it was listed
+            Err(err) if err.status() == StatusCode::NOT_FOUND => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(result)
+}
+
+/// Remove an image, returns its untagged names and its ID when it is deleted.
+/// While containers use the image, it is an error unless `force` is set,
+/// and then the containers are removed first.
+async fn rm_image(name: &str, force: bool) -> Result<(Vec<String>, Option<String>), Error> {
+    let image = images::find(name).await?;
+    // removing one of several tags only untags the image, the tag is removed by its full name
+    let tag = images::matching_tag(&image, name).filter(|_| image.repo_tags.len() > 1);
+    let untag = tag.is_some();
+    // IDs and their prefixes are removed by full ID
+    let name = match tag {
+        Some(tag) => tag,
+        None if image.id.starts_with(name) => image.id.as_str(),
+        None => name,
+    };
+    if !untag {
+        let users = image_users().await?.remove(&image.id).unwrap_or_default();
+        if !users.is_empty() && !force {
+            let ids: Vec<_> = users
+                .iter()
+                .map(|container| container.id.as_str())
+                .collect();
+            return Err(Error::new(
+                StatusCode::CONFLICT,
+                "image is in use by a container",
+                format!(
+                    "image used by {}: image is in use by a container",
+                    ids.join(", ")
+                ),
+            ));
+        }
+        for container in users {
+            rm_container(container.id, true, DEFAULT_STOP_TIMEOUT, false).await?;
+        }
+    }
+
+    images::remove(name).await?;
+    match images::status(&image.id).await {
+        Ok(remaining) => {
+            let untagged = image
+                .repo_tags
+                .into_iter()
+                .filter(|tag| !remaining.repo_tags.contains(tag))
+                .collect();
+            Ok((untagged, None))
+        }
+        Err(err) if err.status() == StatusCode::NOT_FOUND => Ok((image.repo_tags, Some(image.id))),
+        Err(err) => Err(err),
+    }
+}
+
+/// The error of the image endpoints that the CRI can't serve.
+fn image_unsupported_error() -> Error {
+    Error::new(
+        StatusCode::NOT_IMPLEMENTED,
+        "not implemented",
+        "this image operation is not supported when the images are served by the CRI runtime",
+    )
+}
+
+#[derive(Deserialize)]
+pub struct ImageRemoveQuery {
+    /// Remove the containers using the image too.
+    #[serde(default, deserialize_with = "query::bool_param")]
+    force: bool,
+}
+
+#[derive(Deserialize)]
+pub struct ImagePullQuery {
+    reference: String,
+    /// Only report the ID of the image.
+    #[serde(default, deserialize_with = "query::bool_param")]
+    quiet: bool,
+}
+
+#[derive(Deserialize)]
+pub struct ImageCreateQuery {
+    #[serde(rename = "fromImage")]
+    from_image: Option<String>,
+    tag: Option<String>,
+}
+
+/// image_list_libpod responds to GET `/libpod/images/json`.
+pub async fn image_list_libpod() -> Result<Json<Vec<LibpodImageSummary>>, Error> {
+    let summaries = list_images()
+        .await?
+        .into_iter()
+        .map(|(image, config, containers)| {
+            images::to_summary(image, &config, containers.len() as i64)
+        })
+        .collect();
+    Ok(Json(summaries))
+}
+
+/// image_get_libpod responds to GET `/libpod/images/:name/json` and `/libpod/images/:name/exists`,
+/// the names of images contain slashes.
+pub async fn image_get_libpod(
+    Path(params): Path<HashMap<String, String>>,
+) -> Result<Response, Error> {
+    let path = params.get("name").expect("image path");
+    if let Some(name) = path.strip_suffix("/json") {
+        let image = images::find(name).await?;
+        let (image, config) = images::inspect(&image.id).await?;
+        Ok(Json(images::to_image_data(image, config)).into_response())
+    } else if let Some(name) = path.strip_suffix("/exists") {
+        images::find(name).await?;
+        Ok(StatusCode::NO_CONTENT.into_response())
+    } else {
+        Err(image_unsupported_error())
+    }
+}
+
+/// Pull an image, writing the progress line given at each interval until it completes,
+/// the CRI doesn't report the progress of pulls. Without a line, the client is still checked.
+/// Returns `None` when the client is gone, the pull is cancelled then.
+async fn pull_with_progress<T: serde::Serialize>(
+    writer: &mut tokio::io::DuplexStream,
+    reference: &str,
+    credentials: &Credentials,
+    progress: impl Fn(Duration) -> Option<T>,
+) -> Option<Result<String, Error>> {
+    let pull = images::pull(reference, credentials);
+    tokio::pin!(pull);
+    let start = tokio::time::Instant::now();
+    let mut interval =
+        tokio::time::interval_at(start + PULL_PROGRESS_INTERVAL, PULL_PROGRESS_INTERVAL);
+    loop {
+        tokio::select! {
+            result = &mut pull => return Some(result),
+            _ = interval.tick() => {
+                let written = match progress(start.elapsed()) {
+                    Some(line) => write_json_line(writer, &line).await,
+                    // writing nothing fails once the client is gone
+                    None => writer.write(&[]).await.map(|_| ()),
+                };
+                if written.is_err() {
+                    tracing::info!("cancelling the pull of {reference}, the client is gone");
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+/// image_pull_libpod responds to POST `/libpod/images/pull`,
+/// with a stream of reports ending with the ID of the image or an error.
+pub async fn image_pull_libpod(
+    Query(query): Query<ImagePullQuery>,
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    let credentials = Credentials::from_headers(&headers)?;
+    if query.reference.is_empty() {
+        return Err(Error::bad_request("no reference given"));
+    }
+    let reference = query.reference;
+    let quiet = query.quiet;
+    let stream = |message: String| LibpodImagesPullReport {
+        error: None,
+        id: None,
+        images: None,
+        stream: Some(message),
+    };
+
+    let (mut writer, response) = streaming_response();
+    tokio::spawn(async move {
+        if !quiet {
+            let start = stream(format!("Trying to pull {reference}...\n"));
+            if write_json_line(&mut writer, &start).await.is_err() {
+                return;
+            }
+        }
+        let progress = |elapsed: Duration| {
+            let message = format!("Pulling {reference}: {}s elapsed\n", elapsed.as_secs());
+            (!quiet).then(|| stream(message))
+        };
+        let report = match pull_with_progress(&mut writer, &reference, &credentials, progress).await
+        {
+            None => return,
+            Some(Ok(id)) => LibpodImagesPullReport {
+                error: None,
+                id: Some(id.clone()),
+                images: Some(vec![id]),
+                stream: None,
+            },
+            Some(Err(err)) => LibpodImagesPullReport {
+                error: Some(err.to_string()),
+                id: None,
+                images: None,
+                stream: None,
+            },
+        };
+        let _ = write_json_line(&mut writer, &report).await;
+    });
+    Ok(response)
+}
+
+/// image_delete_libpod responds to DELETE `/libpod/images/:name`.
+pub async fn image_delete_libpod(
+    Path(params): Path<HashMap<String, String>>,
+    Query(query): Query<ImageRemoveQuery>,
+) -> Result<Json<LibpodImagesRemoveReport>, Error> {
+    let name = images::clean_image(params.get("name").expect("image name"));
+    let (untagged, deleted) = rm_image(name, query.force).await?;
+    Ok(Json(LibpodImagesRemoveReport {
+        deleted: Some(deleted.into_iter().collect()),
+        errors: Some(Vec::new()),
+        exit_code: Some(0),
+        untagged: Some(untagged),
+    }))
+}
+
+/// image_list responds to GET `/images/json`.
+pub async fn image_list() -> Result<Json<Vec<LibpodImageSummary>>, Error> {
+    let summaries = list_images()
+        .await?
+        .into_iter()
+        .map(|(image, config, containers)| {
+            let mut summary = images::to_summary(image, &config, containers.len() as i64);
+            summary.id = summary.id.map(|id| format!("sha256:{id}"));
+            summary
+        })
+        .collect();
+    Ok(Json(summaries))
+}
+
+/// image_get responds to GET `/images/:name/json`, the names of images contain slashes.
+pub async fn image_get(
+    Path(params): Path<HashMap<String, String>>,
+) -> Result<Json<ImageInspect>, Error> {
+    let path = params.get("name").expect("image path");
+    let name = path
+        .strip_suffix("/json")
+        .ok_or_else(image_unsupported_error)?;
+    let image = images::find(name).await?;
+    let (image, config) = images::inspect(&image.id).await?;
+    Ok(Json(images::to_image_inspect(image, config)))
+}
+
+/// image_create responds to POST `/images/create`, which pulls `fromImage`,
+/// with a stream of Docker progress lines.
+pub async fn image_create(
+    Query(query): Query<ImageCreateQuery>,
+    headers: HeaderMap,
+) -> Result<Response, Error> {
+    let credentials = Credentials::from_headers(&headers)?;
+    let from_image = query
+        .from_image
+        .filter(|image| !image.is_empty())
+        .ok_or_else(|| Error::bad_request("only pulling images is supported"))?;
+    let reference = match query.tag.filter(|tag| !tag.is_empty()) {
+        Some(tag) if tag.starts_with("sha256:") => format!("{from_image}@{tag}"),
+        Some(tag) => format!("{from_image}:{tag}"),
+        None => from_image,
+    };
+
+    let (mut writer, response) = streaming_response();
+    tokio::spawn(async move {
+        let (repository, tag) = images::split_tag(&reference);
+        let (repository, tag) = (repository.to_string(), tag.to_string());
+        let start = images::Progress {
+            id: Some(tag.clone()),
+            ..images::Progress::status(format!("Pulling from {repository}"))
+        };
+        if write_json_line(&mut writer, &start).await.is_err() {
+            return;
+        }
+        let previous = images::status(&reference).await.ok().map(|image| image.id);
+        let progress = |elapsed: Duration| {
+            Some(images::Progress {
+                id: Some(tag.clone()),
+                progress: Some(format!("{}s elapsed", elapsed.as_secs())),
+                ..images::Progress::status("Downloading".to_string())
+            })
+        };
+        let report = match pull_with_progress(&mut writer, &reference, &credentials, progress).await
+        {
+            None => return,
+            Some(Ok(id)) if previous.as_ref() == Some(&id) => {
+                images::Progress::status(format!("Status: Image is up to date for {reference}"))
+            }
+            Some(Ok(_)) => {
+                images::Progress::status(format!("Status: Downloaded newer image for {reference}"))
+            }
+            Some(Err(err)) => images::Progress::error(err.to_string()),
+        };
+        let _ = write_json_line(&mut writer, &report).await;
+    });
+    Ok(response)
+}
+
+/// image_delete responds to DELETE `/images/:name`.
+pub async fn image_delete(
+    Path(params): Path<HashMap<String, String>>,
+    Query(query): Query<ImageRemoveQuery>,
+) -> Result<Json<Vec<HashMap<&'static str, String>>>, Error> {
+    let name = images::clean_image(params.get("name").expect("image name"));
+    let (untagged, deleted) = rm_image(name, query.force).await?;
+    let mut items: Vec<_> = untagged
+        .into_iter()
+        .map(|tag| HashMap::from([("Untagged", tag)]))
+        .collect();
+    items.extend(deleted.map(|id| HashMap::from([("Deleted", format!("sha256:{id}"))])));
+    Ok(Json(items))
+}
+
+/// image_unsupported responds to the image endpoints that the CRI can't serve.
+pub async fn image_unsupported() -> Error {
+    image_unsupported_error()
+}
+
+/// system_df_libpod responds to GET `/libpod/system/df`.
+pub async fn system_df_libpod() -> Result<Json<SystemDfReport>, Error> {
+    let image_reports = list_images()
+        .await?
+        .iter()
+        .flat_map(|(image, config, containers)| {
+            images::to_df_reports(image, config, containers.len() as i64)
+        })
+        .collect();
+
+    let sizes: HashMap<String, i64> = stats::sample(&[])
+        .await?
+        .iter()
+        .map(|sample| {
+            let used = sample
+                .stats
+                .writable_layer
+                .as_ref()
+                .and_then(|usage| usage.used_bytes.as_ref())
+                .map(|used| i64::try_from(used.value).unwrap_or(i64::MAX))
+                .unwrap_or_default();
+            (sample.id().to_string(), used)
+        })
+        .collect();
+    let mut container_reports = Vec::new();
+    for container in list_containers(None).await? {
+        let record = store::get_container(&container.id).await;
+        let size = sizes.get(&container.id).copied();
+        container_reports.push(SystemDfContainerReport {
+            command: record.as_ref().map(record_command),
+            container_id: Some(container.id.clone()),
+            created: nanos_to_datetime(container.created_at),
+            image: container.image.as_ref().map(|spec| spec.image.clone()),
+            local_volumes: record
+                .as_ref()
+                .map(|record| record.volumes.volumes.len() as i64),
+            names: container
+                .metadata
+                .as_ref()
+                .map(|metadata| metadata.name.clone()),
+            rw_size: size,
+            size,
+            status: Some(state_to_string(container.state())),
+        });
+    }
+
+    let mut volume_reports = Vec::new();
+    for (record, containers) in list_volumes(None).await? {
+        let size = volumes::size(&record.name).await;
+        volume_reports.push(SystemDfVolumeReport {
+            links: Some(containers.len() as i64),
+            reclaimable_size: Some(if containers.is_empty() { size } else { 0 }),
+            size: Some(size),
+            volume_name: Some(record.name),
+        });
+    }
+
+    Ok(Json(SystemDfReport {
+        containers: Some(container_reports),
+        images: Some(image_reports),
+        images_size: Some(images::fs_usage().await?),
+        volumes: Some(volume_reports),
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct EventsQuery {
+    since: Option<String>,
+    until: Option<String>,
+    filters: Option<String>,
+    #[serde(
+        default = "query::default_true",
+        deserialize_with = "query::bool_param"
+    )]
+    stream: bool,
+}
+
+fn parse_event_time(value: Option<String>, name: &str) -> Result<Option<DateTime<Utc>>, Error> {
+    value
+        .filter(|value| !value.is_empty())
+        .map(|value| {
+            query::parse_time(&value)
+                .ok_or_else(|| Error::bad_request(format!("invalid {name} time: {value}")))
+        })
+        .transpose()
+}
+
+async fn events_response(query: EventsQuery, uri: Uri, compat: bool) -> Result<Response, Error> {
+    let options = events::EventOptions {
+        since: parse_event_time(query.since, "since")?,
+        until: parse_event_time(query.until, "until")?,
+        filters: filters::EventFilters::parse(query.filters.as_deref())?,
+        raw_filters: query.filters,
+        stream: query.stream,
+        compat,
+        podman_path: uri.path().to_string(),
+    };
+    let mut receiver = events::subscribe(options).await?;
+
+    let (mut writer, response) = streaming_response();
+    tokio::spawn(async move {
+        // dropping the receiver once the client is gone stops the forwarding of the events
+        let mut check = tokio::time::interval(EVENTS_CLIENT_CHECK_INTERVAL);
+        loop {
+            let written = tokio::select! {
+                event = receiver.recv() => match event {
+                    Some(event) => write_json_line(&mut writer, &event).await,
+                    None => break,
+                },
+                // writing nothing fails once the client is gone
+                _ = check.tick() => writer.write(&[]).await.map(|_| ()),
+            };
+            if written.is_err() {
+                break;
+            }
+        }
+    });
+    Ok(response)
+}
+
+/// events responds to GET `/events`.
+pub async fn events(
+    OriginalUri(uri): OriginalUri,
+    Query(query): Query<EventsQuery>,
+) -> Result<Response, Error> {
+    events_response(query, uri, true).await
+}
+
+/// events_libpod responds to GET `/libpod/events`.
+pub async fn events_libpod(
+    OriginalUri(uri): OriginalUri,
+    Query(query): Query<EventsQuery>,
+) -> Result<Response, Error> {
+    events_response(query, uri, false).await
 }
 
 pub async fn ping() -> StatusCode {
     StatusCode::OK
 }
 
-pub async fn version() -> Json<cri::VersionResponse> {
+pub async fn version() -> Result<Json<cri::VersionResponse>, Error> {
     let client = get_client();
     let request = Request::new(cri::VersionRequest {
         version: "podman-cri".to_string(),
     });
-    let response = client
-        .await
-        .unwrap()
-        .version(request)
-        .await
-        .unwrap()
-        .into_inner();
-    Json(response)
+    let response = client.await?.version(request).await?.into_inner();
+    Ok(Json(response))
+}
+
+/// Check that a container has the `env` binary, which sets the environment of exec processes.
+async fn check_env_binary(container_id: &str) -> Result<(), Error> {
+    let request = cri::ExecSyncRequest {
+        container_id: container_id.to_string(),
+        cmd: vec!["env".to_string()],
+        timeout: ENV_CHECK_TIMEOUT,
+    };
+    let missing = match get_client().await?.exec_sync(request).await {
+        Ok(response) => response.into_inner().exit_code == 127,
+        Err(status) => status.message().contains("not found"),
+    };
+    if missing {
+        return Err(Error::bad_request(
+            "the container has no env binary, which is required to set the environment of exec processes",
+        ));
+    }
+    Ok(())
 }
 
+/// container_exec_libpod responds to POST `/libpod/containers/:name/exec`.
+/// It only records the exec session: the CRI `Exec` call is made when the session starts.
 pub async fn container_exec_libpod(
     Path(path_params): Path<HashMap<String, String>>,
-    post_params: Json<ContainerExecRequest>,
-) -> StatusCode {
-    let container_id = path_params.get("name").expect("container id").to_string();
+    Json(post_params): Json<ContainerExecRequest>,
+) -> Result<(StatusCode, Json<IdResponse>), Error> {
+    let name = resolve_container(path_params.get("name").expect("container id")).await?;
+    let status = container_status(name).await?;
 
-    let request = cri::ExecRequest {
-        container_id,
-        cmd: post_params.cmd.clone().expect("cmd"),
+    let cmd = post_params
+        .cmd
+        .ok_or_else(|| Error::bad_request("no command given"))?;
+    // the CRI runs exec processes as the user and in the working directory of the container
+    if post_params
+        .user
+        .as_deref()
+        .is_some_and(|user| !user.is_empty())
+    {
+        return Err(Error::bad_request(
+            "the user of an exec process is not supported by the CRI runtime",
+        ));
+    }
+    if post_params.privileged.unwrap_or(false) {
+        return Err(Error::bad_request(
+            "privileged exec processes are not supported by the CRI runtime",
+        ));
+    }
+    if post_params
+        .working_dir
+        .as_deref()
+        .is_some_and(|dir| !dir.is_empty())
+    {
+        return Err(Error::bad_request(
+            "the working directory of an exec process is not supported by the CRI runtime",
+        ));
+    }
+    // The CRI has no way to set the environment of an exec process, so we wrap the command.
+    let cmd = match post_params.env {
+        Some(env) if !env.is_empty() => {
+            if status.state() == cri::ContainerState::ContainerRunning {
+                check_env_binary(&status.id).await?;
+            }
+            ["env".to_string()]
+                .into_iter()
+                .chain(env)
+                .chain(cmd)
+                .collect()
+        }
+        _ => cmd,
+    };
+
+    let session = exec::ExecSession {
+        id: exec::new_id(),
+        container_id: status.id,
+        cmd,
         tty: post_params.tty.unwrap_or(false),
-        stdin: post_params.attach_stdin.unwrap_or(true),
+        stdin: post_params.attach_stdin.unwrap_or(false),
         stdout: post_params.attach_stdout.unwrap_or(true),
         stderr: post_params.attach_stderr.unwrap_or(true),
+        detach_keys: post_params.detach_keys,
+        running: false,
+        exit_code: None,
+        resize: None,
+        idle_since: Instant::now(),
     };
+    let id = session.id.clone();
+    exec::insert(session);
 
-    let response = get_client()
-        .await
-        .unwrap()
-        .exec(request)
+    Ok((StatusCode::CREATED, Json(IdResponse::new(id))))
+}
+
+/// exec_start_libpod responds to POST `/libpod/exec/:id/start`.
+/// It calls the CRI `Exec` and relays the streams of the process over the hijacked connection.
+pub async fn exec_start_libpod(
+    Path(params): Path<HashMap<String, String>>,
+    mut request: axum::extract::Request,
+) -> Result<Response, Error> {
+    let id = params.get("id").expect("exec id").to_string();
+    // check and mark the session as running at once, so that it only starts once
+    let session = exec::update(&id, |session| {
+        (!std::mem::replace(&mut session.running, true)).then(|| session.clone())
+    })
+    .ok_or_else(|| Error::no_such("exec session", &id))?
+    .ok_or_else(|| {
+        Error::new(
+            StatusCode::CONFLICT,
+            "exec session state improper",
+            format!("exec session {id} is already running"),
+        )
+    })?;
+
+    let upgrade = streaming::on_upgrade(&mut request);
+    let exec = async {
+        let body = to_bytes(request.into_body(), usize::MAX)
+            .await
+            .map_err(|err| Error::bad_request(err.to_string()))?;
+        let message = cri::ExecRequest {
+            container_id: session.container_id.clone(),
+            cmd: session.cmd.clone(),
+            tty: session.tty,
+            stdin: session.stdin,
+            stdout: session.stdout,
+            // the CRI rejects stderr together with a terminal, which merges both streams anyway
+            stderr: session.stderr && !session.tty,
+        };
+        let url = get_client().await?.exec(message).await?.into_inner().url;
+        Ok::<_, Error>((body, url))
+    };
+    let (body, url) = match exec.await {
+        Ok(exec) => exec,
+        Err(err) => {
+            // the session never ran, it can be started again
+            exec::update(&id, |session| session.running = false);
+            return Err(err);
+        }
+    };
+    // clients may send no body at all
+    let start: Option<ExecStartLibpodRequest> = serde_json::from_slice(&body).ok();
+    let detach = start
+        .as_ref()
+        .and_then(|start| start.detach)
+        .unwrap_or(false);
+
+    let (resize_sender, resize_receiver) = mpsc::channel(8);
+    if let Some((h, w)) = start.and_then(|start| start.h.zip(start.w)) {
+        let _ = resize_sender.try_send(TerminalSize {
+            width: w as u16,
+            height: h as u16,
+        });
+    }
+    exec::update(&id, |session| session.resize = Some(resize_sender));
+
+    let tty = session.tty;
+    let attach_stdin = session.stdin;
+    let relay_session = move |input: Option<ClientReader>, output: ClientWriter| async move {
+        let input = input.filter(|_| attach_stdin);
+        let exit_code =
+            match streaming::relay(&url, tty, input, output, Some(resize_receiver)).await {
+                Ok(exit_code) => exit_code,
+                Err(err) => {
+                    tracing::error!("exec session {id} failed: {err}");
+                    None
+                }
+            };
+        exec::update(&id, |session| {
+            session.running = false;
+            // without an exit code, the session failed, like Podman's own errors
+            session.exit_code = Some(exit_code.unwrap_or(125));
+            session.resize = None;
+            session.idle_since = Instant::now();
+        });
+    };
+
+    if detach {
+        tokio::spawn(relay_session(None, Box::new(tokio::io::sink())));
+        return Ok(StatusCode::OK.into_response());
+    }
+
+    Ok(streaming::hijack(upgrade, tty, relay_session))
+}
+
+/// exec_inspect_libpod responds to GET `/libpod/exec/:id/json`.
+pub async fn exec_inspect_libpod(
+    Path(params): Path<HashMap<String, String>>,
+) -> Result<Json<InspectExecSession>, Error> {
+    let id = params.get("id").expect("exec id");
+    let session = exec::get(id).ok_or_else(|| Error::no_such("exec session", id))?;
+    Ok(Json(session.into()))
+}
+
+#[derive(Deserialize)]
+pub struct ResizeQuery {
+    h: u16,
+    w: u16,
+}
+
+/// exec_resize_libpod responds to POST `/libpod/exec/:id/resize`.
+pub async fn exec_resize_libpod(
+    Path(params): Path<HashMap<String, String>>,
+    Query(query): Query<ResizeQuery>,
+) -> Result<StatusCode, Error> {
+    let id = params.get("id").expect("exec id");
+    let session = exec::get(id).ok_or_else(|| Error::no_such("exec session", id))?;
+    if let Some(resize) = session.resize {
+        let size = TerminalSize {
+            width: query.w,
+            height: query.h,
+        };
+        let _ = resize.send(size).await;
+    }
+    Ok(StatusCode::OK)
+}
+
+/// Runtime specific information about a container, from a verbose `ContainerStatus`.
+async fn container_info(container_id: String) -> Result<serde_json::Value, Error> {
+    let (_, info) = container_status_verbose(container_id).await?;
+    Ok(info)
+}
+
+/// Wait until the container is running: clients usually attach before starting the container.
+/// Returns `false` when the container already exited, possibly between two polls.
+async fn wait_running(container_id: &str, output: &mut ClientWriter) -> Result<bool, Error> {
+    let deadline = tokio::time::Instant::now() + ATTACH_START_TIMEOUT;
+    loop {
+        let status = container_status(container_id.to_string()).await?;
+        match status.state() {
+            cri::ContainerState::ContainerRunning => return Ok(true),
+            cri::ContainerState::ContainerExited => return Ok(false),
+            _ => {}
+        }
+        if tokio::time::Instant::now() > deadline {
+            return Err(Error::new(
+                StatusCode::CONFLICT,
+                "container state improper",
+                format!("container {container_id} was not started in time"),
+            ));
+        }
+        // writing nothing fails once a client reading a response body is gone
+        if output.write(&[]).await.is_err() {
+            return Err(Error::internal("the client is gone"));
+        }
+        tokio::time::sleep(ATTACH_POLL_INTERVAL).await;
+    }
+}
+
+/// Write the output of an exited container, which is only left in its log file.
+async fn replay_logs(
+    container_id: &str,
+    tty: bool,
+    stdout: bool,
+    stderr: bool,
+    output: ClientWriter,
+) -> Result<(), Error> {
+    let status = container_status(container_id.to_string()).await?;
+    let options = logs::LogOptions {
+        stdout,
+        stderr,
+        multiplexed: !tty,
+        ..Default::default()
+    };
+    let running: logs::RunningCheck = Box::new(|| Box::pin(async { false }));
+    logs::write_logs(status.log_path.into(), options, running, output)
         .await
-        .unwrap()
-        .into_inner();
-    let url = response.url;
+        .map_err(|err| Error::internal(err.to_string()))
+}
+
+#[derive(Deserialize)]
+pub struct AttachQuery {
+    #[serde(default, deserialize_with = "query::bool_param")]
+    stdin: bool,
+    #[serde(default, deserialize_with = "query::bool_param")]
+    stdout: bool,
+    #[serde(default, deserialize_with = "query::bool_param")]
+    stderr: bool,
+}
+
+/// container_attach responds to POST `/containers/:name/attach` and `/libpod/containers/:name/attach`.
+/// It calls the CRI `Attach` once the container runs and relays the streams over the hijacked connection.
+pub async fn container_attach(
+    Path(params): Path<HashMap<String, String>>,
+    Query(query): Query<AttachQuery>,
+    mut request: axum::extract::Request,
+) -> Result<Response, Error> {
+    let name = resolve_container(params.get("name").expect("container id")).await?;
+    let status = container_status(name).await?;
+    let container_id = status.id;
+
+    let info = container_info(container_id.clone()).await?;
+    let tty = info["runtimeSpec"]["process"]["terminal"]
+        .as_bool()
+        .unwrap_or(false);
+
+    // like Podman, attach to all the streams when none is selected
+    let all = !(query.stdin || query.stdout || query.stderr);
+    let stdout = query.stdout || all;
+    let stderr = query.stderr || all;
+    let message = cri::AttachRequest {
+        container_id: container_id.clone(),
+        stdin: query.stdin || all,
+        tty,
+        stdout,
+        // the CRI rejects stderr together with a terminal, which merges both streams anyway
+        stderr: stderr && !tty,
+    };
+
+    let (resize_sender, resize_receiver) = mpsc::channel(8);
+    attach::insert(container_id.clone(), resize_sender.clone());
+
+    let upgrade = streaming::on_upgrade(&mut request);
+    let session = move |input: Option<ClientReader>, mut output: ClientWriter| async move {
+        let input = input.filter(|_| message.stdin);
+        let result = async {
+            if !wait_running(&container_id, &mut output).await? {
+                return replay_logs(&container_id, tty, stdout, stderr, output).await;
+            }
+            let url = get_client().await?.attach(message).await?.into_inner().url;
+            streaming::relay(&url, tty, input, output, Some(resize_receiver))
+                .await
+                .map(|_| ())
+                .map_err(|err| Error::internal(err.to_string()))
+        }
+        .await;
+
+        if let Err(err) = result {
+            tracing::error!("attach to container {container_id} failed: {err}");
+        }
+        attach::remove(&container_id, &resize_sender);
+    };
+
+    Ok(streaming::hijack(upgrade, tty, session))
+}
+
+/// container_resize responds to POST `/containers/:name/resize` and `/libpod/containers/:name/resize`.
+pub async fn container_resize(
+    Path(params): Path<HashMap<String, String>>,
+    Query(query): Query<ResizeQuery>,
+) -> Result<StatusCode, Error> {
+    let name = resolve_container(params.get("name").expect("container id")).await?;
+    let status = container_status(name).await?;
+
+    let size = TerminalSize {
+        width: query.w,
+        height: query.h,
+    };
+    if !attach::resize(&status.id, size).await {
+        // Podman ignores resizing of containers without a terminal
+        tracing::debug!("no session attached to container {}", status.id);
+    }
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize)]
+pub struct LogsQuery {
+    #[serde(default, deserialize_with = "query::bool_param")]
+    follow: bool,
+    since: Option<String>,
+    until: Option<String>,
+    tail: Option<String>,
+    #[serde(default, deserialize_with = "query::bool_param")]
+    timestamps: bool,
+    #[serde(default, deserialize_with = "query::bool_param")]
+    stdout: bool,
+    #[serde(default, deserialize_with = "query::bool_param")]
+    stderr: bool,
+}
+
+impl TryFrom<LogsQuery> for logs::LogOptions {
+    type Error = Error;
 
-    StatusCode::CREATED
+    fn try_from(value: LogsQuery) -> Result<Self, Self::Error> {
+        let parse_time = |time: Option<String>| match time.as_deref() {
+            None | Some("") | Some("0") => Ok(None),
+            Some(time) => query::parse_time(time)
+                .map(Some)
+                .ok_or_else(|| Error::bad_request(format!("invalid time: {time}"))),
+        };
+        let tail = match value.tail.as_deref() {
+            None | Some("") | Some("all") | Some("-1") => None,
+            Some(tail) => Some(
+                tail.parse()
+                    .map_err(|_| Error::bad_request(format!("invalid tail: {tail}")))?,
+            ),
+        };
+        // like Podman, show both streams when none is selected
+        let all = !(value.stdout || value.stderr);
+
+        Ok(logs::LogOptions {
+            follow: value.follow,
+            since: parse_time(value.since)?,
+            until: parse_time(value.until)?,
+            tail,
+            timestamps: value.timestamps,
+            stdout: value.stdout || all,
+            stderr: value.stderr || all,
+            multiplexed: true,
+        })
+    }
+}
 
-    // TODO upgrade connection and pipe the `url` websocket to the http stream
+async fn logs_response(name: String, options: logs::LogOptions) -> Result<Response, Error> {
+    let status = container_status(name).await?;
+    let path = std::path::PathBuf::from(status.log_path);
+    if !path.is_file() {
+        return Err(Error::new(
+            StatusCode::NOT_FOUND,
+            "no such file",
+            format!("no log file for container {}", status.id),
+        ));
+    }
+
+    let container_id = status.id;
+    let running: logs::RunningCheck = Box::new(move || {
+        let container_id = container_id.clone();
+        Box::pin(async move {
+            container_status(container_id)
+                .await
+                .is_ok_and(|status| status.state() == cri::ContainerState::ContainerRunning)
+        })
+    });
+
+    let content_type = if options.multiplexed {
+        "application/vnd.docker.multiplexed-stream"
+    } else {
+        "text/plain; charset=utf-8"
+    };
+
+    let (writer, reader) = tokio::io::duplex(64 * 1024);
+    tokio::spawn(async move {
+        if let Err(err) = logs::write_logs(path, options, running, writer).await {
+            tracing::error!("failed to read logs: {err}");
+        }
+    });
+
+    let body = axum::body::Body::from_stream(tokio_util::io::ReaderStream::new(reader));
+    Ok(([(axum::http::header::CONTENT_TYPE, content_type)], body).into_response())
+}
+
+/// container_logs responds to GET `/containers/:name/logs`.
+/// Like Docker, the logs of containers with a terminal are not multiplexed.
+pub async fn container_logs(
+    Path(params): Path<HashMap<String, String>>,
+    Query(query): Query<LogsQuery>,
+) -> Result<Response, Error> {
+    let name = resolve_container(params.get("name").expect("container id")).await?;
+    let mut options: logs::LogOptions = query.try_into()?;
+
+    let info = container_info(name.clone()).await?;
+    options.multiplexed = !info["runtimeSpec"]["process"]["terminal"]
+        .as_bool()
+        .unwrap_or(false);
+
+    logs_response(name, options).await
+}
+
+/// container_logs_libpod responds to GET `/libpod/containers/:name/logs`.
+pub async fn container_logs_libpod(
+    Path(params): Path<HashMap<String, String>>,
+    Query(query): Query<LogsQuery>,
+) -> Result<Response, Error> {
+    let name = resolve_container(params.get("name").expect("container id")).await?;
+    let options: logs::LogOptions = query.try_into()?;
+
+    logs_response(name, options).await
+}
+
+/// Interval between the stats reports of the compat API, like Docker.
+const COMPAT_STATS_INTERVAL: Duration = Duration::from_secs(1);
+/// Default interval between the stats reports of the libpod API, like Podman.
+const LIBPOD_STATS_INTERVAL: u64 = 5;
+
+/// A response streaming the content written to the returned writer.
+fn streaming_response() -> (tokio::io::DuplexStream, Response) {
+    let (writer, reader) = tokio::io::duplex(64 * 1024);
+    let body = axum::body::Body::from_stream(tokio_util::io::ReaderStream::new(reader));
+    (writer, body.into_response())
+}
+
+async fn write_json_line<T: serde::Serialize>(
+    writer: &mut tokio::io::DuplexStream,
+    value: &T,
+) -> std::io::Result<()> {
+    let mut line = serde_json::to_vec(value)?;
+    line.push(b'\n');
+    writer.write_all(&line).await?;
+    writer.flush().await
+}
+
+#[derive(Deserialize)]
+pub struct CompatStatsQuery {
+    #[serde(
+        default = "query::default_true",
+        deserialize_with = "query::bool_param"
+    )]
+    stream: bool,
+    /// Report a single sample, without the previous one.
+    #[serde(default, rename = "one-shot", deserialize_with = "query::bool_param")]
+    one_shot: bool,
+}
+
+/// container_stats responds to GET `/containers/:name/stats`.
+pub async fn container_stats(
+    Path(params): Path<HashMap<String, String>>,
+    Query(query): Query<CompatStatsQuery>,
+) -> Result<Response, Error> {
+    let name = resolve_container(params.get("name").expect("container id")).await?;
+    let ids = vec![name];
+
+    let mut previous = if query.one_shot {
+        None
+    } else {
+        stats::sample(&ids).await?.pop()
+    };
+
+    if !query.stream {
+        if previous.is_some() {
+            // wait for a second sample, to report the CPU usage in between
+            tokio::time::sleep(COMPAT_STATS_INTERVAL).await;
+        }
+        let sample = stats::sample(&ids)
+            .await?
+            .pop()
+            .ok_or_else(|| Error::no_such("container", &ids[0]))?;
+        return Ok(Json(stats::compat_stats(&sample, previous.as_ref())).into_response());
+    }
+
+    let (mut writer, response) = streaming_response();
+    tokio::spawn(async move {
+        loop {
+            let sample = match stats::sample(&ids).await {
+                Ok(mut samples) => match samples.pop() {
+                    Some(sample) => sample,
+                    None => break,
+                },
+                Err(err) => {
+                    tracing::error!("failed to get the stats of container {}: {err}", ids[0]);
+                    break;
+                }
+            };
+            let report = stats::compat_stats(&sample, previous.as_ref());
+            if write_json_line(&mut writer, &report).await.is_err() {
+                break;
+            }
+            previous = Some(sample);
+            tokio::time::sleep(COMPAT_STATS_INTERVAL).await;
+        }
+    });
+    Ok(response)
+}
+
+/// IDs of the running containers.
+async fn running_containers() -> Result<Vec<String>, Error> {
+    let filter = cri::ContainerFilter {
+        state: Some(cri::ContainerStateValue {
+            state: cri::ContainerState::ContainerRunning.into(),
+        }),
+        ..Default::default()
+    };
+    let containers = list_containers(Some(filter)).await?;
+    Ok(containers
+        .into_iter()
+        .map(|container| container.id)
+        .collect())
+}
+
+/// Parse a boolean from a list of query parameters.
+fn bool_pair(value: &str) -> Result<bool, Error> {
+    query::parse_bool(value)
+        .ok_or_else(|| Error::bad_request(format!("invalid boolean value: {value}")))
+}
+
+/// container_stats_libpod responds to GET `/libpod/containers/stats`.
+/// The parameters are read as a list, since `containers` may be repeated.
+pub async fn container_stats_libpod(
+    Query(params): Query<Vec<(String, String)>>,
+) -> Result<Response, Error> {
+    let mut names = Vec::new();
+    let mut stream = true;
+    let mut interval = LIBPOD_STATS_INTERVAL;
+    for (key, value) in params {
+        match key.as_str() {
+            "containers" => names.push(value),
+            "stream" => stream = bool_pair(&value)?,
+            "interval" => {
+                interval = value
+                    .parse()
+                    .ok()
+                    .filter(|interval| *interval > 0)
+                    .ok_or_else(|| Error::bad_request(format!("invalid interval: {value}")))?
+            }
+            _ => {}
+        }
+    }
+
+    // like Podman, report the running containers by default
+    let mut ids = Vec::new();
+    for name in &names {
+        ids.push(resolve_container(name).await?);
+    }
+    let all = ids.is_empty();
+
+    let sample = move |ids: Vec<String>| async move {
+        let ids = if all {
+            running_containers().await?
+        } else {
+            ids
+        };
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        stats::sample(&ids).await
+    };
+
+    if !stream {
+        let samples = sample(ids).await?;
+        let report = stats::StatsReport {
+            error: None,
+            stats: samples
+                .iter()
+                .map(|sample| stats::libpod_stats(sample, None))
+                .collect(),
+        };
+        return Ok(Json(report).into_response());
+    }
+
+    let (mut writer, response) = streaming_response();
+    tokio::spawn(async move {
+        let mut previous: HashMap<String, stats::Sample> = HashMap::new();
+        loop {
+            let report = match sample(ids.clone()).await {
+                Ok(samples) => {
+                    let report = stats::StatsReport {
+                        error: None,
+                        stats: samples
+                            .iter()
+                            .map(|sample| stats::libpod_stats(sample, previous.get(sample.id())))
+                            .collect(),
+                    };
+                    previous = samples
+                        .into_iter()
+                        .map(|sample| (sample.id().to_string(), sample))
+                        .collect();
+                    report
+                }
+                Err(err) => stats::StatsReport {
+                    error: Some(err.to_string()),
+                    stats: Vec::new(),
+                },
+            };
+            if write_json_line(&mut writer, &report).await.is_err() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_secs(interval)).await;
+        }
+    });
+    Ok(response)
+}
+
+/// pod_stats_libpod responds to GET `/libpod/pods/stats`.
+/// The parameters are read as a list, since `namesOrIDs` may be repeated.
+pub async fn pod_stats_libpod(
+    Query(params): Query<Vec<(String, String)>>,
+) -> Result<Json<Vec<PodStatsReport>>, Error> {
+    let mut names = Vec::new();
+    let mut all = false;
+    for (key, value) in params {
+        match key.as_str() {
+            "namesOrIDs" => names.push(value),
+            "all" => all = bool_pair(&value)?,
+            _ => {}
+        }
+    }
+
+    let ids = if all || names.is_empty() {
+        let filter = cri::PodSandboxFilter {
+            state: Some(cri::PodSandboxStateValue {
+                state: cri::PodSandboxState::SandboxReady.into(),
+            }),
+            ..Default::default()
+        };
+        let pods = list_pod_sandbox(Some(filter)).await?;
+        pods.into_iter().map(|pod| pod.id).collect()
+    } else {
+        let mut ids = Vec::new();
+        for name in &names {
+            ids.push(resolve_pod(name).await?);
+        }
+        ids
+    };
+
+    let samples = stats::sample_pods(&ids).await?;
+    Ok(Json(samples.iter().map(stats::pod_stats_report).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CANDIDATES: [(&str, &str); 4] = [
+        ("3f4a9c1e", "web"),
+        ("3f4a77d0", "db"),
+        ("9b21e6aa", "3f4a"),
+        ("c07d5e12", "cache"),
+    ];
+
+    fn resolve_candidate(name_or_id: &str) -> Result<String, Error> {
+        resolve("container", name_or_id, CANDIDATES.into_iter())
+    }
+
+    #[test]
+    fn exact_id_and_name() {
+        assert_eq!(resolve_candidate("3f4a9c1e").unwrap(), "3f4a9c1e");
+        assert_eq!(resolve_candidate("cache").unwrap(), "c07d5e12");
+    }
+
+    #[test]
+    fn leading_slash_of_docker_names() {
+        assert_eq!(resolve_candidate("/web").unwrap(), "3f4a9c1e");
+        assert_eq!(resolve_candidate("/c07d").unwrap(), "c07d5e12");
+    }
+
+    #[test]
+    fn unique_id_prefix() {
+        assert_eq!(resolve_candidate("3f4a7").unwrap(), "3f4a77d0");
+        assert_eq!(resolve_candidate("c").unwrap(), "c07d5e12");
+    }
+
+    #[test]
+    fn name_wins_over_id_prefixes() {
+        assert_eq!(resolve_candidate("3f4a").unwrap(), "9b21e6aa");
+    }
+
+    #[test]
+    fn exact_id_wins_over_names() {
+        let candidates = [("web", "db"), ("1234", "web")];
+        let id = resolve("container", "web", candidates.into_iter()).unwrap();
+        assert_eq!(id, "web");
+    }
+
+    #[test]
+    fn ambiguous_prefix_is_a_conflict() {
+        let err = resolve_candidate("3f").unwrap_err();
+        assert_eq!(err.status(), StatusCode::CONFLICT);
+    }
+
+    #[test]
+    fn duplicate_names_are_a_conflict() {
+        let candidates = [("1234", "web"), ("5678", "web")];
+        let err = resolve("pod", "web", candidates.into_iter()).unwrap_err();
+        assert_eq!(err.status(), StatusCode::CONFLICT);
+    }
+
+    #[test]
+    fn unknown_and_empty_names_are_not_found() {
+        for name_or_id in ["", "/", "bogus", "3f4a9c1e0"] {
+            let err = resolve_candidate(name_or_id).expect_err(name_or_id);
+            assert_eq!(err.status(), StatusCode::NOT_FOUND, "{name_or_id}");
+        }
+    }
 }
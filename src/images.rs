@@ -0,0 +1,596 @@
+//! Images served by the image service of the CRI runtime.
+//!
+//! By default the image endpoints are forwarded to Podman, which only works when Podman
+//! and the CRI runtime share their storage. With `PODMAN_CRI_IMAGES=cri`, images are listed,
+//! inspected, pulled and removed through the CRI instead. The CRI only tells the ID, the names,
+//! and the size of an image, the rest comes from the OCI configuration in its verbose status.
+//!
+//! In both modes, containers are created from images of the CRI runtime, which are pulled
+//! when needed according to the pull policy of the request.
+
+use std::collections::HashMap;
+
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use podman_api::{
+    models::{
+        Config, History, ImageConfig, ImageData, ImageInspect, LibpodImageSummary, RootFs,
+        SpecGenerator, SystemDfImageReport,
+    },
+    types::Object,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::auth::Credentials;
+use crate::cri;
+use crate::cri_clients::get_image_client;
+use crate::error::Error;
+
+/// Whether the images are served by the CRI runtime instead of Podman.
+pub fn native() -> bool {
+    std::env::var("PODMAN_CRI_IMAGES").is_ok_and(|mode| mode == "cri")
+}
+
+/// The OCI configuration of an image.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct OciImage {
+    created: Option<DateTime<Utc>>,
+    author: Option<String>,
+    architecture: Option<String>,
+    os: Option<String>,
+    variant: Option<String>,
+    config: Option<OciConfig>,
+    rootfs: Option<OciRootFs>,
+    history: Option<Vec<OciHistory>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+struct OciConfig {
+    user: Option<String>,
+    exposed_ports: Option<HashMap<String, Object>>,
+    env: Option<Vec<String>>,
+    entrypoint: Option<Vec<String>>,
+    cmd: Option<Vec<String>>,
+    volumes: Option<HashMap<String, Object>>,
+    working_dir: Option<String>,
+    labels: Option<HashMap<String, String>>,
+    stop_signal: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct OciRootFs {
+    r#type: Option<String>,
+    diff_ids: Option<Vec<String>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct OciHistory {
+    created: Option<DateTime<Utc>>,
+    created_by: Option<String>,
+    author: Option<String>,
+    comment: Option<String>,
+    empty_layer: Option<bool>,
+}
+
+/// The verbose information of an image status, as reported by CRI-O and containerd.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct ImageInfo {
+    image_spec: OciImage,
+}
+
+/// A line of the progress of a compat pull.
+#[derive(Debug, Default, Serialize)]
+pub struct Progress {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub progress: Option<String>,
+    #[serde(rename = "errorDetail", skip_serializing_if = "Option::is_none")]
+    pub error_detail: Option<ErrorDetail>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ErrorDetail {
+    pub message: String,
+}
+
+impl Progress {
+    pub fn status(status: String) -> Self {
+        Progress {
+            status: Some(status),
+            ..Default::default()
+        }
+    }
+
+    pub fn error(message: String) -> Self {
+        Progress {
+            error_detail: Some(ErrorDetail {
+                message: message.clone(),
+            }),
+            error: Some(message),
+            ..Default::default()
+        }
+    }
+}
+
+/// When to pull the image of a container, see Podman's `--pull`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PullPolicy {
+    Always,
+    /// Pull the image only when it is missing.
+    #[default]
+    Missing,
+    /// Never pull the image, it must be present.
+    Never,
+    /// Pull the image when the registry has a newer one, use the present one if it can't be checked.
+    Newer,
+}
+
+impl PullPolicy {
+    /// The policy with Podman's names and aliases.
+    pub fn parse(policy: Option<&str>) -> Result<Self, Error> {
+        let policy = policy.unwrap_or_default();
+        match policy.to_lowercase().as_str() {
+            "always" => Ok(PullPolicy::Always),
+            "" | "missing" | "ifmissing" | "ifnotpresent" => Ok(PullPolicy::Missing),
+            "never" => Ok(PullPolicy::Never),
+            "newer" | "ifnewer" => Ok(PullPolicy::Newer),
+            _ => Err(Error::bad_request(format!(
+                "unsupported pull policy \"{policy}\""
+            ))),
+        }
+    }
+}
+
+/// The platform of an image, `os/arch[/variant]`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Platform {
+    pub os: Option<String>,
+    pub arch: Option<String>,
+    pub variant: Option<String>,
+}
+
+impl Platform {
+    /// The platform of a libpod create request.
+    pub fn from_spec(spec: &SpecGenerator) -> Self {
+        let non_empty = |value: &Option<String>| value.clone().filter(|value| !value.is_empty());
+        Platform {
+            os: non_empty(&spec.image_os),
+            arch: non_empty(&spec.image_arch),
+            variant: non_empty(&spec.image_variant),
+        }
+    }
+
+    /// The platform of a compat `platform` query parameter.
+    pub fn parse(platform: &str) -> Result<Self, Error> {
+        let non_empty = |value: &str| (!value.is_empty()).then(|| value.to_string());
+        let mut parts = platform.split('/');
+        let result = Platform {
+            os: parts.next().and_then(non_empty),
+            arch: parts.next().and_then(non_empty),
+            variant: parts.next().and_then(non_empty),
+        };
+        if parts.next().is_some() {
+            return Err(Error::bad_request(format!(
+                "invalid platform \"{platform}\""
+            )));
+        }
+        Ok(result)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Platform::default()
+    }
+
+    /// Whether an image is for this platform, unknown values match.
+    fn matches(&self, config: &OciImage) -> bool {
+        let matches = |wanted: &Option<String>, actual: &Option<String>| match (wanted, actual) {
+            (Some(wanted), Some(actual)) => wanted == actual,
+            _ => true,
+        };
+        matches(&self.os, &config.os)
+            && matches(&self.arch, &config.architecture)
+            && matches(&self.variant, &config.variant)
+    }
+}
+
+impl std::fmt::Display for Platform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts: Vec<&str> = [&self.os, &self.arch, &self.variant]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect();
+        f.write_str(&parts.join("/"))
+    }
+}
+
+/// Cleans input from Podman Desktop.
+/// Podman Desktop sometimes passes a garbage "sha256:" at the beginning of the image ID.
+pub fn clean_image(image: &str) -> &str {
+    image.strip_prefix("sha256:").unwrap_or(image)
+}
+
+fn spec(name: &str) -> cri::ImageSpec {
+    cri::ImageSpec {
+        image: name.to_string(),
+        ..Default::default()
+    }
+}
+
+pub async fn list() -> Result<Vec<cri::Image>, Error> {
+    let request = cri::ListImagesRequest { filter: None };
+    let response = get_image_client().await?.list_images(request).await?;
+    Ok(response.into_inner().images)
+}
+
+/// The image matching a name or an ID.
+pub async fn status(name: &str) -> Result<cri::Image, Error> {
+    let request = cri::ImageStatusRequest {
+        image: Some(spec(name)),
+        verbose: false,
+    };
+    let response = get_image_client().await?.image_status(request).await?;
+    response
+        .into_inner()
+        .image
+        .ok_or_else(|| Error::no_such("image", name))
+}
+
+/// The image matching a name, an ID or a unique ID prefix.
+/// Runtimes don't all resolve ID prefixes, so they are looked up in the list of images.
+pub async fn find(name: &str) -> Result<cri::Image, Error> {
+    let name = clean_image(name);
+    let err = match status(name).await {
+        Ok(image) => return Ok(image),
+        Err(err) => err,
+    };
+    let is_id_prefix = !name.is_empty() && name.chars().all(|c| c.is_ascii_hexdigit());
+    if err.status() != StatusCode::NOT_FOUND || !is_id_prefix {
+        return Err(err);
+    }
+
+    let mut matches: Vec<cri::Image> = list()
+        .await?
+        .into_iter()
+        .filter(|image| image.id.starts_with(name))
+        .collect();
+    match matches.len() {
+        0 => Err(err),
+        1 => Ok(matches.remove(0)),
+        _ => Err(Error::new(
+            StatusCode::CONFLICT,
+            "more than one result for image name or ID",
+            format!("more than one result for image name or ID \"{name}\""),
+        )),
+    }
+}
+
+/// The image matching a name or an ID, with its OCI configuration.
+pub async fn inspect(name: &str) -> Result<(cri::Image, OciImage), Error> {
+    let request = cri::ImageStatusRequest {
+        image: Some(spec(name)),
+        verbose: true,
+    };
+    let response = get_image_client()
+        .await?
+        .image_status(request)
+        .await?
+        .into_inner();
+    let image = response
+        .image
+        .ok_or_else(|| Error::no_such("image", name))?;
+
+    // the runtime may not report the configuration, the image is still usable without it
+    let config = match response.info.get("info") {
+        Some(info) => match serde_json::from_str::<ImageInfo>(info) {
+            Ok(info) => info.image_spec,
+            Err(err) => {
+                tracing::warn!(
+                    "ignoring invalid configuration of image {}: {err}",
+                    image.id
+                );
+                OciImage::default()
+            }
+        },
+        None => OciImage::default(),
+    };
+    Ok((image, config))
+}
+
+/// Pull an image with the credentials of its registry, returns its ID.
+pub async fn pull(reference: &str, credentials: &Credentials) -> Result<String, Error> {
+    let request = cri::PullImageRequest {
+        image: Some(spec(reference)),
+        auth: credentials.for_reference(reference),
+        sandbox_config: None,
+    };
+    get_image_client().await?.pull_image(request).await?;
+    // the image reference of the response is a digested name for CRI-O, not the ID
+    Ok(status(reference).await?.id)
+}
+
+/// Make sure that the image of a container is present, pulling it according to the policy,
+/// and that it is for the requested platform. Returns the image to create the container with.
+pub async fn ensure(
+    name: &str,
+    policy: PullPolicy,
+    platform: &Platform,
+    credentials: &Credentials,
+) -> Result<cri::ImageSpec, Error> {
+    let name = clean_image(name);
+    let present = match find(name).await {
+        Ok(image) => Some(image),
+        Err(err) if err.status() == StatusCode::NOT_FOUND => None,
+        Err(err) => return Err(err),
+    };
+
+    let image = match (policy, present) {
+        (PullPolicy::Missing | PullPolicy::Never, Some(image)) => image,
+        (PullPolicy::Never, None) => return Err(Error::no_such("image", name)),
+        (PullPolicy::Newer, Some(image)) => match pull(name, credentials).await {
+            Ok(id) => status(&id).await?,
+            Err(err) => {
+                tracing::warn!(
+                    "using the present image {name}, failed to check for a newer one: {err}"
+                );
+                image
+            }
+        },
+        (PullPolicy::Always | PullPolicy::Missing | PullPolicy::Newer, _) => {
+            tracing::info!("pulling image {name}");
+            status(&pull(name, credentials).await?).await?
+        }
+    };
+
+    // the runtime pulls images for its own platform, it can't pull the one of another platform
+    if !platform.is_empty() {
+        let (_, config) = inspect(&image.id).await?;
+        if !platform.matches(&config) {
+            let actual = Platform {
+                os: config.os,
+                arch: config.architecture,
+                variant: config.variant,
+            };
+            return Err(Error::bad_request(format!(
+                "image {name} is for the {actual} platform, not for the requested {platform} platform"
+            )));
+        }
+    }
+
+    // names are kept for display, IDs and their prefixes are resolved
+    let image = if image.id.starts_with(name) {
+        image.id
+    } else {
+        name.to_string()
+    };
+    Ok(spec(&image))
+}
+
+pub async fn remove(name: &str) -> Result<(), Error> {
+    let request = cri::RemoveImageRequest {
+        image: Some(spec(name)),
+    };
+    get_image_client().await?.remove_image(request).await?;
+    Ok(())
+}
+
+/// The space used by the images, according to the image filesystems of the runtime.
+pub async fn fs_usage() -> Result<i64, Error> {
+    let response = get_image_client()
+        .await?
+        .image_fs_info(cri::ImageFsInfoRequest {})
+        .await?
+        .into_inner();
+    let used = response
+        .image_filesystems
+        .iter()
+        .filter_map(|usage| usage.used_bytes.as_ref())
+        .map(|used| used.value)
+        .sum::<u64>();
+    Ok(i64::try_from(used).unwrap_or(i64::MAX))
+}
+
+/// Split a tagged name into its repository and its tag.
+pub fn split_tag(name: &str) -> (&str, &str) {
+    match name.rsplit_once(':') {
+        // a colon before the last slash separates the port of the registry
+        Some((repository, tag)) if !tag.contains('/') => (repository, tag),
+        _ => (name, "latest"),
+    }
+}
+
+/// The tag of an image that a name refers to, the name may be short or have no tag.
+/// Runtimes report fully qualified tags, the image was resolved from the name by the runtime.
+pub fn matching_tag<'a>(image: &'a cri::Image, name: &str) -> Option<&'a str> {
+    // digests and IDs aren't tags
+    if name.contains('@') || image.id.starts_with(name) {
+        return None;
+    }
+    let (repository, tag) = split_tag(name);
+    let tagged = format!("{repository}:{tag}");
+    if let Some(tag) = image.repo_tags.iter().find(|tag| **tag == tagged) {
+        return Some(tag);
+    }
+    let suffix = format!("/{tagged}");
+    let mut tags = image.repo_tags.iter().filter(|tag| tag.ends_with(&suffix));
+    match (tags.next(), tags.next()) {
+        (Some(tag), None) => Some(tag),
+        _ => None,
+    }
+}
+
+fn size(image: &cri::Image) -> i64 {
+    i64::try_from(image.size).unwrap_or(i64::MAX)
+}
+
+/// The digest of an image, from its first digested name.
+fn digest(image: &cri::Image) -> Option<String> {
+    image
+        .repo_digests
+        .first()
+        .and_then(|name| name.split_once('@'))
+        .map(|(_, digest)| digest.to_string())
+}
+
+fn labels(config: &OciImage) -> HashMap<String, String> {
+    config
+        .config
+        .as_ref()
+        .and_then(|config| config.labels.clone())
+        .unwrap_or_default()
+}
+
+/// The image in the format of the image lists, with the number of containers using it.
+pub fn to_summary(image: cri::Image, config: &OciImage, containers: i64) -> LibpodImageSummary {
+    let size = size(&image);
+    LibpodImageSummary {
+        arch: config.architecture.clone(),
+        containers: Some(containers),
+        created: config.created.map(|created| created.timestamp()),
+        dangling: Some(image.repo_tags.is_empty()),
+        digest: digest(&image),
+        id: Some(image.id),
+        labels: Some(labels(config)),
+        names: Some(image.repo_tags.clone()),
+        os: config.os.clone(),
+        read_only: Some(false),
+        repo_digests: Some(image.repo_digests),
+        repo_tags: Some(image.repo_tags),
+        shared_size: Some(0),
+        size: Some(size),
+        virtual_size: Some(size),
+        ..Default::default()
+    }
+}
+
+fn history(config: &OciImage) -> Vec<History> {
+    config
+        .history
+        .iter()
+        .flatten()
+        .map(|history| History {
+            author: history.author.clone(),
+            comment: history.comment.clone(),
+            created: history.created,
+            created_by: history.created_by.clone(),
+            empty_layer: history.empty_layer,
+        })
+        .collect()
+}
+
+fn root_fs(config: &OciImage) -> Option<RootFs> {
+    config.rootfs.as_ref().map(|rootfs| RootFs {
+        layers: rootfs
+            .diff_ids
+            .clone()
+            .map(|layers| layers.into_iter().map(Into::into).collect()),
+        r#type: rootfs.r#type.clone(),
+    })
+}
+
+/// The image in the format of the libpod inspect.
+pub fn to_image_data(image: cri::Image, config: OciImage) -> ImageData {
+    let size = size(&image);
+    let oci_config = config.config.as_ref();
+    ImageData {
+        architecture: config.architecture.clone(),
+        author: config.author.clone(),
+        config: oci_config.map(|oci| ImageConfig {
+            cmd: oci.cmd.clone(),
+            entrypoint: oci.entrypoint.clone(),
+            env: oci.env.clone(),
+            exposed_ports: oci.exposed_ports.clone(),
+            labels: oci.labels.clone(),
+            stop_signal: oci.stop_signal.clone(),
+            user: oci.user.clone(),
+            volumes: oci.volumes.clone(),
+            working_dir: oci.working_dir.clone(),
+            ..Default::default()
+        }),
+        created: config.created,
+        digest: digest(&image),
+        history: Some(history(&config)),
+        id: Some(image.id),
+        labels: Some(labels(&config)),
+        names_history: Some(Vec::new()),
+        os: config.os.clone(),
+        repo_digests: Some(image.repo_digests),
+        repo_tags: Some(image.repo_tags),
+        root_fs: root_fs(&config),
+        size: Some(size),
+        user: oci_config.and_then(|oci| oci.user.clone()),
+        virtual_size: Some(size),
+        ..Default::default()
+    }
+}
+
+/// The image in the format of the compat inspect, with a `sha256:` ID like Docker.
+pub fn to_image_inspect(image: cri::Image, config: OciImage) -> ImageInspect {
+    let size = size(&image);
+    let oci_config = config.config.as_ref().map(|oci| Config {
+        cmd: oci.cmd.clone(),
+        entrypoint: oci.entrypoint.clone(),
+        env: oci.env.clone(),
+        exposed_ports: oci.exposed_ports.clone(),
+        labels: oci.labels.clone(),
+        stop_signal: oci.stop_signal.clone(),
+        user: oci.user.clone(),
+        volumes: oci.volumes.clone(),
+        working_dir: oci.working_dir.clone(),
+        ..Default::default()
+    });
+    ImageInspect {
+        architecture: config.architecture.clone(),
+        author: config.author.clone(),
+        config: oci_config,
+        created: config.created.map(|created| created.to_rfc3339()),
+        id: Some(format!("sha256:{}", image.id)),
+        os: config.os.clone(),
+        repo_digests: Some(image.repo_digests),
+        repo_tags: Some(image.repo_tags),
+        root_fs: root_fs(&config),
+        size: Some(size),
+        variant: config.variant.clone(),
+        virtual_size: Some(size),
+        ..Default::default()
+    }
+}
+
+/// The disk usage reports of an image, one per tag like Podman.
+pub fn to_df_reports(
+    image: &cri::Image,
+    config: &OciImage,
+    containers: i64,
+) -> Vec<SystemDfImageReport> {
+    let report = |repository: &str, tag: &str| SystemDfImageReport {
+        containers: Some(containers),
+        created: config.created,
+        image_id: Some(image.id.clone()),
+        repository: Some(repository.to_string()),
+        shared_size: Some(0),
+        size: Some(size(image)),
+        tag: Some(tag.to_string()),
+        unique_size: Some(size(image)),
+    };
+    if image.repo_tags.is_empty() {
+        return vec![report("<none>", "<none>")];
+    }
+    image
+        .repo_tags
+        .iter()
+        .map(|name| {
+            let (repository, tag) = split_tag(name);
+            report(repository, tag)
+        })
+        .collect()
+}
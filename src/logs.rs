@@ -0,0 +1,396 @@
+//! Container logs.
+//!
+//! CRI runtimes write the output of a container to a log file, one entry per line:
+//! `<RFC3339Nano timestamp> <stream> <tag> <content>`.
+//! The tag is `F` for a full line and `P` for a partial line, continued by the next entry of the same stream.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    future::Future,
+    path::PathBuf,
+    pin::Pin,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use tokio::{
+    fs::File,
+    io::{AsyncBufReadExt, AsyncWrite, AsyncWriteExt, BufReader},
+};
+
+use crate::streaming::{frame, StreamType};
+
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A complete log line, possibly merged from several partial entries.
+#[derive(Clone, Debug)]
+pub struct LogLine {
+    /// Timestamp of the first entry, as written by the runtime.
+    pub timestamp: String,
+    pub time: DateTime<Utc>,
+    pub stream: StreamType,
+    /// Content of the line, including the trailing newline for full lines.
+    pub content: Vec<u8>,
+}
+
+struct Entry<'a> {
+    timestamp: &'a str,
+    time: DateTime<Utc>,
+    stream: StreamType,
+    partial: bool,
+    content: &'a [u8],
+}
+
+fn parse_entry(line: &[u8]) -> Option<Entry<'_>> {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    let mut fields = line.splitn(4, |byte| *byte == b' ');
+
+    let timestamp = std::str::from_utf8(fields.next()?).ok()?;
+    let time = DateTime::parse_from_rfc3339(timestamp).ok()?.to_utc();
+    let stream = match fields.next()? {
+        b"stdout" => StreamType::Stdout,
+        b"stderr" => StreamType::Stderr,
+        _ => return None,
+    };
+    let partial = match fields.next()? {
+        b"P" => true,
+        b"F" => false,
+        _ => return None,
+    };
+    let content = fields.next().unwrap_or_default();
+
+    Some(Entry {
+        timestamp,
+        time,
+        stream,
+        partial,
+        content,
+    })
+}
+
+/// Parser of CRI log entries, merging partial entries into complete lines.
+#[derive(Default)]
+pub struct LogParser {
+    partial: HashMap<u8, LogLine>,
+}
+
+impl LogParser {
+    /// Parse one entry of the log file. Returns a line once it is complete.
+    /// Malformed entries are skipped.
+    pub fn push(&mut self, raw: &[u8]) -> Option<LogLine> {
+        let entry = parse_entry(raw)?;
+
+        let mut line = self
+            .partial
+            .remove(&(entry.stream as u8))
+            .unwrap_or_else(|| LogLine {
+                timestamp: entry.timestamp.to_string(),
+                time: entry.time,
+                stream: entry.stream,
+                content: Vec::new(),
+            });
+        line.content.extend_from_slice(entry.content);
+
+        if entry.partial {
+            self.partial.insert(entry.stream as u8, line);
+            None
+        } else {
+            line.content.push(b'\n');
+            Some(line)
+        }
+    }
+}
+
+/// Options of a logs request, see `GET /libpod/containers/:name/logs`.
+#[derive(Clone, Debug, Default)]
+pub struct LogOptions {
+    pub follow: bool,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    /// Number of lines to show from the end of the logs, all of them if `None`.
+    pub tail: Option<usize>,
+    pub timestamps: bool,
+    pub stdout: bool,
+    pub stderr: bool,
+    /// Write the lines using Podman's multiplexed framing, instead of raw.
+    pub multiplexed: bool,
+}
+
+impl LogOptions {
+    fn accepts(&self, line: &LogLine) -> bool {
+        let stream = match line.stream {
+            StreamType::Stderr => self.stderr,
+            _ => self.stdout,
+        };
+        stream
+            && self.since.is_none_or(|since| line.time >= since)
+            && self.until.is_none_or(|until| line.time <= until)
+    }
+
+    fn encode(&self, line: &LogLine) -> Vec<u8> {
+        let payload = if self.timestamps {
+            [line.timestamp.as_bytes(), b" ", &line.content].concat()
+        } else {
+            line.content.clone()
+        };
+
+        if self.multiplexed {
+            frame(line.stream, &payload)
+        } else {
+            payload
+        }
+    }
+}
+
+pub type RunningCheck = Box<dyn Fn() -> Pin<Box<dyn Future<Output = bool> + Send>> + Send>;
+
+/// Write the logs of the file at `path` to `output`.
+///
+/// With `follow`, keep reading the file as it grows,
+/// until `running` reports that the container stopped or the `until` time passed.
+pub async fn write_logs<W>(
+    path: PathBuf,
+    options: LogOptions,
+    running: RunningCheck,
+    mut output: W,
+) -> std::io::Result<()>
+where
+    W: AsyncWrite + Unpin,
+{
+    let mut reader = BufReader::new(File::open(&path).await?);
+    let mut parser = LogParser::default();
+    let mut raw = Vec::new();
+
+    // read the current content first, to apply the tail option,
+    // keeping only the last lines in memory
+    let mut lines = VecDeque::new();
+    while reader.read_until(b'\n', &mut raw).await? > 0 {
+        if raw.ends_with(b"\n") {
+            let line = parser.push(&raw).filter(|line| options.accepts(line));
+            if let Some(line) = line {
+                match options.tail {
+                    None => output.write_all(&options.encode(&line)).await?,
+                    Some(0) => {}
+                    Some(tail) => {
+                        if lines.len() == tail {
+                            lines.pop_front();
+                        }
+                        lines.push_back(line);
+                    }
+                }
+            }
+            raw.clear();
+        }
+    }
+    for line in &lines {
+        output.write_all(&options.encode(line)).await?;
+    }
+    output.flush().await?;
+
+    if !options.follow {
+        return output.shutdown().await;
+    }
+
+    let mut stopped = false;
+    loop {
+        if options.until.is_some_and(|until| Utc::now() > until) {
+            break;
+        }
+
+        if reader.read_until(b'\n', &mut raw).await? == 0 {
+            if stopped {
+                break;
+            }
+            // writing nothing fails once the client is gone
+            if output.write(&[]).await.is_err() {
+                break;
+            }
+            // read once more after the container stops, to get its last lines
+            if !running().await {
+                stopped = true;
+            } else {
+                tokio::time::sleep(FOLLOW_POLL_INTERVAL).await;
+            }
+            continue;
+        }
+
+        // the runtime may not have written the whole entry yet
+        if !raw.ends_with(b"\n") {
+            continue;
+        }
+        if let Some(line) = parser.push(&raw) {
+            if options.accepts(&line) {
+                output.write_all(&options.encode(&line)).await?;
+                output.flush().await?;
+            }
+        }
+        raw.clear();
+    }
+
+    output.shutdown().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/container.log");
+
+    fn time(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().to_utc()
+    }
+
+    fn all_streams() -> LogOptions {
+        LogOptions {
+            stdout: true,
+            stderr: true,
+            ..Default::default()
+        }
+    }
+
+    async fn read_fixture(options: LogOptions) -> Vec<u8> {
+        let running: RunningCheck = Box::new(|| Box::pin(async { false }));
+        let mut output = Vec::new();
+        write_logs(FIXTURE.into(), options, running, &mut output)
+            .await
+            .unwrap();
+        output
+    }
+
+    #[test]
+    fn parser_joins_partial_entries() {
+        let mut parser = LogParser::default();
+
+        assert!(parser
+            .push(b"2024-05-01T10:00:00Z stdout P hello \n")
+            .is_none());
+        // a full entry of the other stream doesn't end the partial line
+        let line = parser
+            .push(b"2024-05-01T10:00:01Z stderr F oops\n")
+            .unwrap();
+        assert!(matches!(line.stream, StreamType::Stderr));
+        assert_eq!(line.content, b"oops\n");
+
+        let line = parser
+            .push(b"2024-05-01T10:00:02Z stdout F world\n")
+            .unwrap();
+        assert!(matches!(line.stream, StreamType::Stdout));
+        assert_eq!(line.content, b"hello world\n");
+        assert_eq!(line.timestamp, "2024-05-01T10:00:00Z");
+        assert_eq!(line.time, time("2024-05-01T10:00:00Z"));
+    }
+
+    #[test]
+    fn parser_skips_malformed_entries() {
+        let mut parser = LogParser::default();
+
+        assert!(parser.push(b"not a log entry\n").is_none());
+        assert!(parser
+            .push(b"2024-05-01T10:00:00Z stdin F input\n")
+            .is_none());
+        assert!(parser
+            .push(b"2024-05-01T10:00:00Z stdout X content\n")
+            .is_none());
+        let line = parser.push(b"2024-05-01T10:00:00Z stdout F\n").unwrap();
+        assert_eq!(line.content, b"\n");
+    }
+
+    #[tokio::test]
+    async fn logs_of_all_streams() {
+        let output = read_fixture(all_streams()).await;
+        assert_eq!(
+            output,
+            b"first line\nfirst error\nsecond error\nsecond line\nthird line\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn logs_of_one_stream() {
+        let options = LogOptions {
+            stdout: true,
+            ..Default::default()
+        };
+        let output = read_fixture(options).await;
+        assert_eq!(output, b"first line\nsecond line\nthird line\n");
+
+        let options = LogOptions {
+            stderr: true,
+            multiplexed: true,
+            ..Default::default()
+        };
+        let output = read_fixture(options).await;
+        let expected = [
+            frame(StreamType::Stderr, b"first error\n"),
+            frame(StreamType::Stderr, b"second error\n"),
+        ]
+        .concat();
+        assert_eq!(output, expected);
+    }
+
+    #[tokio::test]
+    async fn logs_since_until() {
+        let options = LogOptions {
+            since: Some(time("2024-05-01T10:00:01Z")),
+            until: Some(time("2024-05-01T10:00:03Z")),
+            ..all_streams()
+        };
+        let output = read_fixture(options).await;
+        assert_eq!(output, b"first error\nsecond error\nsecond line\n");
+    }
+
+    #[tokio::test]
+    async fn logs_tail() {
+        let options = LogOptions {
+            tail: Some(2),
+            ..all_streams()
+        };
+        assert_eq!(read_fixture(options).await, b"second line\nthird line\n");
+
+        let options = LogOptions {
+            tail: Some(0),
+            ..all_streams()
+        };
+        assert!(read_fixture(options).await.is_empty());
+
+        // the tail applies to the selected stream
+        let options = LogOptions {
+            tail: Some(1),
+            stderr: true,
+            ..Default::default()
+        };
+        assert_eq!(read_fixture(options).await, b"second error\n");
+    }
+
+    #[tokio::test]
+    async fn logs_timestamps() {
+        let options = LogOptions {
+            tail: Some(2),
+            timestamps: true,
+            stdout: true,
+            ..Default::default()
+        };
+        let output = read_fixture(options).await;
+        assert_eq!(
+            output,
+            b"2024-05-01T10:00:02Z second line\n2024-05-01T12:00:04+02:00 third line\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn follow_stops_without_client() {
+        let options = LogOptions {
+            follow: true,
+            tail: Some(0),
+            ..all_streams()
+        };
+        let running: RunningCheck = Box::new(|| Box::pin(async { true }));
+        let (writer, reader) = tokio::io::duplex(64);
+        drop(reader);
+
+        let logs = write_logs(FIXTURE.into(), options, running, writer);
+        tokio::time::timeout(Duration::from_secs(5), logs)
+            .await
+            .expect("still following the logs")
+            .unwrap();
+    }
+}
@@ -1,6 +1,6 @@
 use axum::{
     extract::Request,
-    http::HeaderValue,
+    http::{header::CONTENT_TYPE, HeaderValue},
     middleware::{self, Next},
     response::Response,
     routing::{any, delete, get, post},
@@ -9,6 +9,8 @@ use axum::{
 
 use tower_http::trace::TraceLayer;
 
+// the doc comments generated from the proto file don't follow the markdown conventions
+#[allow(clippy::doc_lazy_continuation, clippy::doc_overindented_list_items)]
 pub mod cri {
     tonic::include_proto!("runtime.v1");
 }
@@ -19,8 +21,28 @@ use crate::unix::serve;
 pub mod proxy;
 use crate::proxy::reverse_proxy;
 
+pub mod attach;
+pub mod auth;
 pub mod cri_clients;
+pub mod env;
+pub mod error;
+pub mod events;
+pub mod exec;
+pub mod extract;
+pub mod filters;
 pub mod handlers;
+pub mod images;
+pub mod logs;
+pub mod network;
+pub mod ports;
+pub mod query;
+pub mod resources;
+pub mod security;
+pub mod stats;
+pub mod store;
+pub mod streaming;
+pub mod userns;
+pub mod volumes;
 
 #[tokio::main]
 async fn main() {
@@ -29,46 +51,153 @@ async fn main() {
         // .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
         .init();
 
-    let libpod_router = Router::new()
+    let mut libpod_router = Router::new()
         // libpod containers routes
         .route("/containers/json", get(handlers::container_list_libpod))
+        .route("/containers/stats", get(handlers::container_stats_libpod))
         .route(
             "/containers/create",
             post(handlers::container_create_libpod),
         )
         .route("/containers/:name/start", post(handlers::container_start))
-        .route("/containers/:name/json", get(handlers::container_inspect_libpod))
-
+        .route(
+            "/containers/:name/json",
+            get(handlers::container_inspect_libpod),
+        )
+        .route(
+            "/containers/:name/exec",
+            post(handlers::container_exec_libpod),
+        )
+        .route("/containers/:name/attach", post(handlers::container_attach))
+        .route("/containers/:name/resize", post(handlers::container_resize))
+        .route(
+            "/containers/:name/logs",
+            get(handlers::container_logs_libpod),
+        )
+        .route("/containers/:name/stop", post(handlers::container_stop))
+        .route("/containers/:name/kill", post(handlers::container_kill))
+        .route(
+            "/containers/:name/restart",
+            post(handlers::container_restart),
+        )
+        .route(
+            "/containers/:name/wait",
+            post(handlers::container_wait_libpod),
+        )
+        .route(
+            "/containers/:name/update",
+            post(handlers::container_update_libpod),
+        )
+        .route(
+            "/containers/:name",
+            delete(handlers::container_delete_libpod),
+        )
+        // libpod exec routes
+        .route("/exec/:id/start", post(handlers::exec_start_libpod))
+        .route("/exec/:id/json", get(handlers::exec_inspect_libpod))
+        .route("/exec/:id/resize", post(handlers::exec_resize_libpod))
         // libpod pods routes
         .route("/pods/json", get(handlers::pod_list_libpod))
+        .route("/pods/stats", get(handlers::pod_stats_libpod))
         .route("/pods/create", post(handlers::pod_create_libpod))
         .route("/pods/:name/start", post(handlers::pod_start_libpod))
         .route("/pods/:name/stop", post(handlers::pod_stop_libpod))
-        .route("/pods/:name", delete(handlers::pod_delete_libpod));
+        .route("/pods/:name/json", get(handlers::pod_inspect_libpod))
+        .route("/pods/:name", delete(handlers::pod_delete_libpod))
+        // libpod volumes routes
+        .route("/volumes/json", get(handlers::volume_list_libpod))
+        .route("/volumes/create", post(handlers::volume_create_libpod))
+        .route("/volumes/prune", post(handlers::volume_prune_libpod))
+        .route("/volumes/:name/json", get(handlers::volume_inspect_libpod))
+        .route("/volumes/:name/exists", get(handlers::volume_exists_libpod))
+        .route("/volumes/:name", delete(handlers::volume_delete))
+        // libpod system routes
+        .route("/system/df", get(handlers::system_df_libpod))
+        .route("/events", get(handlers::events_libpod));
 
-    let app = Router::new()
+    let mut app = Router::new()
         // compat containers routes
         .route("/containers/json", get(handlers::container_list))
         .route("/containers/create", post(handlers::container_create))
         .route("/containers/:name/json", get(handlers::container_inspect))
         .route("/containers/:name/start", post(handlers::container_start))
         .route("/containers/:name/stop", post(handlers::container_stop))
+        .route(
+            "/containers/:name/exec",
+            post(handlers::container_exec_libpod),
+        )
+        .route("/containers/:name/attach", post(handlers::container_attach))
+        .route("/containers/:name/resize", post(handlers::container_resize))
+        .route("/containers/:name/logs", get(handlers::container_logs))
+        .route("/containers/:name/kill", post(handlers::container_kill))
+        .route(
+            "/containers/:name/restart",
+            post(handlers::container_restart),
+        )
+        .route("/containers/:name/wait", post(handlers::container_wait))
+        .route("/containers/:name/stats", get(handlers::container_stats))
+        .route("/containers/:name/update", post(handlers::container_update))
+        .route("/containers/:name", delete(handlers::container_delete))
+        // compat exec routes
+        .route("/exec/:id/start", post(handlers::exec_start_libpod))
+        .route("/exec/:id/json", get(handlers::exec_inspect_libpod))
+        .route("/exec/:id/resize", post(handlers::exec_resize_libpod))
+        // compat volumes routes
+        .route("/volumes", get(handlers::volume_list))
+        .route("/volumes/create", post(handlers::volume_create))
+        .route("/volumes/prune", post(handlers::volume_prune))
+        .route(
+            "/volumes/:name",
+            get(handlers::volume_inspect).delete(handlers::volume_delete),
+        )
+        // compat system routes
+        .route("/events", get(handlers::events))
         // reply to ping
         .route("/_ping", get(handlers::ping))
         .route("/cri/_ping", get(handlers::ping))
-        .route("/cri/version", get(handlers::version))
+        .route("/cri/version", get(handlers::version));
+
+    if images::native() {
+        // the image service of the CRI runtime serves the images,
+        // the names of images contain slashes so they are matched by wildcards
+        libpod_router = libpod_router
+            .route("/images/json", get(handlers::image_list_libpod))
+            .route("/images/pull", post(handlers::image_pull_libpod))
+            .route(
+                "/images/*name",
+                get(handlers::image_get_libpod)
+                    .delete(handlers::image_delete_libpod)
+                    .fallback(handlers::image_unsupported),
+            );
+        app = app
+            .route("/images/json", get(handlers::image_list))
+            .route("/images/create", post(handlers::image_create))
+            .route(
+                "/images/*name",
+                get(handlers::image_get)
+                    .delete(handlers::image_delete)
+                    .fallback(handlers::image_unsupported),
+            )
+            .route("/build", post(handlers::image_unsupported))
+            .route(
+                "/:api_version/libpod/build",
+                any(handlers::image_unsupported),
+            );
+    } else {
         // forward to podman all the image-related paths
         // CRI-O and Podman (root user) share the same storage for images,
         // so CRI-O can access any image pulled or built by Podman.
-        .route("/images/*path", any(reverse_proxy))
-        .route("/build", post(reverse_proxy))
+        app = app
+            .route("/images/*path", any(reverse_proxy))
+            .route("/build", post(reverse_proxy))
+            .route("/:api_version/libpod/build", any(reverse_proxy))
+            .route("/:api_version/libpod/images/*path", any(reverse_proxy));
+    }
+
+    let app = app
         // forward to podman all the other paths we don't want to handle
-        .route("/events", any(reverse_proxy))
-        .route("/volumes", post(reverse_proxy))
         .route("/:api_version/libpod/_ping", any(reverse_proxy))
         .route("/:api_version/libpod/info", any(reverse_proxy))
-        .route("/:api_version/libpod/build", any(reverse_proxy))
-        .route("/:api_version/libpod/images/*path", any(reverse_proxy))
         // nest libpod routes
         .nest("/:api_version/libpod", libpod_router)
         // modify headers
@@ -82,11 +211,13 @@ async fn main() {
     serve(app, path).await;
 }
 
-/// modify_headers forces the `Content-Type` header to be `application/json`.
-/// This makes the app more tolerant to clients using the wrong content type.
+/// modify_headers sets the `Content-Type` header to `application/json` when it is missing.
+/// This makes the app more tolerant to clients that don't set the content type,
+/// without losing the type of the other bodies forwarded to Podman.
 async fn modify_headers(mut request: Request, next: Next) -> Response {
     request
         .headers_mut()
-        .insert("Content-Type", HeaderValue::from_static("application/json"));
+        .entry(CONTENT_TYPE)
+        .or_insert(HeaderValue::from_static("application/json"));
     next.run(request).await
 }
@@ -0,0 +1,158 @@
+//! Name resolution settings of sandboxes: DNS, hostname and `/etc/hosts`.
+//!
+//! The DNS configuration and the hostname are part of the CRI sandbox config.
+//! The runtime doesn't manage `/etc/hosts`, so like the kubelet for `hostAliases`,
+//! we write the file of a pod with extra hosts once its sandbox runs, with the addresses of
+//! its hostname, and mount it in its containers.
+
+use std::net::IpAddr;
+
+use podman_api::models::{CreateContainerConfig, PodSpecGenerator, SpecGenerator};
+
+use crate::cri;
+use crate::error::Error;
+
+pub const HOSTS_PATH: &str = "/etc/hosts";
+
+const HOSTS_HEADER: &str = "# Managed by podman-cri.
+127.0.0.1\tlocalhost
+::1\tlocalhost ip6-localhost ip6-loopback
+";
+
+#[derive(Debug, Default)]
+pub struct NetworkOptions {
+    hostname: Option<String>,
+    dns: cri::DnsConfig,
+    /// Extra hosts, as IP and names.
+    hosts: Vec<(IpAddr, Vec<String>)>,
+}
+
+/// An extra host, `name:ip` with names separated by `;`, Docker also accepts `name=ip`.
+fn parse_host(host: &str) -> Result<(IpAddr, Vec<String>), Error> {
+    let invalid = || Error::bad_request(format!("invalid host entry: {host}"));
+    let (names, ip) = host
+        .split_once('=')
+        .or_else(|| host.split_once(':'))
+        .ok_or_else(invalid)?;
+    let ip = ip.trim_start_matches('[').trim_end_matches(']');
+    if ip == "host-gateway" {
+        return Err(Error::bad_request(
+            "the host-gateway address is not supported by the CRI runtime",
+        ));
+    }
+    let ip = ip.parse().map_err(|_| invalid())?;
+    let names: Vec<String> = names
+        .split(';')
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .collect();
+    if names.is_empty() {
+        return Err(invalid());
+    }
+    Ok((ip, names))
+}
+
+fn new(
+    hostname: Option<&String>,
+    servers: Option<&Vec<String>>,
+    searches: Option<&Vec<String>>,
+    options: Option<&Vec<String>>,
+    hosts: Option<&Vec<String>>,
+    no_manage_hosts: bool,
+) -> Result<NetworkOptions, Error> {
+    let hosts = hosts
+        .into_iter()
+        .flatten()
+        .map(|host| parse_host(host))
+        .collect::<Result<Vec<_>, _>>()?;
+    if no_manage_hosts && !hosts.is_empty() {
+        return Err(Error::bad_request(
+            "extra hosts can't be added when /etc/hosts isn't managed",
+        ));
+    }
+
+    Ok(NetworkOptions {
+        hostname: hostname.filter(|hostname| !hostname.is_empty()).cloned(),
+        dns: cri::DnsConfig {
+            servers: servers.cloned().unwrap_or_default(),
+            searches: searches.cloned().unwrap_or_default(),
+            options: options.cloned().unwrap_or_default(),
+        },
+        hosts,
+    })
+}
+
+impl NetworkOptions {
+    /// The settings of a libpod container create request.
+    pub fn from_spec(spec: &SpecGenerator) -> Result<Self, Error> {
+        new(
+            spec.hostname.as_ref(),
+            spec.dns_server.as_ref(),
+            spec.dns_search.as_ref(),
+            spec.dns_option.as_ref(),
+            spec.hostadd.as_ref(),
+            false,
+        )
+    }
+
+    /// The settings of a libpod pod create request.
+    pub fn from_pod_spec(spec: &PodSpecGenerator) -> Result<Self, Error> {
+        new(
+            spec.hostname.as_ref(),
+            spec.dns_server.as_ref(),
+            spec.dns_search.as_ref(),
+            spec.dns_option.as_ref(),
+            spec.hostadd.as_ref(),
+            spec.no_manage_hosts.unwrap_or(false),
+        )
+    }
+
+    /// The settings of a compat create request.
+    pub fn from_create_config(config: &CreateContainerConfig) -> Result<Self, Error> {
+        let host_config = config.host_config.clone().unwrap_or_default();
+        new(
+            config.hostname.as_ref(),
+            host_config.dns.as_ref(),
+            host_config.dns_search.as_ref(),
+            host_config.dns_options.as_ref(),
+            host_config.extra_hosts.as_ref(),
+            false,
+        )
+    }
+
+    /// Whether a container asks for a setting of the sandbox,
+    /// which it can't do when it joins an existing pod.
+    pub fn is_empty(&self) -> bool {
+        self.hostname.is_none() && self.dns == cri::DnsConfig::default() && self.hosts.is_empty()
+    }
+
+    /// Set the DNS configuration and the hostname of a sandbox.
+    pub fn apply(&self, config: &mut cri::PodSandboxConfig) {
+        if let Some(hostname) = &self.hostname {
+            config.hostname = hostname.clone();
+        }
+        if self.dns != cri::DnsConfig::default() {
+            config.dns_config = Some(self.dns.clone());
+        }
+    }
+
+    /// Whether the sandbox needs its own `/etc/hosts` file, for extra hosts.
+    pub fn has_hosts(&self) -> bool {
+        !self.hosts.is_empty()
+    }
+
+    /// The content of the `/etc/hosts` file of the sandbox,
+    /// with the `ips` of the sandbox for its `hostname`.
+    pub fn hosts_file(&self, hostname: &str, ips: &[String]) -> String {
+        let mut content = HOSTS_HEADER.to_string();
+        if !hostname.is_empty() {
+            for ip in ips {
+                content.push_str(&format!("{ip}\t{hostname}\n"));
+            }
+        }
+        for (ip, names) in &self.hosts {
+            content.push_str(&format!("{ip}\t{}\n", names.join(" ")));
+        }
+        content
+    }
+}
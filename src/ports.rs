@@ -0,0 +1,253 @@
+//! Published ports, see `SpecGenerator.portmappings` and `HostConfig.PortBindings`.
+//!
+//! In the CRI, ports belong to the sandbox: they are set when the pod is created and can't change.
+//! The mappings are resolved once, with the host ports Podman would pick at random,
+//! and saved in the records of the pod and its containers to be reported back.
+
+use std::{
+    collections::HashMap,
+    net::{TcpListener, UdpSocket},
+};
+
+use podman_api::models::{InspectHostPort, Port, PortBinding, PortMapping};
+
+use crate::cri;
+use crate::error::Error;
+
+const DEFAULT_PROTOCOL: &str = "tcp";
+const MAX_PORT: i32 = 65535;
+/// Attempts to find a block of free consecutive host ports, like Podman.
+const PORT_BLOCK_ATTEMPTS: usize = 10;
+
+fn parse_protocol(protocol: &str) -> Result<cri::Protocol, Error> {
+    match protocol.to_lowercase().as_str() {
+        "" | "tcp" => Ok(cri::Protocol::Tcp),
+        "udp" => Ok(cri::Protocol::Udp),
+        "sctp" => Ok(cri::Protocol::Sctp),
+        _ => Err(Error::bad_request(format!(
+            "unrecognized protocol \"{protocol}\" in port mapping"
+        ))),
+    }
+}
+
+fn protocol_name(protocol: cri::Protocol) -> String {
+    protocol.as_str_name().to_lowercase()
+}
+
+fn parse_port(port: &str) -> Result<i32, Error> {
+    port.parse()
+        .ok()
+        .filter(|port| (1..=65535).contains(port))
+        .ok_or_else(|| Error::bad_request(format!("invalid port number: {port}")))
+}
+
+/// The address to bind to check host ports, all the addresses when the mapping has none.
+fn bind_ip(host_ip: &str) -> &str {
+    if host_ip.is_empty() {
+        "0.0.0.0"
+    } else {
+        host_ip
+    }
+}
+
+/// A free port of the host, like the ones Podman assigns to mappings without host port.
+fn random_port(host_ip: &str, protocol: cri::Protocol) -> Result<i32, Error> {
+    let host_ip = bind_ip(host_ip);
+    let port = match protocol {
+        cri::Protocol::Udp => UdpSocket::bind((host_ip, 0)).and_then(|socket| socket.local_addr()),
+        _ => TcpListener::bind((host_ip, 0)).and_then(|listener| listener.local_addr()),
+    }
+    .map_err(|err| Error::internal(format!("cannot allocate a host port: {err}")))?
+    .port();
+    Ok(port.into())
+}
+
+/// Whether a host port is free.
+fn is_free(host_ip: &str, protocol: cri::Protocol, port: i32) -> bool {
+    let (host_ip, Ok(port)) = (bind_ip(host_ip), u16::try_from(port)) else {
+        return false;
+    };
+    match protocol {
+        cri::Protocol::Udp => UdpSocket::bind((host_ip, port)).is_ok(),
+        _ => TcpListener::bind((host_ip, port)).is_ok(),
+    }
+}
+
+/// The first port of a block of `range` free consecutive ports of the host,
+/// for mappings of port ranges without host port.
+fn random_ports(host_ip: &str, protocol: cri::Protocol, range: i32) -> Result<i32, Error> {
+    for _ in 0..PORT_BLOCK_ATTEMPTS {
+        let start = random_port(host_ip, protocol)?;
+        if start + range - 1 <= MAX_PORT
+            && (start + 1..start + range).all(|port| is_free(host_ip, protocol, port))
+        {
+            return Ok(start);
+        }
+    }
+    Err(Error::internal(format!(
+        "cannot allocate a block of {range} consecutive host ports"
+    )))
+}
+
+fn mapping(
+    protocol: cri::Protocol,
+    container_port: i32,
+    host_port: Option<i32>,
+    host_ip: String,
+) -> Result<cri::PortMapping, Error> {
+    let host_port = match host_port {
+        Some(port) if port > 0 => port,
+        _ => random_port(&host_ip, protocol)?,
+    };
+    Ok(cri::PortMapping {
+        protocol: protocol.into(),
+        container_port,
+        host_port,
+        host_ip,
+    })
+}
+
+/// The sandbox port mappings of the libpod `portmappings`.
+/// A mapping covers `range` consecutive ports and may list several comma separated protocols.
+pub fn from_port_mappings(mappings: &[PortMapping]) -> Result<Vec<cri::PortMapping>, Error> {
+    let mut result = Vec::new();
+    for port_mapping in mappings {
+        let container_port = port_mapping
+            .container_port
+            .filter(|port| (1..=65535).contains(port))
+            .ok_or_else(|| Error::bad_request("port mapping without valid container port"))?;
+        let range = port_mapping.range.unwrap_or(1).max(1);
+        let host_port = port_mapping.host_port.filter(|port| *port > 0);
+        for port in [Some(container_port), host_port].into_iter().flatten() {
+            if port
+                .checked_add(range - 1)
+                .is_none_or(|last| last > MAX_PORT)
+            {
+                return Err(Error::bad_request(format!(
+                    "port range of {range} ports from port {port} exceeds {MAX_PORT}"
+                )));
+            }
+        }
+        let host_ip = port_mapping.host_ip.clone().unwrap_or_default();
+        let protocols = port_mapping.protocol.as_deref().unwrap_or(DEFAULT_PROTOCOL);
+        for protocol in protocols.split(',') {
+            let protocol = parse_protocol(protocol.trim())?;
+            // like Podman, a range without host port gets a block of consecutive host ports
+            let host_port = match host_port {
+                Some(port) => port,
+                None => random_ports(&host_ip, protocol, range)?,
+            };
+            for offset in 0..range {
+                result.push(cri::PortMapping {
+                    protocol: protocol.into(),
+                    container_port: container_port + offset,
+                    host_port: host_port + offset,
+                    host_ip: host_ip.clone(),
+                });
+            }
+        }
+    }
+    Ok(result)
+}
+
+/// The sandbox port mappings of the compat `PortBindings`, keyed by `port/protocol`.
+pub fn from_port_bindings(
+    bindings: &HashMap<String, Vec<PortBinding>>,
+) -> Result<Vec<cri::PortMapping>, Error> {
+    let mut result = Vec::new();
+    for (key, bindings) in bindings {
+        let (container_port, protocol) = key.split_once('/').unwrap_or((key, DEFAULT_PROTOCOL));
+        let container_port = parse_port(container_port)?;
+        let protocol = parse_protocol(protocol)?;
+        for binding in bindings {
+            let host_port = match binding.host_port.as_deref() {
+                None | Some("") => None,
+                Some(port) => Some(parse_port(port)?),
+            };
+            result.push(mapping(
+                protocol,
+                container_port,
+                host_port,
+                binding.host_ip.clone().unwrap_or_default(),
+            )?);
+        }
+    }
+    Ok(result)
+}
+
+/// Podman's representation of a resolved mapping, to be saved in the records.
+pub fn to_port_mapping(mapping: &cri::PortMapping) -> PortMapping {
+    PortMapping {
+        container_port: Some(mapping.container_port),
+        host_ip: Some(mapping.host_ip.clone()),
+        host_port: Some(mapping.host_port),
+        protocol: Some(protocol_name(mapping.protocol())),
+        range: Some(1),
+    }
+}
+
+/// The ports of the compat container list.
+pub fn to_ports(mappings: &[PortMapping]) -> Vec<Port> {
+    mappings
+        .iter()
+        .map(|mapping| Port {
+            ip: mapping.host_ip.clone().filter(|ip| !ip.is_empty()),
+            private_port: mapping.container_port.unwrap_or_default(),
+            public_port: mapping.host_port,
+            r#type: mapping
+                .protocol
+                .clone()
+                .unwrap_or(DEFAULT_PROTOCOL.to_string()),
+        })
+        .collect()
+}
+
+/// The published ports keyed by `port/protocol`, like in `NetworkSettings.Ports`.
+fn by_container_port(mappings: &[PortMapping]) -> HashMap<String, Vec<(String, String)>> {
+    let mut result: HashMap<String, Vec<(String, String)>> = HashMap::new();
+    for mapping in mappings {
+        let key = format!(
+            "{}/{}",
+            mapping.container_port.unwrap_or_default(),
+            mapping.protocol.as_deref().unwrap_or(DEFAULT_PROTOCOL)
+        );
+        let host_ip = mapping.host_ip.clone().unwrap_or_default();
+        let host_port = mapping.host_port.unwrap_or_default().to_string();
+        result.entry(key).or_default().push((host_ip, host_port));
+    }
+    result
+}
+
+/// `NetworkSettings.Ports` of the compat inspect.
+pub fn to_port_bindings(mappings: &[PortMapping]) -> HashMap<String, Vec<PortBinding>> {
+    by_container_port(mappings)
+        .into_iter()
+        .map(|(key, hosts)| {
+            let bindings = hosts
+                .into_iter()
+                .map(|(host_ip, host_port)| PortBinding {
+                    host_ip: Some(host_ip),
+                    host_port: Some(host_port),
+                })
+                .collect();
+            (key, bindings)
+        })
+        .collect()
+}
+
+/// `NetworkSettings.Ports` of the libpod inspect.
+pub fn to_inspect_ports(mappings: &[PortMapping]) -> HashMap<String, Vec<InspectHostPort>> {
+    by_container_port(mappings)
+        .into_iter()
+        .map(|(key, hosts)| {
+            let bindings = hosts
+                .into_iter()
+                .map(|(host_ip, host_port)| InspectHostPort {
+                    host_ip: Some(host_ip),
+                    host_port: Some(host_port),
+                })
+                .collect();
+            (key, bindings)
+        })
+        .collect()
+}
@@ -1,16 +1,30 @@
 use axum::{
     body::{to_bytes, Body, Bytes},
     extract::Request,
-    http::StatusCode,
+    http::{header, HeaderName, StatusCode},
     response::{IntoResponse, Response},
 };
+
+use crate::error::Error;
 use http_body_util::Full;
+use hyper::body::Incoming;
 use hyper_util::client::legacy::Client;
 use hyperlocal::{UnixClientExt, UnixConnector, Uri};
 
 const PODMAN_ENDPOINT_DEFAULT: &str = "/run/user/1000/podman/podman.sock";
 
-pub async fn reverse_proxy(req: Request<Body>) -> Result<Response, StatusCode> {
+const HOP_BY_HOP_HEADERS: [HeaderName; 8] = [
+    header::CONNECTION,
+    header::CONTENT_LENGTH,
+    header::PROXY_AUTHENTICATE,
+    header::PROXY_AUTHORIZATION,
+    header::TE,
+    header::TRAILER,
+    header::TRANSFER_ENCODING,
+    header::UPGRADE,
+];
+
+pub async fn reverse_proxy(req: Request<Body>) -> Result<Response, Error> {
     let path = req.uri().path();
     let path_query = req
         .uri()
@@ -18,24 +32,61 @@ pub async fn reverse_proxy(req: Request<Body>) -> Result<Response, StatusCode> {
         .map(|v| v.as_str())
         .unwrap_or(path);
 
-    let socket = std::env::var("PODMAN_ENDPOINT").unwrap_or(PODMAN_ENDPOINT_DEFAULT.into());
-    let uri = Uri::new(socket, path_query);
+    let uri = podman_uri(path_query);
 
     let (parts, body) = req.into_parts();
-    let bytes = to_bytes(body, usize::MAX).await.unwrap();
+    let bytes = to_bytes(body, usize::MAX)
+        .await
+        .map_err(|err| Error::bad_request(err.to_string()))?;
 
-    let request: hyper::Request<Full<Bytes>> = hyper::Request::builder()
-        .method(parts.method)
-        .uri(uri)
+    let mut builder = hyper::Request::builder().method(parts.method).uri(uri);
+    // forward the headers of the client, like the registry credentials,
+    // except the ones of the connection and of the body, which is sent whole
+    for (name, value) in parts.headers.iter() {
+        if !HOP_BY_HOP_HEADERS.contains(name) {
+            builder = builder.header(name, value);
+        }
+    }
+    let request: hyper::Request<Full<Bytes>> = builder
         .body(Full::from(bytes))
-        .expect("request builder");
+        .map_err(|err| Error::internal(err.to_string()))?;
 
     let client: Client<UnixConnector, Full<Bytes>> = Client::unix();
     let response = client
         .request(request)
         .await
-        .map_err(|_| StatusCode::BAD_REQUEST)?
+        .map_err(podman_unavailable)?
         .into_response();
 
     Ok(response)
 }
+
+/// Send a GET request to Podman, returns the body of a successful response.
+pub async fn get(path_query: &str) -> Result<Incoming, Error> {
+    let client: Client<UnixConnector, Full<Bytes>> = Client::unix();
+    let response = client
+        .get(podman_uri(path_query).into())
+        .await
+        .map_err(podman_unavailable)?;
+    if !response.status().is_success() {
+        return Err(Error::new(
+            StatusCode::BAD_GATEWAY,
+            "podman error",
+            format!("Podman replied {} to {path_query}", response.status()),
+        ));
+    }
+    Ok(response.into_body())
+}
+
+fn podman_uri(path_query: &str) -> Uri {
+    let socket = std::env::var("PODMAN_ENDPOINT").unwrap_or(PODMAN_ENDPOINT_DEFAULT.into());
+    Uri::new(socket, path_query)
+}
+
+fn podman_unavailable(err: impl std::fmt::Display) -> Error {
+    Error::new(
+        StatusCode::BAD_GATEWAY,
+        "podman unavailable",
+        format!("cannot forward the request to Podman: {err}"),
+    )
+}
@@ -0,0 +1,95 @@
+//! Helpers to parse query parameters the way Podman does.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{de::Error, Deserialize, Deserializer};
+
+/// Deserialize a boolean query parameter.
+/// Docker clients send `1` and `0`, Podman clients send `true` and `false`.
+pub fn bool_param<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = String::deserialize(deserializer)?;
+    parse_bool(&value).ok_or_else(|| D::Error::custom(format!("invalid boolean value: {value}")))
+}
+
+/// Parse a boolean query parameter, see [`bool_param`].
+pub fn parse_bool(value: &str) -> Option<bool> {
+    match value.to_lowercase().as_str() {
+        "1" | "true" | "t" | "yes" => Some(true),
+        "" | "0" | "false" | "f" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// Default of the boolean parameters that are enabled unless disabled explicitly.
+pub fn default_true() -> bool {
+    true
+}
+
+/// Parse a point in time, as accepted by the `since` and `until` parameters:
+/// a Unix timestamp with optional fractional seconds, an RFC 3339 date,
+/// or a duration like `10m` or `1h30m`, relative to now.
+pub fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(seconds) = value.parse::<f64>() {
+        let nanos = (seconds.fract() * 1e9) as u32;
+        return DateTime::from_timestamp(seconds.trunc() as i64, nanos);
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.to_utc());
+    }
+    parse_duration(value).map(|duration| Utc::now() - duration)
+}
+
+/// Parse a Go-style duration with `h`, `m`, `s` and `ms` units.
+pub fn parse_duration(value: &str) -> Option<TimeDelta> {
+    let mut total = TimeDelta::zero();
+    let mut rest = value;
+    while !rest.is_empty() {
+        let digits = rest.find(|c: char| !c.is_ascii_digit())?;
+        let amount: i64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        let unit = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        total += match &rest[..unit] {
+            "h" => TimeDelta::hours(amount),
+            "m" => TimeDelta::minutes(amount),
+            "s" => TimeDelta::seconds(amount),
+            "ms" => TimeDelta::milliseconds(amount),
+            _ => return None,
+        };
+        rest = &rest[unit..];
+    }
+    Some(total)
+}
+
+/// Parse the `filters` parameter: a JSON map of filter names to lists of values,
+/// like `{"label":["app=web"],"status":["running"]}`.
+/// Docker clients may also send maps of values to booleans, like `{"label":{"app=web":true}}`.
+pub fn parse_filters(value: &str) -> Option<HashMap<String, Vec<String>>> {
+    if value.is_empty() {
+        return Some(HashMap::new());
+    }
+    let filters: HashMap<String, serde_json::Value> = serde_json::from_str(value).ok()?;
+    filters
+        .into_iter()
+        .map(|(key, values)| {
+            let values = match values {
+                serde_json::Value::Array(items) => items
+                    .into_iter()
+                    .map(|item| item.as_str().map(str::to_string))
+                    .collect::<Option<Vec<_>>>()?,
+                serde_json::Value::Object(items) => items
+                    .into_iter()
+                    .filter(|(_, enabled)| enabled.as_bool().unwrap_or(false))
+                    .map(|(item, _)| item)
+                    .collect(),
+                _ => return None,
+            };
+            Some((key, values))
+        })
+        .collect()
+}
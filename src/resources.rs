@@ -0,0 +1,315 @@
+//! Resource limits of containers, see `SpecGenerator.resource_limits` and the compat `HostConfig`.
+//!
+//! The CRI only knows a subset of the cgroup settings. Those with a cgroup v2 equivalent,
+//! like the PIDs limit, are passed as `unified` settings on cgroup v2 hosts, the others are
+//! ignored and reported back to the client as warnings.
+
+use std::{path::Path, sync::LazyLock};
+
+use podman_api::models::{HostConfig, LinuxResources, SpecGenerator};
+
+use crate::cri;
+
+/// CFS period used to convert `NanoCpus` to a quota, like Docker.
+const DEFAULT_CPU_PERIOD: i64 = 100_000;
+const NANOS_PER_CPU: i64 = 1_000_000_000;
+
+const PIDS_MAX: &str = "pids.max";
+const MEMORY_LOW: &str = "memory.low";
+
+/// Whether the host uses cgroup v2, the runtimes reject `unified` settings on cgroup v1.
+static CGROUP_V2: LazyLock<bool> =
+    LazyLock::new(|| Path::new("/sys/fs/cgroup/cgroup.controllers").exists());
+
+#[derive(Debug, Default)]
+pub struct Resources {
+    pub linux: cri::LinuxContainerResources,
+    /// Settings the CRI can't apply.
+    pub warnings: Vec<String>,
+}
+
+impl Resources {
+    /// The resources of a libpod create request.
+    pub fn from_spec(spec: &SpecGenerator) -> Self {
+        let mut result = Resources::default();
+        if let Some(resources) = &spec.resource_limits {
+            result.add_linux_resources(resources, "resource_limits");
+        }
+        result.linux.oom_score_adj = spec.oom_score_adj.unwrap_or_default();
+        result
+            .linux
+            .unified
+            .extend(spec.unified.clone().unwrap_or_default());
+
+        let devices = [
+            ("throttle_read_bps_device", &spec.throttle_read_bps_device),
+            ("throttle_read_iops_device", &spec.throttle_read_iops_device),
+            ("throttle_write_bps_device", &spec.throttle_write_bps_device),
+            (
+                "throttle_write_iops_device",
+                &spec.throttle_write_iops_device,
+            ),
+        ];
+        for (name, devices) in devices {
+            result.unsupported(name, devices.as_ref().is_some_and(|d| !d.is_empty()));
+        }
+        result.unsupported(
+            "weight_device",
+            spec.weight_device.as_ref().is_some_and(|d| !d.is_empty()),
+        );
+        result.unsupported(
+            "r_limits",
+            spec.r_limits.as_ref().is_some_and(|r| !r.is_empty()),
+        );
+        result
+    }
+
+    /// The resources of the libpod update request.
+    pub fn from_linux_resources(resources: &LinuxResources) -> Self {
+        let mut result = Resources::default();
+        result.add_linux_resources(resources, "");
+        result
+    }
+
+    fn add_linux_resources(&mut self, resources: &LinuxResources, prefix: &str) {
+        let name = |field: &str| {
+            if prefix.is_empty() {
+                field.to_string()
+            } else {
+                format!("{prefix}.{field}")
+            }
+        };
+
+        if let Some(memory) = &resources.memory {
+            self.linux.memory_limit_in_bytes = memory.limit.unwrap_or_default();
+            self.linux.memory_swap_limit_in_bytes = memory.swap.unwrap_or_default();
+            if let Some(reservation) = memory.reservation.filter(|value| *value > 0) {
+                self.set_unified(&name("memory.reservation"), MEMORY_LOW, reservation);
+            }
+            self.unsupported(&name("memory.swappiness"), memory.swappiness.is_some());
+            self.unsupported(&name("memory.kernel"), memory.kernel.is_some());
+            self.unsupported(&name("memory.kernelTCP"), memory.kernel_tcp.is_some());
+            self.unsupported(
+                &name("memory.disableOOMKiller"),
+                memory.disable_oom_killer.unwrap_or(false),
+            );
+        }
+
+        if let Some(cpu) = &resources.cpu {
+            self.linux.cpu_shares = cpu.shares.unwrap_or_default().into();
+            self.linux.cpu_quota = cpu.quota.unwrap_or_default();
+            self.linux.cpu_period = cpu.period.unwrap_or_default().into();
+            self.linux.cpuset_cpus = cpu.cpus.clone().unwrap_or_default();
+            self.linux.cpuset_mems = cpu.mems.clone().unwrap_or_default();
+            self.unsupported(&name("cpu.realtimePeriod"), cpu.realtime_period.is_some());
+            self.unsupported(&name("cpu.realtimeRuntime"), cpu.realtime_runtime.is_some());
+            self.unsupported(&name("cpu.burst"), cpu.burst.is_some());
+            self.unsupported(&name("cpu.idle"), cpu.idle.is_some());
+        }
+
+        if let Some(limit) = resources.pids.as_ref().and_then(|pids| pids.limit) {
+            if limit > 0 {
+                self.set_unified(&name("pids.limit"), PIDS_MAX, limit);
+            }
+        }
+
+        self.linux.hugepage_limits = resources
+            .hugepage_limits
+            .iter()
+            .flatten()
+            .map(|hugepage| cri::HugepageLimit {
+                page_size: hugepage.page_size.clone().unwrap_or_default(),
+                limit: hugepage.limit.unwrap_or_default().max(0) as u64,
+            })
+            .collect();
+
+        self.linux
+            .unified
+            .extend(resources.unified.clone().unwrap_or_default());
+
+        self.unsupported(&name("blockIO"), resources.block_io.is_some());
+        self.unsupported(
+            &name("devices"),
+            resources.devices.as_ref().is_some_and(|d| !d.is_empty()),
+        );
+        self.unsupported(&name("network"), resources.network.is_some());
+        self.unsupported(
+            &name("rdma"),
+            resources.rdma.as_ref().is_some_and(|r| !r.is_empty()),
+        );
+    }
+
+    /// The resources of a compat create request.
+    pub fn from_host_config(host_config: &HostConfig) -> Self {
+        let mut result = Resources::default();
+        let linux = &mut result.linux;
+
+        linux.memory_limit_in_bytes = host_config.memory.unwrap_or_default();
+        linux.memory_swap_limit_in_bytes = host_config.memory_swap.unwrap_or_default();
+        linux.cpu_shares = host_config.cpu_shares.unwrap_or_default();
+        linux.cpu_period = host_config.cpu_period.unwrap_or_default();
+        linux.cpu_quota = host_config.cpu_quota.unwrap_or_default();
+        linux.cpuset_cpus = host_config.cpuset_cpus.clone().unwrap_or_default();
+        linux.cpuset_mems = host_config.cpuset_mems.clone().unwrap_or_default();
+        linux.oom_score_adj = host_config.oom_score_adj.unwrap_or_default();
+
+        if let Some(nano_cpus) = host_config.nano_cpus.filter(|value| *value > 0) {
+            if linux.cpu_period == 0 {
+                linux.cpu_period = DEFAULT_CPU_PERIOD;
+            }
+            linux.cpu_quota = nano_cpus * linux.cpu_period / NANOS_PER_CPU;
+        }
+        if let Some(reservation) = host_config.memory_reservation.filter(|value| *value > 0) {
+            result.set_unified("MemoryReservation", MEMORY_LOW, reservation);
+        }
+        if let Some(limit) = host_config.pids_limit.filter(|value| *value > 0) {
+            result.set_unified("PidsLimit", PIDS_MAX, limit);
+        }
+
+        // -1 is Docker's default, the swappiness of the host
+        result.unsupported(
+            "MemorySwappiness",
+            host_config
+                .memory_swappiness
+                .is_some_and(|value| value >= 0),
+        );
+        result.unsupported(
+            "KernelMemory",
+            host_config.kernel_memory.is_some_and(|value| value > 0),
+        );
+        result.unsupported(
+            "KernelMemoryTCP",
+            host_config.kernel_memory_tcp.is_some_and(|value| value > 0),
+        );
+        result.unsupported("OomKillDisable", host_config.oom_kill_disable == Some(true));
+        result.unsupported(
+            "BlkioWeight",
+            host_config.blkio_weight.is_some_and(|value| value > 0),
+        );
+        let lists = [
+            (
+                "BlkioWeightDevice",
+                host_config.blkio_weight_device.as_ref().map(Vec::len),
+            ),
+            (
+                "BlkioDeviceReadBps",
+                host_config.blkio_device_read_bps.as_ref().map(Vec::len),
+            ),
+            (
+                "BlkioDeviceWriteBps",
+                host_config.blkio_device_write_bps.as_ref().map(Vec::len),
+            ),
+            (
+                "BlkioDeviceReadIOps",
+                host_config.blkio_device_read_i_ops.as_ref().map(Vec::len),
+            ),
+            (
+                "BlkioDeviceWriteIOps",
+                host_config.blkio_device_write_i_ops.as_ref().map(Vec::len),
+            ),
+            (
+                "DeviceCgroupRules",
+                host_config.device_cgroup_rules.as_ref().map(Vec::len),
+            ),
+            ("Ulimits", host_config.ulimits.as_ref().map(Vec::len)),
+        ];
+        for (name, count) in lists {
+            result.unsupported(name, count.unwrap_or_default() > 0);
+        }
+        result.unsupported(
+            "CpuRealtimePeriod",
+            host_config
+                .cpu_realtime_period
+                .is_some_and(|value| value > 0),
+        );
+        result.unsupported(
+            "CpuRealtimeRuntime",
+            host_config
+                .cpu_realtime_runtime
+                .is_some_and(|value| value > 0),
+        );
+        result.unsupported(
+            "CpuCount",
+            host_config.cpu_count.is_some_and(|value| value > 0),
+        );
+        result.unsupported(
+            "CpuPercent",
+            host_config.cpu_percent.is_some_and(|value| value > 0),
+        );
+        result.unsupported(
+            "IOMaximumBandwidth",
+            host_config
+                .io_maximum_bandwidth
+                .is_some_and(|value| value > 0),
+        );
+        result.unsupported(
+            "IOMaximumIOps",
+            host_config.io_maximum_i_ops.is_some_and(|value| value > 0),
+        );
+        result
+    }
+
+    /// The resources to send to the runtime, `None` when nothing is set.
+    pub fn linux(&self) -> Option<cri::LinuxContainerResources> {
+        (self.linux != cri::LinuxContainerResources::default()).then(|| self.linux.clone())
+    }
+
+    /// Apply the settings that are set on top of `current`, the others are left unchanged.
+    pub fn update(
+        &self,
+        mut current: cri::LinuxContainerResources,
+    ) -> cri::LinuxContainerResources {
+        let new = &self.linux;
+        let numbers = [
+            (&mut current.cpu_period, new.cpu_period),
+            (&mut current.cpu_quota, new.cpu_quota),
+            (&mut current.cpu_shares, new.cpu_shares),
+            (
+                &mut current.memory_limit_in_bytes,
+                new.memory_limit_in_bytes,
+            ),
+            (
+                &mut current.memory_swap_limit_in_bytes,
+                new.memory_swap_limit_in_bytes,
+            ),
+            (&mut current.oom_score_adj, new.oom_score_adj),
+        ];
+        for (value, new) in numbers {
+            if new != 0 {
+                *value = new;
+            }
+        }
+        if !new.cpuset_cpus.is_empty() {
+            current.cpuset_cpus = new.cpuset_cpus.clone();
+        }
+        if !new.cpuset_mems.is_empty() {
+            current.cpuset_mems = new.cpuset_mems.clone();
+        }
+        if !new.hugepage_limits.is_empty() {
+            current.hugepage_limits = new.hugepage_limits.clone();
+        }
+        current.unified.extend(new.unified.clone());
+        current
+    }
+
+    /// Set the `unified` setting `key` for the field `name` of the request.
+    fn set_unified(&mut self, name: &str, key: &str, value: i64) {
+        if !*CGROUP_V2 {
+            self.warnings.push(format!(
+                "{name} is only supported on cgroup v2 hosts and was ignored"
+            ));
+            return;
+        }
+        self.linux
+            .unified
+            .insert(key.to_string(), value.to_string());
+    }
+
+    fn unsupported(&mut self, name: &str, set: bool) {
+        if set {
+            self.warnings.push(format!(
+                "{name} is not supported by the CRI runtime and was ignored"
+            ));
+        }
+    }
+}
@@ -0,0 +1,257 @@
+//! Security settings of containers: capabilities, privileges, SELinux, seccomp, AppArmor and user.
+//!
+//! The runtime checks the context of a container against the one of its sandbox,
+//! so the sandboxes created for containers outside of a pod get the matching context.
+
+use podman_api::models::{CreateContainerConfig, SpecGenerator};
+
+use crate::cri::{self, security_profile::ProfileType};
+use crate::error::Error;
+
+const UNCONFINED: &str = "unconfined";
+/// SELinux type of containers with labeling disabled, like Podman's `label=disable`.
+const SPC_TYPE: &str = "spc_t";
+
+/// Capability names as the CRI expects them: uppercase, without the `CAP_` prefix.
+fn capabilities(names: &Option<Vec<String>>) -> Vec<String> {
+    names
+        .iter()
+        .flatten()
+        .map(|name| {
+            let name = name.to_uppercase();
+            name.strip_prefix("CAP_").unwrap_or(&name).to_string()
+        })
+        .collect()
+}
+
+fn profile(value: &str) -> cri::SecurityProfile {
+    let (profile_type, localhost_ref) = match value {
+        "" | "default" | "runtime/default" => (ProfileType::RuntimeDefault, String::new()),
+        UNCONFINED => (ProfileType::Unconfined, String::new()),
+        _ => (ProfileType::Localhost, value.to_string()),
+    };
+    cri::SecurityProfile {
+        profile_type: profile_type.into(),
+        localhost_ref,
+    }
+}
+
+fn default_profile() -> cri::SecurityProfile {
+    profile("")
+}
+
+/// The `seccomp=` or `apparmor=` security option of a profile, `None` for the default one.
+fn profile_option(kind: &str, profile: &cri::SecurityProfile) -> Option<String> {
+    match profile.profile_type() {
+        ProfileType::RuntimeDefault => None,
+        ProfileType::Unconfined => Some(format!("{kind}={UNCONFINED}")),
+        ProfileType::Localhost => Some(format!("{kind}={}", profile.localhost_ref)),
+    }
+}
+
+fn parse_id(value: &str, kind: &str) -> Result<i64, Error> {
+    value.parse().map_err(|_| {
+        Error::bad_request(format!(
+            "{kind} names are not supported by the CRI runtime, use a numeric ID instead of \"{value}\""
+        ))
+    })
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct SecurityOptions {
+    pub cap_add: Vec<String>,
+    pub cap_drop: Vec<String>,
+    pub privileged: bool,
+    pub readonly_rootfs: bool,
+    pub no_new_privileges: bool,
+    selinux: cri::SeLinuxOption,
+    seccomp: Option<cri::SecurityProfile>,
+    apparmor: Option<cri::SecurityProfile>,
+    run_as_user: Option<i64>,
+    run_as_username: String,
+    run_as_group: Option<i64>,
+    /// Supplementary groups.
+    pub groups: Vec<i64>,
+}
+
+impl SecurityOptions {
+    /// The security options of a libpod create request.
+    pub fn from_spec(spec: &SpecGenerator) -> Result<Self, Error> {
+        let mut result = SecurityOptions {
+            cap_add: capabilities(&spec.cap_add),
+            cap_drop: capabilities(&spec.cap_drop),
+            privileged: spec.privileged.unwrap_or(false),
+            readonly_rootfs: spec.read_only_filesystem.unwrap_or(false),
+            no_new_privileges: spec.no_new_privileges.unwrap_or(false),
+            seccomp: spec.seccomp_profile_path.as_deref().map(profile),
+            apparmor: spec.apparmor_profile.as_deref().map(profile),
+            ..Default::default()
+        };
+        for option in spec.selinux_opts.iter().flatten() {
+            result.parse_label(option)?;
+        }
+        if let Some(user) = spec.user.as_deref() {
+            result.parse_user(user)?;
+        }
+        result.parse_groups(spec.groups.iter().flatten())?;
+        Ok(result)
+    }
+
+    /// The security options of a compat create request.
+    pub fn from_create_config(config: &CreateContainerConfig) -> Result<Self, Error> {
+        let mut result = SecurityOptions::default();
+        if let Some(user) = config.user.as_deref() {
+            result.parse_user(user)?;
+        }
+        let Some(host_config) = &config.host_config else {
+            return Ok(result);
+        };
+
+        result.cap_add = capabilities(&host_config.cap_add);
+        result.cap_drop = capabilities(&host_config.cap_drop);
+        result.privileged = host_config.privileged.unwrap_or(false);
+        result.readonly_rootfs = host_config.readonly_rootfs.unwrap_or(false);
+        result.parse_groups(host_config.group_add.iter().flatten())?;
+
+        for option in host_config.security_opt.iter().flatten() {
+            // Docker accepts the legacy `key:value` form too
+            let (key, value) = option
+                .split_once('=')
+                .or_else(|| option.split_once(':'))
+                .unwrap_or((option, ""));
+            match key {
+                "label" => result.parse_label(value)?,
+                "seccomp" => result.seccomp = Some(profile(value)),
+                "apparmor" => result.apparmor = Some(profile(value)),
+                "no-new-privileges" => {
+                    result.no_new_privileges = value.is_empty()
+                        || value.parse().map_err(|_| {
+                            Error::bad_request(format!("invalid security option: {option}"))
+                        })?
+                }
+                _ => {
+                    return Err(Error::bad_request(format!(
+                        "security option not supported by the CRI runtime: {option}"
+                    )))
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// An SELinux option, like `type:spc_t` or `disable`.
+    fn parse_label(&mut self, option: &str) -> Result<(), Error> {
+        let (key, value) = option.split_once(':').unwrap_or((option, ""));
+        let value = value.to_string();
+        match key {
+            "disable" => self.selinux.r#type = SPC_TYPE.to_string(),
+            "user" => self.selinux.user = value,
+            "role" => self.selinux.role = value,
+            "type" => self.selinux.r#type = value,
+            "level" => self.selinux.level = value,
+            // labels of the files and nested containers are up to the runtime
+            "filetype" | "nested" => {}
+            _ => {
+                return Err(Error::bad_request(format!(
+                    "invalid SELinux option: {option}"
+                )))
+            }
+        }
+        Ok(())
+    }
+
+    /// A user, with an optional group: `user[:group]`.
+    fn parse_user(&mut self, user: &str) -> Result<(), Error> {
+        let (user, group) = match user.split_once(':') {
+            Some((user, group)) => (user, Some(group)),
+            None => (user, None),
+        };
+        match user.parse() {
+            Ok(uid) => self.run_as_user = Some(uid),
+            Err(_) if user.is_empty() => {}
+            Err(_) => self.run_as_username = user.to_string(),
+        }
+        if let Some(group) = group.filter(|group| !group.is_empty()) {
+            self.run_as_group = Some(parse_id(group, "group")?);
+        }
+        Ok(())
+    }
+
+    fn parse_groups<'a>(&mut self, groups: impl Iterator<Item = &'a String>) -> Result<(), Error> {
+        for group in groups {
+            // Podman's `keep-groups` only makes sense for its own runtimes
+            if group != "keep-groups" {
+                self.groups.push(parse_id(group, "group")?);
+            }
+        }
+        Ok(())
+    }
+
+    fn selinux_options(&self) -> Option<cri::SeLinuxOption> {
+        (self.selinux != cri::SeLinuxOption::default()).then(|| self.selinux.clone())
+    }
+
+    /// The security context of the container.
+    pub fn container_context(&self) -> cri::LinuxContainerSecurityContext {
+        cri::LinuxContainerSecurityContext {
+            capabilities: Some(cri::Capability {
+                add_capabilities: self.cap_add.clone(),
+                drop_capabilities: self.cap_drop.clone(),
+                ..Default::default()
+            }),
+            privileged: self.privileged,
+            selinux_options: self.selinux_options(),
+            run_as_user: self.run_as_user.map(|value| cri::Int64Value { value }),
+            run_as_group: self.run_as_group.map(|value| cri::Int64Value { value }),
+            run_as_username: self.run_as_username.clone(),
+            readonly_rootfs: self.readonly_rootfs,
+            supplemental_groups: self.groups.clone(),
+            no_new_privs: self.no_new_privileges,
+            // Podman confines containers by default, not setting a profile would leave them unconfined
+            seccomp: Some(self.seccomp.clone().unwrap_or_else(default_profile)),
+            apparmor: Some(self.apparmor.clone().unwrap_or_else(default_profile)),
+            ..Default::default()
+        }
+    }
+
+    /// The security context of a sandbox created for the container alone.
+    pub fn sandbox_context(&self) -> cri::LinuxSandboxSecurityContext {
+        cri::LinuxSandboxSecurityContext {
+            selinux_options: self.selinux_options(),
+            privileged: self.privileged,
+            seccomp: Some(self.seccomp.clone().unwrap_or_else(default_profile)),
+            apparmor: Some(self.apparmor.clone().unwrap_or_else(default_profile)),
+            ..Default::default()
+        }
+    }
+
+    /// The options in the `SecurityOpt` format of inspect.
+    pub fn security_opt(&self) -> Vec<String> {
+        let labels = [
+            ("user", &self.selinux.user),
+            ("role", &self.selinux.role),
+            ("type", &self.selinux.r#type),
+            ("level", &self.selinux.level),
+        ];
+        let labels = labels
+            .into_iter()
+            .filter(|(_, value)| !value.is_empty())
+            .map(|(key, value)| format!("label={key}:{value}"));
+        let profiles = [
+            self.seccomp
+                .as_ref()
+                .and_then(|profile| profile_option("seccomp", profile)),
+            self.apparmor
+                .as_ref()
+                .and_then(|profile| profile_option("apparmor", profile)),
+        ];
+
+        labels
+            .chain(profiles.into_iter().flatten())
+            .chain(
+                self.no_new_privileges
+                    .then(|| "no-new-privileges".to_string()),
+            )
+            .collect()
+    }
+}
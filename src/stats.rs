@@ -0,0 +1,509 @@
+//! Resource usage statistics of containers and pods.
+//!
+//! The CRI reports cumulative counters, so percentages are computed from the difference
+//! between two samples. Network usage is only known per pod sandbox,
+//! which its containers share. The CRI knows nothing about block IO.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use podman_api::models::{ContainerNetworkStats, ContainerStats, PodStatsReport};
+use serde::Serialize;
+
+use crate::cri;
+use crate::cri_clients::get_client;
+use crate::error::Error;
+
+const NANOS_PER_SECOND: f64 = 1e9;
+
+/// Name of the interface used to report the stats of the default interface of a sandbox.
+const DEFAULT_INTERFACE: &str = "eth0";
+
+/// Stats of one container at a point in time.
+#[derive(Clone, Debug)]
+pub struct Sample {
+    pub stats: cri::ContainerStats,
+    pub pod_sandbox_id: String,
+    /// Network usage of the sandbox of the container.
+    pub network: Option<cri::NetworkUsage>,
+}
+
+impl Sample {
+    pub fn id(&self) -> &str {
+        self.stats
+            .attributes
+            .as_ref()
+            .map(|attributes| attributes.id.as_str())
+            .unwrap_or_default()
+    }
+
+    pub fn name(&self) -> &str {
+        self.stats
+            .attributes
+            .as_ref()
+            .and_then(|attributes| attributes.metadata.as_ref())
+            .map(|metadata| metadata.name.as_str())
+            .unwrap_or_default()
+    }
+
+    fn cpu_usage(&self) -> u64 {
+        self.stats
+            .cpu
+            .as_ref()
+            .and_then(|cpu| cpu.usage_core_nano_seconds.as_ref())
+            .map(|usage| usage.value)
+            .unwrap_or_default()
+    }
+
+    fn cpu_timestamp(&self) -> i64 {
+        self.stats
+            .cpu
+            .as_ref()
+            .map(|cpu| cpu.timestamp)
+            .unwrap_or_default()
+    }
+
+    /// CPU usage in percent of one core since `previous`,
+    /// or averaged by the runtime over its own sample window without a previous sample.
+    pub fn cpu_percent(&self, previous: Option<&Sample>) -> f64 {
+        match previous {
+            Some(previous) if self.cpu_timestamp() > previous.cpu_timestamp() => {
+                let usage = self.cpu_usage().saturating_sub(previous.cpu_usage());
+                let elapsed = self.cpu_timestamp() - previous.cpu_timestamp();
+                usage as f64 / elapsed as f64 * 100.0
+            }
+            _ => {
+                let nano_cores = self
+                    .stats
+                    .cpu
+                    .as_ref()
+                    .and_then(|cpu| cpu.usage_nano_cores.as_ref())
+                    .map(|usage| usage.value)
+                    .unwrap_or_default();
+                nano_cores as f64 / NANOS_PER_SECOND * 100.0
+            }
+        }
+    }
+
+    fn memory(&self) -> Option<&cri::MemoryUsage> {
+        self.stats.memory.as_ref()
+    }
+
+    /// Memory in use, in bytes.
+    pub fn memory_usage(&self) -> u64 {
+        self.memory()
+            .and_then(|memory| memory.working_set_bytes.as_ref())
+            .map(|usage| usage.value)
+            .unwrap_or_default()
+    }
+
+    /// Memory limit in bytes, the memory of the host when the container has no limit.
+    pub fn memory_limit(&self) -> u64 {
+        match self
+            .memory()
+            .and_then(|memory| memory.available_bytes.as_ref())
+        {
+            Some(available) if available.value > 0 => self.memory_usage() + available.value,
+            _ => host_memory(),
+        }
+    }
+
+    pub fn memory_percent(&self) -> f64 {
+        match self.memory_limit() {
+            0 => 0.0,
+            limit => self.memory_usage() as f64 / limit as f64 * 100.0,
+        }
+    }
+
+    /// Network usage per interface.
+    pub fn networks(&self) -> HashMap<String, cri::NetworkInterfaceUsage> {
+        let Some(network) = &self.network else {
+            return HashMap::new();
+        };
+        let default = network.default_interface.iter().map(|interface| {
+            let name = if interface.name.is_empty() {
+                DEFAULT_INTERFACE.to_string()
+            } else {
+                interface.name.clone()
+            };
+            (name, interface.clone())
+        });
+        let others = network
+            .interfaces
+            .iter()
+            .map(|interface| (interface.name.clone(), interface.clone()));
+        default.chain(others).collect()
+    }
+
+    /// Total of the bytes received and transmitted on all interfaces.
+    fn network_io(&self) -> (u64, u64) {
+        let value = |value: &Option<cri::UInt64Value>| {
+            value.as_ref().map(|value| value.value).unwrap_or_default()
+        };
+        self.networks()
+            .values()
+            .fold((0, 0), |(rx, tx), interface| {
+                (
+                    rx + value(&interface.rx_bytes),
+                    tx + value(&interface.tx_bytes),
+                )
+            })
+    }
+
+    fn time(&self) -> Option<DateTime<Utc>> {
+        match self.cpu_timestamp() {
+            0 => None,
+            nanos => Some(DateTime::from_timestamp_nanos(nanos)),
+        }
+    }
+}
+
+/// Total memory of the host in bytes, from `/proc/meminfo`.
+fn host_memory() -> u64 {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").unwrap_or_default();
+    meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemTotal:"))
+        .and_then(|value| value.trim().strip_suffix("kB"))
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .map(|kilobytes| kilobytes * 1024)
+        .unwrap_or_default()
+}
+
+fn online_cpus() -> u64 {
+    std::thread::available_parallelism()
+        .map(|cpus| cpus.get() as u64)
+        .unwrap_or(1)
+}
+
+/// Take a sample of the stats of the containers with the given IDs, of all the containers if empty.
+pub async fn sample(container_ids: &[String]) -> Result<Vec<Sample>, Error> {
+    let mut client = get_client().await?;
+
+    let stats = match container_ids {
+        [container_id] => {
+            let request = cri::ContainerStatsRequest {
+                container_id: container_id.clone(),
+            };
+            let response = client.container_stats(request).await?.into_inner();
+            response.stats.into_iter().collect()
+        }
+        _ => {
+            let request = cri::ListContainerStatsRequest { filter: None };
+            let response = client.list_container_stats(request).await?.into_inner();
+            let wanted: HashSet<&String> = container_ids.iter().collect();
+            response
+                .stats
+                .into_iter()
+                .filter(|stats| {
+                    let id = stats.attributes.as_ref().map(|attributes| &attributes.id);
+                    wanted.is_empty() || id.is_some_and(|id| wanted.contains(id))
+                })
+                .collect::<Vec<_>>()
+        }
+    };
+
+    // container stats don't tell the sandbox of the container
+    let request = cri::ListContainersRequest { filter: None };
+    let pods: HashMap<String, String> = client
+        .list_containers(request)
+        .await?
+        .into_inner()
+        .containers
+        .into_iter()
+        .map(|container| (container.id, container.pod_sandbox_id))
+        .collect();
+
+    let mut networks: HashMap<String, Option<cri::NetworkUsage>> = HashMap::new();
+    let mut samples = Vec::new();
+    for stats in stats {
+        let id = stats
+            .attributes
+            .as_ref()
+            .map(|attributes| attributes.id.clone())
+            .unwrap_or_default();
+        let pod_sandbox_id = pods.get(&id).cloned().unwrap_or_default();
+
+        if !networks.contains_key(&pod_sandbox_id) {
+            let network = pod_stats(&mut client, &pod_sandbox_id)
+                .await
+                .and_then(|stats| stats.linux)
+                .and_then(|linux| linux.network);
+            networks.insert(pod_sandbox_id.clone(), network);
+        }
+
+        samples.push(Sample {
+            stats,
+            network: networks[&pod_sandbox_id].clone(),
+            pod_sandbox_id,
+        });
+    }
+    Ok(samples)
+}
+
+/// Take a sample of the stats of the containers of the pods with the given IDs.
+pub async fn sample_pods(pod_sandbox_ids: &[String]) -> Result<Vec<Sample>, Error> {
+    let mut client = get_client().await?;
+
+    let mut samples = Vec::new();
+    for pod_sandbox_id in pod_sandbox_ids {
+        let Some(linux) = pod_stats(&mut client, pod_sandbox_id)
+            .await
+            .and_then(|stats| stats.linux)
+        else {
+            continue;
+        };
+        samples.extend(linux.containers.into_iter().map(|stats| Sample {
+            stats,
+            pod_sandbox_id: pod_sandbox_id.clone(),
+            network: linux.network.clone(),
+        }));
+    }
+    Ok(samples)
+}
+
+/// Stats of a pod sandbox, `None` if the runtime can't tell.
+async fn pod_stats(
+    client: &mut cri::runtime_service_client::RuntimeServiceClient<tonic::transport::Channel>,
+    pod_sandbox_id: &str,
+) -> Option<cri::PodSandboxStats> {
+    if pod_sandbox_id.is_empty() {
+        return None;
+    }
+    let request = cri::PodSandboxStatsRequest {
+        pod_sandbox_id: pod_sandbox_id.to_string(),
+    };
+    match client.pod_sandbox_stats(request).await {
+        Ok(response) => response.into_inner().stats,
+        Err(status) => {
+            tracing::debug!("no stats for pod {pod_sandbox_id}: {}", status.message());
+            None
+        }
+    }
+}
+
+impl From<&cri::NetworkInterfaceUsage> for ContainerNetworkStats {
+    fn from(value: &cri::NetworkInterfaceUsage) -> Self {
+        let value_of = |value: &Option<cri::UInt64Value>| value.as_ref().map(|value| value.value);
+        ContainerNetworkStats {
+            rx_bytes: value_of(&value.rx_bytes),
+            rx_errors: value_of(&value.rx_errors),
+            tx_bytes: value_of(&value.tx_bytes),
+            tx_errors: value_of(&value.tx_errors),
+            ..Default::default()
+        }
+    }
+}
+
+/// Stats in the format of the libpod API, see `GET /libpod/containers/stats`.
+pub fn libpod_stats(sample: &Sample, previous: Option<&Sample>) -> ContainerStats {
+    let duration =
+        previous.map(|previous| (sample.cpu_timestamp() - previous.cpu_timestamp()).max(0) as u64);
+
+    ContainerStats {
+        container_id: Some(sample.id().to_string()),
+        name: Some(sample.name().to_string()),
+        cpu: Some(sample.cpu_percent(previous)),
+        cpu_nano: Some(sample.cpu_usage()),
+        system_nano: Some(sample.cpu_timestamp().max(0) as u64),
+        duration,
+        mem_usage: Some(sample.memory_usage()),
+        mem_limit: Some(sample.memory_limit()),
+        mem_perc: Some(sample.memory_percent()),
+        network: Some(
+            sample
+                .networks()
+                .iter()
+                .map(|(name, usage)| (name.clone(), usage.into()))
+                .collect(),
+        ),
+        block_input: Some(0),
+        block_output: Some(0),
+        ..Default::default()
+    }
+}
+
+/// A report of the libpod stats endpoint.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct StatsReport {
+    pub error: Option<String>,
+    pub stats: Vec<ContainerStats>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CompatCpuUsage {
+    total_usage: u64,
+    percpu_usage: Vec<u64>,
+    usage_in_kernelmode: u64,
+    usage_in_usermode: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CompatThrottlingData {
+    periods: u64,
+    throttled_periods: u64,
+    throttled_time: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CompatCpuStats {
+    cpu_usage: CompatCpuUsage,
+    /// Docker clients compute the CPU percentage from the deltas of `total_usage`
+    /// and `system_cpu_usage` times `online_cpus`, so we report the elapsed time of all the CPUs.
+    system_cpu_usage: u64,
+    online_cpus: u64,
+    throttling_data: CompatThrottlingData,
+}
+
+impl From<Option<&Sample>> for CompatCpuStats {
+    fn from(value: Option<&Sample>) -> Self {
+        let Some(sample) = value else {
+            return CompatCpuStats::default();
+        };
+        let online_cpus = online_cpus();
+        CompatCpuStats {
+            cpu_usage: CompatCpuUsage {
+                total_usage: sample.cpu_usage(),
+                ..Default::default()
+            },
+            system_cpu_usage: sample.cpu_timestamp().max(0) as u64 * online_cpus,
+            online_cpus,
+            throttling_data: CompatThrottlingData::default(),
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CompatMemoryStats {
+    usage: u64,
+    limit: u64,
+    stats: HashMap<String, u64>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CompatNetworkStats {
+    rx_bytes: u64,
+    rx_packets: u64,
+    rx_errors: u64,
+    rx_dropped: u64,
+    tx_bytes: u64,
+    tx_packets: u64,
+    tx_errors: u64,
+    tx_dropped: u64,
+}
+
+impl From<&cri::NetworkInterfaceUsage> for CompatNetworkStats {
+    fn from(value: &cri::NetworkInterfaceUsage) -> Self {
+        let value_of = |value: &Option<cri::UInt64Value>| {
+            value.as_ref().map(|value| value.value).unwrap_or_default()
+        };
+        CompatNetworkStats {
+            rx_bytes: value_of(&value.rx_bytes),
+            rx_errors: value_of(&value.rx_errors),
+            tx_bytes: value_of(&value.tx_bytes),
+            tx_errors: value_of(&value.tx_errors),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CompatPidsStats {
+    current: u64,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CompatBlkioStats {
+    io_service_bytes_recursive: Vec<serde_json::Value>,
+}
+
+/// Stats in the format of the Docker API, see `GET /containers/:name/stats`.
+#[derive(Debug, Default, Serialize)]
+pub struct CompatStats {
+    id: String,
+    name: String,
+    read: String,
+    preread: String,
+    num_procs: u32,
+    pids_stats: CompatPidsStats,
+    blkio_stats: CompatBlkioStats,
+    cpu_stats: CompatCpuStats,
+    precpu_stats: CompatCpuStats,
+    memory_stats: CompatMemoryStats,
+    networks: HashMap<String, CompatNetworkStats>,
+}
+
+fn rfc3339(time: Option<DateTime<Utc>>) -> String {
+    time.map(|time| time.to_rfc3339_opts(SecondsFormat::Nanos, true))
+        .unwrap_or("0001-01-01T00:00:00Z".to_string())
+}
+
+pub fn compat_stats(sample: &Sample, previous: Option<&Sample>) -> CompatStats {
+    CompatStats {
+        id: sample.id().to_string(),
+        name: format!("/{}", sample.name()),
+        read: rfc3339(sample.time()),
+        preread: rfc3339(previous.and_then(Sample::time)),
+        cpu_stats: Some(sample).into(),
+        precpu_stats: previous.into(),
+        memory_stats: CompatMemoryStats {
+            usage: sample.memory_usage(),
+            limit: sample.memory_limit(),
+            stats: HashMap::new(),
+        },
+        networks: sample
+            .networks()
+            .iter()
+            .map(|(name, usage)| (name.clone(), usage.into()))
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// Format a size in bytes with 3 significant digits and decimal units, like Podman does.
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 7] = ["B", "kB", "MB", "GB", "TB", "PB", "EB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+
+    let integer_digits = if size >= 1.0 {
+        size.log10().floor() as usize + 1
+    } else {
+        1
+    };
+    let decimals = 3_usize.saturating_sub(integer_digits);
+    let mut number = format!("{size:.decimals$}");
+    if number.contains('.') {
+        number = number
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string();
+    }
+    format!("{number}{}", UNITS[unit])
+}
+
+/// Report of a container of a pod, see `GET /libpod/pods/stats`.
+pub fn pod_stats_report(sample: &Sample) -> PodStatsReport {
+    let (rx, tx) = sample.network_io();
+    let usage = sample.memory_usage();
+    let limit = sample.memory_limit();
+
+    PodStatsReport {
+        pod: Some(sample.pod_sandbox_id.chars().take(12).collect()),
+        cid: Some(sample.id().chars().take(12).collect()),
+        name: Some(sample.name().to_string()),
+        cpu: Some(format!("{:.2}%", sample.cpu_percent(None))),
+        mem: Some(format!("{:.2}%", sample.memory_percent())),
+        mem_usage: Some(format!("{} / {}", human_size(usage), human_size(limit))),
+        mem_usage_bytes: Some(format!("{usage}B / {limit}B")),
+        net_io: Some(format!("{} / {}", human_size(rx), human_size(tx))),
+        block_io: Some(format!("{} / {}", human_size(0), human_size(0))),
+        // the CRI doesn't count the processes of a container
+        pids: Some("--".to_string()),
+    }
+}
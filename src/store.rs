@@ -0,0 +1,283 @@
+//! Persistent store of the Podman specific data of the containers and pods we create.
+//!
+//! The CRI has no way to retrieve the original create requests,
+//! so we save them as JSON files under `PODMAN_CRI_STATE_DIR`, one per CRI object.
+//! Records are removed when the CRI reports that their object is gone.
+//! The `/etc/hosts` files we generate for pods are kept along with the records.
+//! The data of named volumes is kept in a directory per volume.
+
+use std::{
+    collections::{HashMap, HashSet},
+    io,
+    path::PathBuf,
+    time::{Duration, SystemTime},
+};
+
+use chrono::{DateTime, Utc};
+use podman_api::models::{CreateContainerConfig, PodSpecGenerator, PortMapping, SpecGenerator};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::cri;
+use crate::volumes::ContainerVolumes;
+
+const STATE_DIR_DEFAULT: &str = "/var/lib/podman-cri";
+
+const CONTAINERS: &str = "containers";
+const PODS: &str = "pods";
+const HOSTS: &str = "hosts";
+const VOLUMES: &str = "volumes";
+const VOLUME_DATA: &str = "volume-data";
+
+const JSON: &str = "json";
+const HOSTS_EXTENSION: &str = "hosts";
+
+/// How long before a list the files kept by the garbage collection may have been written,
+/// in case the file system has a coarse clock.
+const GC_GRACE: Duration = Duration::from_secs(1);
+
+/// The original request that created a container.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum CreateRequest {
+    Libpod(Box<SpecGenerator>),
+    Compat(Box<CreateContainerConfig>),
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ContainerRecord {
+    /// CRI container ID.
+    pub id: String,
+    /// User-facing name.
+    pub name: String,
+    pub pod_sandbox_id: String,
+    /// The pod was created implicitly for this container.
+    pub auto_pod: bool,
+    pub request: CreateRequest,
+    /// Ports published by the sandbox of the container, with their host ports resolved.
+    #[serde(default)]
+    pub port_mappings: Vec<PortMapping>,
+    /// Volumes and tmpfs directories mounted in the container.
+    #[serde(default)]
+    pub volumes: ContainerVolumes,
+    /// The resources of the last update, which replace those of the request.
+    #[serde(default)]
+    pub resources: Option<cri::LinuxContainerResources>,
+    /// The IDs the container had before restarts recreated it.
+    #[serde(default)]
+    pub previous_ids: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PodRecord {
+    /// CRI pod sandbox ID.
+    pub id: String,
+    /// User-facing name.
+    pub name: String,
+    pub spec: Option<PodSpecGenerator>,
+    /// Ports published by the pod, with their host ports resolved.
+    #[serde(default)]
+    pub port_mappings: Vec<PortMapping>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct VolumeRecord {
+    pub name: String,
+    pub driver: String,
+    pub labels: HashMap<String, String>,
+    pub options: HashMap<String, String>,
+    pub created_at: DateTime<Utc>,
+    /// The volume was created for a container, without a name.
+    pub anonymous: bool,
+}
+
+fn dir(kind: &str) -> PathBuf {
+    let state_dir = std::env::var("PODMAN_CRI_STATE_DIR").unwrap_or(STATE_DIR_DEFAULT.into());
+    PathBuf::from(state_dir).join(kind)
+}
+
+fn path(kind: &str, id: &str, extension: &str) -> io::Result<PathBuf> {
+    // IDs are used as file names
+    if id.is_empty() || id.starts_with('.') || id.contains('/') {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "invalid ID"));
+    }
+    Ok(dir(kind).join(format!("{id}.{extension}")))
+}
+
+async fn write(kind: &str, id: &str, extension: &str, content: &[u8]) -> io::Result<PathBuf> {
+    let path = path(kind, id, extension)?;
+    tokio::fs::create_dir_all(dir(kind)).await?;
+
+    // write to a temporary file first, so that a crash never leaves a truncated file,
+    // with a unique name, in case the file is saved concurrently
+    let tmp = path.with_extension(format!("{}.tmp", Uuid::new_v4().simple()));
+    tokio::fs::write(&tmp, content).await?;
+    tokio::fs::rename(tmp, &path).await?;
+    Ok(path)
+}
+
+async fn save<T: Serialize>(kind: &str, id: &str, record: &T) -> io::Result<()> {
+    write(kind, id, JSON, &serde_json::to_vec(record)?).await?;
+    Ok(())
+}
+
+async fn load<T: DeserializeOwned>(kind: &str, id: &str) -> Option<T> {
+    let content = tokio::fs::read(path(kind, id, JSON).ok()?).await.ok()?;
+    match serde_json::from_slice(&content) {
+        Ok(record) => Some(record),
+        Err(err) => {
+            tracing::warn!("ignoring corrupted record {kind}/{id}: {err}");
+            None
+        }
+    }
+}
+
+async fn remove(kind: &str, id: &str, extension: &str) {
+    let Ok(path) = path(kind, id, extension) else {
+        return;
+    };
+    match tokio::fs::remove_file(path).await {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => {
+            tracing::warn!("failed to remove record {kind}/{id}: {err}")
+        }
+        _ => {}
+    }
+}
+
+/// The IDs of the objects that have a file.
+async fn ids(kind: &str, extension: &str) -> Vec<String> {
+    let mut ids = Vec::new();
+    let Ok(mut entries) = tokio::fs::read_dir(dir(kind)).await else {
+        return ids;
+    };
+
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let file_name = entry.file_name();
+        if let Some(id) = file_name
+            .to_str()
+            .and_then(|name| name.strip_suffix(&format!(".{extension}")))
+        {
+            ids.push(id.to_string());
+        }
+    }
+    ids
+}
+
+/// Remove the files of the objects that are not in `live`, the objects listed at `listed_at`.
+/// The files written since then are kept, their objects may have been created after the list.
+async fn gc(kind: &str, extension: &str, live: &HashSet<String>, listed_at: SystemTime) {
+    let written_before = listed_at - GC_GRACE;
+    for id in ids(kind, extension).await {
+        if live.contains(&id) {
+            continue;
+        }
+        let Ok(path) = path(kind, &id, extension) else {
+            continue;
+        };
+        let modified = tokio::fs::metadata(path)
+            .await
+            .and_then(|metadata| metadata.modified());
+        if modified.is_ok_and(|modified| modified < written_before) {
+            tracing::debug!("removing stale record {kind}/{id}");
+            remove(kind, &id, extension).await;
+        }
+    }
+}
+
+pub async fn save_container(record: &ContainerRecord) {
+    if let Err(err) = save(CONTAINERS, &record.id, record).await {
+        tracing::error!("failed to save record of container {}: {err}", record.id);
+    }
+}
+
+pub async fn get_container(id: &str) -> Option<ContainerRecord> {
+    load(CONTAINERS, id).await
+}
+
+/// The records of all the containers.
+pub async fn list_containers() -> Vec<ContainerRecord> {
+    let mut records = Vec::new();
+    for id in ids(CONTAINERS, JSON).await {
+        records.extend(get_container(&id).await);
+    }
+    records
+}
+
+pub async fn remove_container(id: &str) {
+    remove(CONTAINERS, id, JSON).await
+}
+
+/// Remove the records of the containers that are not in `live`, listed at `listed_at`.
+pub async fn gc_containers(live: &HashSet<String>, listed_at: SystemTime) {
+    gc(CONTAINERS, JSON, live, listed_at).await
+}
+
+pub async fn save_pod(record: &PodRecord) {
+    if let Err(err) = save(PODS, &record.id, record).await {
+        tracing::error!("failed to save record of pod {}: {err}", record.id);
+    }
+}
+
+pub async fn get_pod(id: &str) -> Option<PodRecord> {
+    load(PODS, id).await
+}
+
+pub async fn remove_pod(id: &str) {
+    remove(PODS, id, JSON).await;
+    remove(HOSTS, id, HOSTS_EXTENSION).await
+}
+
+/// Remove the records of the pods that are not in `live`, listed at `listed_at`.
+pub async fn gc_pods(live: &HashSet<String>, listed_at: SystemTime) {
+    gc(PODS, JSON, live, listed_at).await;
+    gc(HOSTS, HOSTS_EXTENSION, live, listed_at).await
+}
+
+/// Save the `/etc/hosts` file of the containers of a pod, returns its path.
+pub async fn save_hosts(pod_id: &str, content: &str) -> io::Result<PathBuf> {
+    write(HOSTS, pod_id, HOSTS_EXTENSION, content.as_bytes()).await
+}
+
+/// The path of the `/etc/hosts` file of a pod, if it has one.
+pub async fn hosts_path(pod_id: &str) -> Option<PathBuf> {
+    let path = path(HOSTS, pod_id, HOSTS_EXTENSION).ok()?;
+    tokio::fs::try_exists(&path)
+        .await
+        .unwrap_or(false)
+        .then_some(path)
+}
+
+/// The directory holding the data of a volume.
+pub fn volume_path(name: &str) -> io::Result<PathBuf> {
+    // the name is checked like the one of the record
+    path(VOLUMES, name, JSON)?;
+    Ok(dir(VOLUME_DATA).join(name))
+}
+
+/// Save the record of a volume and create its data directory.
+pub async fn save_volume(record: &VolumeRecord) -> io::Result<()> {
+    tokio::fs::create_dir_all(volume_path(&record.name)?).await?;
+    save(VOLUMES, &record.name, record).await
+}
+
+pub async fn get_volume(name: &str) -> Option<VolumeRecord> {
+    load(VOLUMES, name).await
+}
+
+/// The records of all the volumes.
+pub async fn list_volumes() -> Vec<VolumeRecord> {
+    let mut records = Vec::new();
+    for name in ids(VOLUMES, JSON).await {
+        records.extend(get_volume(&name).await);
+    }
+    records
+}
+
+/// Remove a volume and its data.
+pub async fn remove_volume(name: &str) -> io::Result<()> {
+    match tokio::fs::remove_dir_all(volume_path(name)?).await {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+        _ => {}
+    }
+    remove(VOLUMES, name, JSON).await;
+    Ok(())
+}
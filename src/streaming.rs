@@ -0,0 +1,258 @@
+//! Relay interactive streams between Podman clients and the CRI streaming server.
+//!
+//! The CRI `Exec` and `Attach` calls only return a URL served by the runtime's streaming server.
+//! We connect to that URL with the Kubernetes WebSocket remote command protocol,
+//! where every message is prefixed by a channel byte,
+//! and translate the channels into Podman's stream framing.
+
+use std::future::Future;
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue, StatusCode, Version},
+    response::{IntoResponse, Response},
+};
+use futures::{SinkExt, StreamExt};
+use hyper::upgrade::OnUpgrade;
+use hyper_util::rt::TokioIo;
+use serde::Serialize;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    sync::mpsc,
+};
+use tokio_tungstenite::tungstenite::{self, client::IntoClientRequest, Message};
+use tokio_util::io::ReaderStream;
+
+const STDIN_CHANNEL: u8 = 0;
+const STDOUT_CHANNEL: u8 = 1;
+const STDERR_CHANNEL: u8 = 2;
+const ERROR_CHANNEL: u8 = 3;
+const RESIZE_CHANNEL: u8 = 4;
+const CLOSE_CHANNEL: u8 = 255;
+
+const PROTOCOL_V5: &str = "v5.channel.k8s.io";
+const PROTOCOL_V4: &str = "v4.channel.k8s.io";
+
+const MULTIPLEXED_STREAM: &str = "application/vnd.docker.multiplexed-stream";
+const RAW_STREAM: &str = "application/vnd.docker.raw-stream";
+
+pub type ClientReader = Box<dyn AsyncRead + Send + Unpin>;
+pub type ClientWriter = Box<dyn AsyncWrite + Send + Unpin>;
+
+/// Stream identifiers of Podman's multiplexed framing.
+#[derive(Clone, Copy, Debug)]
+pub enum StreamType {
+    Stdin = 0,
+    Stdout = 1,
+    Stderr = 2,
+}
+
+/// Encode a frame of Podman's multiplexed stream:
+/// a header with the stream type, three zero bytes and the big-endian payload size,
+/// followed by the payload.
+pub fn frame(stream: StreamType, payload: &[u8]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(8 + payload.len());
+    frame.push(stream as u8);
+    frame.extend_from_slice(&[0, 0, 0]);
+    frame.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    frame.extend_from_slice(payload);
+    frame
+}
+
+/// Terminal size, as expected by the resize channel of the streaming server.
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct TerminalSize {
+    pub width: u16,
+    pub height: u16,
+}
+
+/// Return the pending upgrade of the client connection, if the client asked for one.
+pub fn on_upgrade(request: &mut Request) -> Option<OnUpgrade> {
+    if request.headers().contains_key(header::UPGRADE) {
+        request.extensions_mut().remove::<OnUpgrade>()
+    } else {
+        None
+    }
+}
+
+/// Hand the client connection over to `session`, like Podman's hijacked endpoints.
+///
+/// When the client asked for an upgrade, the connection becomes bidirectional after a `101` response.
+/// Otherwise only the output is streamed back, in a close-delimited response body.
+pub fn hijack<F, Fut>(upgrade: Option<OnUpgrade>, tty: bool, session: F) -> Response
+where
+    F: FnOnce(Option<ClientReader>, ClientWriter) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let content_type = if tty { RAW_STREAM } else { MULTIPLEXED_STREAM };
+
+    match upgrade {
+        Some(upgrade) => {
+            tokio::spawn(async move {
+                match upgrade.await {
+                    Ok(upgraded) => {
+                        let (reader, writer) = tokio::io::split(TokioIo::new(upgraded));
+                        session(Some(Box::new(reader)), Box::new(writer)).await;
+                    }
+                    Err(err) => tracing::error!("failed to upgrade connection: {err}"),
+                }
+            });
+
+            let mut response = StatusCode::SWITCHING_PROTOCOLS.into_response();
+            let headers = response.headers_mut();
+            headers.insert(header::CONNECTION, HeaderValue::from_static("Upgrade"));
+            headers.insert(header::UPGRADE, HeaderValue::from_static("tcp"));
+            headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+            response
+        }
+        None => {
+            let (writer, reader) = tokio::io::duplex(64 * 1024);
+            tokio::spawn(session(None, Box::new(writer)));
+
+            // HTTP/1.0 makes hyper delimit the body by closing the connection instead of chunking it,
+            // so that clients reading the raw socket see the plain stream.
+            let mut response = Response::new(Body::from_stream(ReaderStream::new(reader)));
+            *response.version_mut() = Version::HTTP_10;
+            response
+                .headers_mut()
+                .insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+            response
+        }
+    }
+}
+
+/// Parse the exit code from the status object sent on the error channel.
+/// The streaming server reports a non-zero exit code as the cause of a failure,
+/// a failure without one is a process that could not run.
+fn parse_exit_code(payload: &[u8]) -> Option<i64> {
+    let status: serde_json::Value = serde_json::from_slice(payload).ok()?;
+    match status["status"].as_str()? {
+        "Success" => Some(0),
+        _ => status["details"]["causes"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|cause| cause["reason"] == "ExitCode")
+            .and_then(|cause| cause["message"].as_str())
+            .and_then(|code| code.parse().ok())
+            .or_else(|| Some(failure_exit_code(status["message"].as_str()?))),
+    }
+}
+
+/// The exit code of a process that could not run, like the ones of Podman:
+/// 126 when the command cannot be invoked, 127 when it's not found, 125 otherwise.
+fn failure_exit_code(message: &str) -> i64 {
+    let message = message.to_lowercase();
+    if message.contains("permission denied") {
+        126
+    } else if message.contains("not found") || message.contains("no such file") {
+        127
+    } else {
+        125
+    }
+}
+
+async fn read_input(input: &mut Option<ClientReader>, buf: &mut [u8]) -> std::io::Result<usize> {
+    match input {
+        Some(reader) => reader.read(buf).await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn recv_resize(resize: &mut Option<mpsc::Receiver<TerminalSize>>) -> Option<TerminalSize> {
+    match resize {
+        Some(receiver) => receiver.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Connect to the CRI streaming `url` and relay the streams until the remote process ends.
+///
+/// Output is written to `output`, multiplexed unless `tty` is set.
+/// Returns the exit code of the process, when the streaming server reports one.
+pub async fn relay(
+    url: &str,
+    tty: bool,
+    mut input: Option<ClientReader>,
+    mut output: ClientWriter,
+    mut resize: Option<mpsc::Receiver<TerminalSize>>,
+) -> Result<Option<i64>, tungstenite::Error> {
+    let mut request = url.replacen("http", "ws", 1).into_client_request()?;
+    request.headers_mut().insert(
+        header::SEC_WEBSOCKET_PROTOCOL,
+        HeaderValue::from_str(&format!("{PROTOCOL_V5}, {PROTOCOL_V4}"))?,
+    );
+
+    let (socket, response) = tokio_tungstenite::connect_async(request).await?;
+    let can_close_stdin = response
+        .headers()
+        .get(header::SEC_WEBSOCKET_PROTOCOL)
+        .is_some_and(|protocol| protocol == PROTOCOL_V5);
+    let (mut sink, mut stream) = socket.split();
+
+    let mut exit_code = None;
+    let mut buf = vec![0u8; 32 * 1024];
+
+    loop {
+        tokio::select! {
+            message = stream.next() => match message {
+                Some(Ok(Message::Binary(data))) => {
+                    let Some((channel, payload)) = data.split_first() else {
+                        continue;
+                    };
+                    let stream_type = match *channel {
+                        STDOUT_CHANNEL => StreamType::Stdout,
+                        STDERR_CHANNEL => StreamType::Stderr,
+                        ERROR_CHANNEL => {
+                            exit_code = parse_exit_code(payload);
+                            continue;
+                        }
+                        _ => continue,
+                    };
+                    if payload.is_empty() {
+                        continue;
+                    }
+                    let written = if tty {
+                        output.write_all(payload).await
+                    } else {
+                        output.write_all(&frame(stream_type, payload)).await
+                    };
+                    if written.and(output.flush().await).is_err() {
+                        // the client went away
+                        break;
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None => break,
+                Some(Ok(_)) => {}
+                Some(Err(err)) => return Err(err),
+            },
+            read = read_input(&mut input, &mut buf) => match read {
+                Ok(n) if n > 0 => {
+                    let mut data = Vec::with_capacity(n + 1);
+                    data.push(STDIN_CHANNEL);
+                    data.extend_from_slice(&buf[..n]);
+                    sink.send(Message::Binary(data)).await?;
+                }
+                _ => {
+                    input = None;
+                    if can_close_stdin {
+                        sink.send(Message::Binary(vec![CLOSE_CHANNEL, STDIN_CHANNEL])).await?;
+                    }
+                }
+            },
+            size = recv_resize(&mut resize) => match size {
+                Some(size) => {
+                    let mut data = vec![RESIZE_CHANNEL];
+                    data.extend(serde_json::to_vec(&size).unwrap_or_default());
+                    sink.send(Message::Binary(data)).await?;
+                }
+                None => resize = None,
+            },
+        }
+    }
+
+    let _ = output.shutdown().await;
+    Ok(exit_code)
+}
@@ -64,9 +64,10 @@ pub async fn send(request: Request<Body>, path: String) -> Response<Incoming> {
 
 #[derive(Clone, Debug)]
 #[allow(dead_code)]
-struct UdsConnectInfo {
+pub struct UdsConnectInfo {
     peer_addr: Arc<tokio::net::unix::SocketAddr>,
-    peer_cred: UCred,
+    /// The credentials of the client process.
+    pub peer_cred: UCred,
 }
 
 impl connect_info::Connected<&UnixStream> for UdsConnectInfo {
@@ -0,0 +1,209 @@
+//! User namespaces of sandboxes, see Podman's `--userns`, `--uidmap` and `--gidmap`.
+//!
+//! In the CRI, the user namespace belongs to the sandbox and all its containers share it.
+//! Explicit mappings are passed in the sandbox security context. The `auto` and `keep-id` modes
+//! let CRI-O allocate the mappings, through its `userns-mode` annotation,
+//! which the runtime handler must allow. `keep-id` maps the IDs of the calling client,
+//! podman-cri itself runs as root.
+
+use tokio::net::unix::UCred;
+
+use podman_api::models::{IdMap, IdMappingOptions, Namespace};
+
+use crate::cri;
+use crate::error::Error;
+
+/// Annotation selecting the user namespace mode of a sandbox in CRI-O.
+const USERNS_MODE_ANNOTATION: &str = "io.kubernetes.cri-o.userns-mode";
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub enum UserNamespace {
+    /// The user namespace of the host.
+    #[default]
+    Host,
+    /// Mappings allocated by the runtime, with the options of the CRI-O annotation.
+    Auto(Vec<String>),
+    /// Explicit mappings.
+    Mappings(cri::UserNamespace),
+}
+
+fn unsupported(mode: &str) -> Error {
+    Error::bad_request(format!(
+        "user namespace mode \"{mode}\" is not supported by the CRI runtime"
+    ))
+}
+
+fn parse_id(value: &str, option: &str) -> Result<u32, Error> {
+    value
+        .parse()
+        .map_err(|_| Error::bad_request(format!("invalid user namespace option: {option}")))
+}
+
+fn mapping(map: &IdMap) -> Result<cri::IdMapping, Error> {
+    let id = |value: Option<i64>| {
+        value
+            .and_then(|value| u32::try_from(value).ok())
+            .ok_or_else(|| Error::bad_request("invalid ID mapping"))
+    };
+    Ok(cri::IdMapping {
+        container_id: id(map.container_id)?,
+        host_id: id(map.host_id)?,
+        length: id(map.size)?,
+    })
+}
+
+fn mappings(maps: &[IdMap]) -> Result<Vec<cri::IdMapping>, Error> {
+    maps.iter().map(mapping).collect()
+}
+
+/// An additional mapping of the `auto` mode, in the `container:host:size` format.
+fn auto_mapping(map: &IdMap) -> Result<String, Error> {
+    let map = mapping(map)?;
+    Ok(format!(
+        "{}:{}:{}",
+        map.container_id, map.host_id, map.length
+    ))
+}
+
+impl UserNamespace {
+    /// The user namespace of a libpod create request, from its `userns` and `idmappings`,
+    /// `client` is the caller whose IDs `keep-id` keeps.
+    pub fn from_spec(
+        userns: Option<&Namespace>,
+        idmappings: Option<&IdMappingOptions>,
+        client: &UCred,
+    ) -> Result<Self, Error> {
+        let mode = userns
+            .and_then(|userns| userns.nsmode.as_deref())
+            .unwrap_or_default();
+        let options = userns
+            .and_then(|userns| userns.value.as_deref())
+            .unwrap_or_default();
+
+        match mode {
+            "" | "default" | "host" | "private" => match idmappings {
+                Some(idmappings) => Self::from_idmappings(idmappings, mode == "private"),
+                None if mode == "private" => Ok(UserNamespace::Auto(Vec::new())),
+                None => Ok(UserNamespace::Host),
+            },
+            _ => Self::from_mode(mode, options, client),
+        }
+    }
+
+    /// The user namespace of a `mode[:options]` string, like the compat `UsernsMode`.
+    pub fn parse(userns: &str, client: &UCred) -> Result<Self, Error> {
+        let (mode, options) = userns.split_once(':').unwrap_or((userns, ""));
+        match mode {
+            "" | "host" => Ok(UserNamespace::Host),
+            "private" => Ok(UserNamespace::Auto(Vec::new())),
+            _ => Self::from_mode(mode, options, client),
+        }
+    }
+
+    fn from_mode(mode: &str, options: &str, client: &UCred) -> Result<Self, Error> {
+        let options = options.split(',').filter(|option| !option.is_empty());
+        match mode {
+            "auto" => {
+                let options = options
+                    .map(|option| match option.split_once('=') {
+                        Some(("size" | "uidmapping" | "gidmapping", _)) => Ok(option.to_string()),
+                        _ => Err(Error::bad_request(format!(
+                            "invalid user namespace option: {option}"
+                        ))),
+                    })
+                    .collect::<Result<_, _>>()?;
+                Ok(UserNamespace::Auto(options))
+            }
+            "keep-id" => {
+                let (host_uid, host_gid) = (client.uid(), client.gid());
+                // root of the container would be root of the host
+                if host_uid == 0 || host_gid == 0 {
+                    return Err(Error::bad_request(
+                        "user namespace mode \"keep-id\" is not supported for clients running as root",
+                    ));
+                }
+                // like Podman, the IDs are kept in the container unless `uid` or `gid` is given
+                let (mut uid, mut gid) = (host_uid, host_gid);
+                for option in options {
+                    match option.split_once('=') {
+                        Some(("uid", value)) => uid = parse_id(value, option)?,
+                        Some(("gid", value)) => gid = parse_id(value, option)?,
+                        _ => {
+                            return Err(Error::bad_request(format!(
+                                "invalid user namespace option: {option}"
+                            )))
+                        }
+                    }
+                }
+                Ok(UserNamespace::Auto(vec![
+                    format!("uidmapping={uid}:{host_uid}:1"),
+                    format!("gidmapping={gid}:{host_gid}:1"),
+                ]))
+            }
+            _ => Err(unsupported(mode)),
+        }
+    }
+
+    fn from_idmappings(idmappings: &IdMappingOptions, private: bool) -> Result<Self, Error> {
+        if idmappings.auto_user_ns.unwrap_or(false) {
+            let mut options = Vec::new();
+            if let Some(auto) = &idmappings.auto_user_ns_opts {
+                if let Some(size) = auto.size.filter(|size| *size > 0) {
+                    options.push(format!("size={size}"));
+                }
+                for map in auto.additional_uid_mappings.iter().flatten() {
+                    options.push(format!("uidmapping={}", auto_mapping(map)?));
+                }
+                for map in auto.additional_gid_mappings.iter().flatten() {
+                    options.push(format!("gidmapping={}", auto_mapping(map)?));
+                }
+            }
+            return Ok(UserNamespace::Auto(options));
+        }
+
+        let uids = mappings(idmappings.uid_map.as_deref().unwrap_or_default())?;
+        let gids = mappings(idmappings.gid_map.as_deref().unwrap_or_default())?;
+        // like Podman, a single kind of mappings is used for both
+        let (uids, gids) = match (uids.is_empty(), gids.is_empty()) {
+            (true, true) if private => return Ok(UserNamespace::Auto(Vec::new())),
+            (true, true) => return Ok(UserNamespace::Host),
+            (false, true) => (uids.clone(), uids),
+            (true, false) => (gids.clone(), gids),
+            (false, false) => (uids, gids),
+        };
+        Ok(UserNamespace::Mappings(cri::UserNamespace {
+            mode: cri::NamespaceMode::Pod.into(),
+            uids,
+            gids,
+        }))
+    }
+
+    pub fn is_host(&self) -> bool {
+        *self == UserNamespace::Host
+    }
+
+    /// Set the user namespace of a sandbox.
+    pub fn apply(&self, config: &mut cri::PodSandboxConfig) {
+        match self {
+            UserNamespace::Host => {}
+            UserNamespace::Auto(options) => {
+                let mode = if options.is_empty() {
+                    "auto".to_string()
+                } else {
+                    format!("auto:{}", options.join(";"))
+                };
+                config
+                    .annotations
+                    .insert(USERNS_MODE_ANNOTATION.to_string(), mode);
+            }
+            UserNamespace::Mappings(userns) => {
+                let linux = config.linux.get_or_insert_with(Default::default);
+                let security_context = linux.security_context.get_or_insert_with(Default::default);
+                let namespace_options = security_context
+                    .namespace_options
+                    .get_or_insert_with(Default::default);
+                namespace_options.userns_options = Some(userns.clone());
+            }
+        }
+    }
+}
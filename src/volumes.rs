@@ -0,0 +1,792 @@
+//! Volumes and mounts of containers.
+//!
+//! The CRI only knows bind mounts. Named volumes are directories managed by podman-cri,
+//! like the `local` driver of Podman, without copying the content of the image into them.
+//! tmpfs mounts are backed by directories of the host's `/dev/shm`, itself a tmpfs,
+//! without their size limit, which is reported in the warnings of the create.
+//!
+//! Anonymous volumes and tmpfs directories are named when the container is created,
+//! and the names are written back in its request, so that the request converts to the same
+//! mounts when the container is recreated.
+
+use std::{
+    collections::HashMap,
+    os::unix::fs::PermissionsExt,
+    path::{Component, Path, PathBuf},
+};
+
+use axum::http::StatusCode;
+use chrono::{SecondsFormat, Utc};
+use podman_api::models::{
+    CreateContainerConfig, ImageVolume, Mount, NamedVolume, SpecGenerator, UsageData, Volume,
+    VolumeConfigResponse,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::cri;
+use crate::error::Error;
+use crate::store::{self, VolumeRecord};
+
+pub const LOCAL_DRIVER: &str = "local";
+const LOCAL_SCOPE: &str = "local";
+/// Parent of the directories backing tmpfs mounts.
+const TMPFS_DIR: &str = "/dev/shm/podman-cri";
+
+const BIND: &str = "bind";
+const VOLUME: &str = "volume";
+const TMPFS: &str = "tmpfs";
+
+/// A tmpfs mount of a container.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Tmpfs {
+    /// Directory backing the mount.
+    pub path: PathBuf,
+    pub mode: Option<u32>,
+}
+
+/// The volumes and tmpfs directories of a container, created along with it.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ContainerVolumes {
+    /// Names of the volumes mounted in the container, anonymous ones included.
+    pub volumes: Vec<String>,
+    /// Names of the anonymous volumes, which can be removed with the container.
+    pub anonymous: Vec<String>,
+    pub tmpfs: Vec<Tmpfs>,
+    /// Settings of the mounts that the CRI runtime can't apply.
+    #[serde(skip)]
+    pub warnings: Vec<String>,
+}
+
+fn invalid_option(option: &str) -> Error {
+    Error::bad_request(format!("invalid mount option: {option}"))
+}
+
+fn overlay_unsupported() -> Error {
+    Error::bad_request("overlay mounts are not supported by the CRI runtime")
+}
+
+/// A volume name, like Docker: alphanumeric characters, `_`, `.` and `-`, starting with an
+/// alphanumeric one.
+fn check_name(name: &str) -> Result<(), Error> {
+    let mut chars = name.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphanumeric())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'));
+    if !valid {
+        return Err(Error::bad_request(format!(
+            "invalid volume name: \"{name}\""
+        )));
+    }
+    Ok(())
+}
+
+/// The name of a new anonymous volume, 64 hexadecimal characters like Podman.
+fn anonymous_name() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+fn volume_path(name: &str) -> Result<PathBuf, Error> {
+    check_name(name)?;
+    store::volume_path(name).map_err(|err| Error::internal(format!("volume {name}: {err}")))
+}
+
+/// A path inside a volume, which must not leave it.
+fn sub_path(path: PathBuf, sub_path: Option<&str>) -> Result<PathBuf, Error> {
+    let Some(sub_path) = sub_path.filter(|sub_path| !sub_path.is_empty()) else {
+        return Ok(path);
+    };
+    let relative = Path::new(sub_path);
+    let valid = relative
+        .components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir));
+    if !valid {
+        return Err(Error::bad_request(format!(
+            "invalid volume subpath: {sub_path}"
+        )));
+    }
+    Ok(path.join(relative))
+}
+
+fn propagation(value: &str) -> Result<cri::MountPropagation, Error> {
+    match value {
+        "" | "private" | "rprivate" => Ok(cri::MountPropagation::PropagationPrivate),
+        "slave" | "rslave" => Ok(cri::MountPropagation::PropagationHostToContainer),
+        "shared" | "rshared" => Ok(cri::MountPropagation::PropagationBidirectional),
+        _ => Err(Error::bad_request(format!(
+            "invalid mount propagation: {value}"
+        ))),
+    }
+}
+
+/// An ID mapping of the `idmap` option, `container-host-size`.
+fn id_mapping(map: &str) -> Option<cri::IdMapping> {
+    let mut ids = map.split('-').map(|id| id.parse().ok());
+    let mapping = cri::IdMapping {
+        container_id: ids.next()??,
+        host_id: ids.next()??,
+        length: ids.next()??,
+    };
+    ids.next().is_none().then_some(mapping)
+}
+
+/// The `idmap=uids=...;gids=...` option, mappings being separated by `#`.
+fn apply_idmap(mount: &mut cri::Mount, option: &str) -> Result<(), Error> {
+    let value = option.strip_prefix("idmap=").unwrap_or_default();
+    for part in value.split(';') {
+        let (kind, maps) = part.split_once('=').ok_or_else(|| invalid_option(option))?;
+        let maps = maps
+            .split('#')
+            .map(id_mapping)
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| invalid_option(option))?;
+        match kind {
+            "uids" => mount.uid_mappings = maps,
+            "gids" => mount.gid_mappings = maps,
+            _ => return Err(invalid_option(option)),
+        }
+    }
+    // like Podman, a single kind of mappings is used for both
+    if mount.uid_mappings.is_empty() {
+        mount.uid_mappings = mount.gid_mappings.clone();
+    }
+    if mount.gid_mappings.is_empty() {
+        mount.gid_mappings = mount.uid_mappings.clone();
+    }
+    Ok(())
+}
+
+/// Apply the options of a mount, as in `-v source:destination:options`.
+fn apply_options<'a>(
+    mount: &mut cri::Mount,
+    options: impl IntoIterator<Item = &'a str>,
+) -> Result<(), Error> {
+    for option in options {
+        match option {
+            "ro" | "readonly" => mount.readonly = true,
+            "rw" => mount.readonly = false,
+            // the CRI has a single kind of relabeling
+            "z" | "Z" => mount.selinux_relabel = true,
+            "private" | "rprivate" | "slave" | "rslave" | "shared" | "rshared" => {
+                mount.propagation = propagation(option)?.into()
+            }
+            "idmap" => return Err(Error::bad_request(
+                "the idmap mount option needs explicit uids and gids mappings with the CRI runtime",
+            )),
+            _ if option.starts_with("idmap=") => apply_idmap(mount, option)?,
+            "O" => return Err(overlay_unsupported()),
+            "U" => {
+                return Err(Error::bad_request(
+                    "the U mount option is not supported by the CRI runtime",
+                ))
+            }
+            // defaults of the runtime, or without effect on bind mounts
+            "" | "bind" | "rbind" | "suid" | "nosuid" | "dev" | "nodev" | "exec" | "noexec"
+            | "copy" | "nocopy" | "tmpcopyup" | "notmpcopyup" => {}
+            // tmpfs options, see `tmpfs_mode`
+            _ if option.starts_with("size=") || option.starts_with("mode=") => {}
+            _ => return Err(invalid_option(option)),
+        }
+    }
+    Ok(())
+}
+
+/// The `mode=` option of a tmpfs mount, in octal.
+fn tmpfs_mode<'a>(options: impl IntoIterator<Item = &'a String>) -> Result<Option<u32>, Error> {
+    let mut mode = None;
+    for option in options {
+        if let Some(value) = option.strip_prefix("mode=") {
+            mode = Some(u32::from_str_radix(value, 8).map_err(|_| invalid_option(option))?);
+        }
+    }
+    Ok(mode)
+}
+
+/// The mount of a `Mount`, in the OCI format of libpod or the one of the compat `HostConfig`.
+fn mount(value: &Mount) -> Result<cri::Mount, Error> {
+    let target = value
+        .target
+        .clone()
+        .ok_or_else(|| Error::bad_request("mount without target"))?;
+    let source = || {
+        value
+            .source
+            .clone()
+            .filter(|source| !source.is_empty())
+            .ok_or_else(|| Error::bad_request(format!("mount of {target} without source")))
+    };
+
+    let host_path = match value.r#type.as_deref().unwrap_or(BIND) {
+        BIND => source()?,
+        VOLUME => {
+            let subpath = value
+                .volume_options
+                .as_ref()
+                .and_then(|options| options.subpath.as_deref());
+            sub_path(volume_path(&source()?)?, subpath)?
+                .to_string_lossy()
+                .into_owned()
+        }
+        // the directory named when the container was created
+        TMPFS => source()?,
+        other => {
+            return Err(Error::bad_request(format!(
+                "mounts of type {other} are not supported by the CRI runtime"
+            )))
+        }
+    };
+
+    let mut mount = cri::Mount {
+        container_path: target,
+        host_path,
+        readonly: value.read_only.unwrap_or(false),
+        ..Default::default()
+    };
+    if let Some(value) = value
+        .bind_options
+        .as_ref()
+        .and_then(|options| options.propagation.as_deref())
+    {
+        mount.propagation = propagation(value)?.into();
+    }
+    apply_options(
+        &mut mount,
+        value.options.iter().flatten().map(String::as_str),
+    )?;
+    Ok(mount)
+}
+
+fn named_volume(value: &NamedVolume) -> Result<cri::Mount, Error> {
+    let name = value.name.as_deref().unwrap_or_default();
+    let host_path = sub_path(volume_path(name)?, value.sub_path.as_deref())?;
+    let mut mount = cri::Mount {
+        container_path: value
+            .dest
+            .clone()
+            .ok_or_else(|| Error::bad_request(format!("volume {name} without destination")))?,
+        host_path: host_path.to_string_lossy().into_owned(),
+        ..Default::default()
+    };
+    apply_options(
+        &mut mount,
+        value.options.iter().flatten().map(String::as_str),
+    )?;
+    Ok(mount)
+}
+
+fn image_volume(value: &ImageVolume) -> Result<cri::Mount, Error> {
+    let image = cri::ImageSpec {
+        image: value
+            .source
+            .clone()
+            .ok_or_else(|| Error::bad_request("image volume without source"))?,
+        ..Default::default()
+    };
+
+    Ok(cri::Mount {
+        image: Some(image),
+        container_path: value
+            .destination
+            .clone()
+            .ok_or_else(|| Error::bad_request("image volume without destination"))?,
+        readonly: !value.read_write.unwrap_or(false),
+        image_sub_path: value.sub_path.clone().unwrap_or_default(),
+        ..Default::default()
+    })
+}
+
+/// The source of a `source:destination[:options]` volume, if it is a volume name
+/// rather than a host path.
+fn bind_volume_name(value: &str) -> Option<&str> {
+    value
+        .split_once(':')
+        .map(|(source, _)| source)
+        .filter(|source| !source.is_empty() && !source.starts_with('/'))
+}
+
+/// The mount of a `source:destination[:options]` volume.
+fn bind(value: &str) -> Result<cri::Mount, Error> {
+    let mut parts = value.splitn(3, ':');
+    let (Some(source), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(Error::bad_request(format!("invalid volume: {value}")));
+    };
+    if source.is_empty() || target.is_empty() {
+        return Err(Error::bad_request(format!("invalid volume: {value}")));
+    }
+
+    let host_path = match bind_volume_name(value) {
+        Some(name) => volume_path(name)?.to_string_lossy().into_owned(),
+        None => source.to_string(),
+    };
+    let mut mount = cri::Mount {
+        container_path: target.to_string(),
+        host_path,
+        ..Default::default()
+    };
+    apply_options(&mut mount, parts.next().unwrap_or_default().split(','))?;
+    Ok(mount)
+}
+
+/// The mounts of a libpod create request.
+pub fn spec_mounts(spec: &SpecGenerator) -> Result<Vec<cri::Mount>, Error> {
+    if spec
+        .overlay_volumes
+        .as_ref()
+        .is_some_and(|volumes| !volumes.is_empty())
+    {
+        return Err(overlay_unsupported());
+    }
+
+    let mounts = spec.mounts.iter().flatten().map(mount);
+    let volumes = spec.volumes.iter().flatten().map(named_volume);
+    let image_volumes = spec.image_volumes.iter().flatten().map(image_volume);
+    mounts.chain(volumes).chain(image_volumes).collect()
+}
+
+/// The mounts of a compat create request.
+pub fn create_config_mounts(config: &CreateContainerConfig) -> Result<Vec<cri::Mount>, Error> {
+    let mut mounts = Vec::new();
+    for key in config.volumes.iter().flat_map(HashMap::keys) {
+        mounts.push(bind(key)?);
+    }
+    if let Some(host_config) = &config.host_config {
+        for value in host_config.binds.iter().flatten() {
+            mounts.push(bind(value)?);
+        }
+        for value in host_config.mounts.iter().flatten() {
+            mounts.push(mount(value)?);
+        }
+    }
+    Ok(mounts)
+}
+
+async fn save_volume(
+    name: &str,
+    labels: HashMap<String, String>,
+    anonymous: bool,
+) -> Result<VolumeRecord, Error> {
+    check_name(name)?;
+    let record = VolumeRecord {
+        name: name.to_string(),
+        driver: LOCAL_DRIVER.to_string(),
+        labels,
+        options: HashMap::new(),
+        created_at: Utc::now(),
+        anonymous,
+    };
+    store::save_volume(&record)
+        .await
+        .map_err(|err| Error::internal(format!("cannot create volume {name}: {err}")))?;
+    Ok(record)
+}
+
+/// Create a volume, with a random name if it has none.
+/// With `ignore_if_exists`, an existing volume of the same name is returned instead of an error.
+pub async fn create(
+    name: Option<String>,
+    driver: Option<String>,
+    labels: HashMap<String, String>,
+    options: HashMap<String, String>,
+    ignore_if_exists: bool,
+) -> Result<VolumeRecord, Error> {
+    if let Some(driver) = driver.filter(|driver| !driver.is_empty() && driver != LOCAL_DRIVER) {
+        return Err(Error::bad_request(format!(
+            "volume driver {driver} is not supported, only {LOCAL_DRIVER} is"
+        )));
+    }
+    // the options of the local driver mount a file system, volumes are plain directories here
+    if !options.is_empty() {
+        return Err(Error::bad_request("volume options are not supported"));
+    }
+
+    let name = name
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(anonymous_name);
+    check_name(&name)?;
+    if let Some(existing) = store::get_volume(&name).await {
+        if ignore_if_exists {
+            return Ok(existing);
+        }
+        return Err(Error::new(
+            StatusCode::CONFLICT,
+            "volume already exists",
+            format!("volume with name {name} already exists: volume already exists"),
+        ));
+    }
+    save_volume(&name, labels, false).await
+}
+
+pub async fn get(name: &str) -> Result<VolumeRecord, Error> {
+    store::get_volume(name)
+        .await
+        .ok_or_else(|| Error::no_such("volume", name))
+}
+
+/// Remove a volume and its data.
+pub async fn remove(name: &str) -> Result<(), Error> {
+    store::remove_volume(name)
+        .await
+        .map_err(|err| Error::internal(format!("cannot remove volume {name}: {err}")))
+}
+
+fn dir_size(path: &Path) -> u64 {
+    let Ok(metadata) = std::fs::symlink_metadata(path) else {
+        return 0;
+    };
+    if !metadata.is_dir() {
+        return metadata.len();
+    }
+    std::fs::read_dir(path)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| dir_size(&entry.path()))
+        .sum()
+}
+
+/// The size of the data of a volume, in bytes.
+pub async fn size(name: &str) -> i64 {
+    let Ok(path) = store::volume_path(name) else {
+        return 0;
+    };
+    let size = tokio::task::spawn_blocking(move || dir_size(&path))
+        .await
+        .unwrap_or_default();
+    i64::try_from(size).unwrap_or(i64::MAX)
+}
+
+fn mountpoint(name: &str) -> String {
+    store::volume_path(name)
+        .map(|path| path.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// The volume in the format of the compat API.
+pub fn to_volume(record: VolumeRecord, usage_data: Option<UsageData>) -> Volume {
+    Volume {
+        created_at: Some(record.created_at.to_rfc3339_opts(SecondsFormat::Secs, true)),
+        usage_data,
+        ..Volume::new(
+            record.driver,
+            record.labels,
+            mountpoint(&record.name),
+            record.name,
+            record.options,
+            LOCAL_SCOPE.to_string(),
+        )
+    }
+}
+
+/// The volume in the format of the libpod API, `mount_count` being the number of running
+/// containers that mount it.
+pub fn to_volume_config(record: VolumeRecord, mount_count: usize) -> VolumeConfigResponse {
+    VolumeConfigResponse {
+        anonymous: Some(record.anonymous),
+        created_at: Some(record.created_at),
+        driver: Some(record.driver),
+        labels: Some(record.labels),
+        mount_count: Some(i32::try_from(mount_count).unwrap_or(i32::MAX)),
+        mountpoint: Some(mountpoint(&record.name)),
+        name: Some(record.name),
+        needs_chown: Some(false),
+        needs_copy_up: Some(false),
+        options: Some(record.options),
+        scope: Some(LOCAL_SCOPE.to_string()),
+        ..VolumeConfigResponse::new()
+    }
+}
+
+impl ContainerVolumes {
+    /// Name the anonymous volumes and the tmpfs directories of a libpod create request.
+    pub fn prepare_spec(spec: &mut SpecGenerator) -> Result<Self, Error> {
+        let mut result = ContainerVolumes::default();
+        for volume in spec.volumes.iter_mut().flatten() {
+            if result.add_volume(&mut volume.name) {
+                volume.is_anonymous = Some(true);
+            }
+        }
+        for mount in spec.mounts.iter_mut().flatten() {
+            result.prepare_mount(mount)?;
+        }
+        Ok(result)
+    }
+
+    /// Name the anonymous volumes and the tmpfs directories of a compat create request.
+    /// Docker's anonymous volumes, the `Volumes` without a source, and the `Tmpfs` mounts
+    /// become `Mounts`.
+    pub fn prepare_create_config(config: &mut CreateContainerConfig) -> Result<Self, Error> {
+        let mut new_mounts = Vec::new();
+        if let Some(volumes) = &mut config.volumes {
+            volumes.retain(|key, _| {
+                if key.contains(':') {
+                    return true;
+                }
+                new_mounts.push(Mount {
+                    r#type: Some(VOLUME.to_string()),
+                    target: Some(key.clone()),
+                    ..Mount::new()
+                });
+                false
+            });
+        }
+        if let Some(host_config) = &mut config.host_config {
+            for (target, options) in host_config.tmpfs.take().unwrap_or_default() {
+                new_mounts.push(Mount {
+                    r#type: Some(TMPFS.to_string()),
+                    target: Some(target),
+                    options: Some(options.split(',').map(str::to_string).collect()),
+                    ..Mount::new()
+                });
+            }
+        }
+        if !new_mounts.is_empty() {
+            config
+                .host_config
+                .get_or_insert_with(Default::default)
+                .mounts
+                .get_or_insert_with(Vec::new)
+                .extend(new_mounts);
+        }
+
+        let mut result = ContainerVolumes::default();
+        let binds = config
+            .host_config
+            .iter()
+            .flat_map(|host_config| host_config.binds.iter().flatten());
+        for value in config.volumes.iter().flat_map(HashMap::keys).chain(binds) {
+            if let Some(name) = bind_volume_name(value) {
+                result.add_volume(&mut Some(name.to_string()));
+            }
+        }
+        if let Some(host_config) = &mut config.host_config {
+            for mount in host_config.mounts.iter_mut().flatten() {
+                result.prepare_mount(mount)?;
+            }
+        }
+        Ok(result)
+    }
+
+    /// Add a volume, naming it if it is anonymous. Returns whether it is.
+    fn add_volume(&mut self, name: &mut Option<String>) -> bool {
+        let anonymous = name.as_deref().unwrap_or_default().is_empty();
+        let name = if anonymous {
+            let new_name = anonymous_name();
+            self.anonymous.push(new_name.clone());
+            name.insert(new_name)
+        } else {
+            name.get_or_insert_with(String::new)
+        };
+        if !self.volumes.contains(name) {
+            self.volumes.push(name.clone());
+        }
+        anonymous
+    }
+
+    fn prepare_mount(&mut self, mount: &mut Mount) -> Result<(), Error> {
+        match mount.r#type.as_deref() {
+            Some(VOLUME) => {
+                self.add_volume(&mut mount.source);
+            }
+            Some(TMPFS) => {
+                let mode =
+                    match mount
+                        .tmpfs_options
+                        .as_ref()
+                        .and_then(|options| options.mode)
+                    {
+                        Some(mode) => Some(u32::try_from(mode).map_err(|_| {
+                            Error::bad_request(format!("invalid tmpfs mode: {mode}"))
+                        })?),
+                        None => tmpfs_mode(mount.options.iter().flatten())?,
+                    };
+                let size = mount
+                    .tmpfs_options
+                    .as_ref()
+                    .and_then(|options| options.size_bytes)
+                    .is_some_and(|size| size > 0)
+                    || mount
+                        .options
+                        .iter()
+                        .flatten()
+                        .any(|option| option.starts_with("size="));
+                if size {
+                    self.warnings.push(format!(
+                        "the size of the tmpfs mount {} is not limited with the CRI runtime",
+                        mount.target.as_deref().unwrap_or_default()
+                    ));
+                }
+                let path = Path::new(TMPFS_DIR).join(Uuid::new_v4().simple().to_string());
+                mount.source = Some(path.to_string_lossy().into_owned());
+                self.tmpfs.push(Tmpfs { path, mode });
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Create the volumes that don't exist yet and the tmpfs directories.
+    pub async fn create(&self) -> Result<(), Error> {
+        for name in &self.volumes {
+            if store::get_volume(name).await.is_none() {
+                save_volume(name, HashMap::new(), self.anonymous.contains(name)).await?;
+            }
+        }
+
+        for tmpfs in &self.tmpfs {
+            let path = &tmpfs.path;
+            let error = |err| Error::internal(format!("cannot create {}: {err}", path.display()));
+            tokio::fs::create_dir_all(path).await.map_err(error)?;
+            if let Some(mode) = tmpfs.mode {
+                tokio::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+                    .await
+                    .map_err(error)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Remove the tmpfs directories, and with `anonymous` the anonymous volumes
+    /// that no other container uses.
+    pub async fn remove(&self, anonymous: bool) {
+        for tmpfs in &self.tmpfs {
+            if !tmpfs.path.starts_with(TMPFS_DIR) {
+                continue;
+            }
+            if let Err(err) = tokio::fs::remove_dir_all(&tmpfs.path).await {
+                tracing::warn!("failed to remove {}: {err}", tmpfs.path.display());
+            }
+        }
+
+        if !anonymous || self.anonymous.is_empty() {
+            return;
+        }
+        let containers = store::list_containers().await;
+        for name in &self.anonymous {
+            let used = containers
+                .iter()
+                .any(|record| record.volumes.volumes.contains(name));
+            if used {
+                continue;
+            }
+            if let Err(err) = store::remove_volume(name).await {
+                tracing::warn!("failed to remove volume {name}: {err}");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(options: &str) -> Result<cri::Mount, Error> {
+        let mut mount = cri::Mount::default();
+        apply_options(&mut mount, options.split(',')).map(|()| mount)
+    }
+
+    fn mapping(container_id: u32, host_id: u32, length: u32) -> cri::IdMapping {
+        cri::IdMapping {
+            container_id,
+            host_id,
+            length,
+        }
+    }
+
+    #[test]
+    fn bind_of_a_host_path() {
+        let mount = bind("/srv/data:/data").unwrap();
+        assert_eq!(mount.host_path, "/srv/data");
+        assert_eq!(mount.container_path, "/data");
+        assert!(!mount.readonly);
+    }
+
+    #[test]
+    fn bind_with_options() {
+        let mount = bind("/srv/data:/data:ro,Z,rshared").unwrap();
+        assert!(mount.readonly);
+        assert!(mount.selinux_relabel);
+        assert_eq!(
+            mount.propagation,
+            i32::from(cri::MountPropagation::PropagationBidirectional)
+        );
+    }
+
+    #[test]
+    fn bind_of_a_named_volume() {
+        let mount = bind("cache:/cache").unwrap();
+        assert_eq!(
+            mount.host_path,
+            volume_path("cache").unwrap().to_string_lossy()
+        );
+        assert_eq!(bind_volume_name("cache:/cache"), Some("cache"));
+        assert_eq!(bind_volume_name("/srv/cache:/cache"), None);
+    }
+
+    #[test]
+    fn bind_rejects_invalid_volumes() {
+        for value in [
+            "/data",
+            ":/data",
+            "/srv/data:",
+            "../etc:/etc",
+            "/srv:/data:bogus",
+        ] {
+            let err = bind(value).expect_err(value);
+            assert_eq!(err.status(), StatusCode::BAD_REQUEST, "{value}");
+        }
+    }
+
+    #[test]
+    fn later_options_win() {
+        assert!(!apply("ro,rw").unwrap().readonly);
+        assert!(apply("rw,readonly").unwrap().readonly);
+    }
+
+    #[test]
+    fn options_without_effect_are_accepted() {
+        let mount = apply(",rbind,nosuid,nodev,noexec,nocopy,size=64m,mode=1777").unwrap();
+        assert_eq!(mount, cri::Mount::default());
+    }
+
+    #[test]
+    fn unsupported_options_are_rejected() {
+        for options in ["O", "U", "idmap", "private,bogus"] {
+            let err = apply(options).expect_err(options);
+            assert_eq!(err.status(), StatusCode::BAD_REQUEST, "{options}");
+        }
+    }
+
+    #[test]
+    fn idmap_with_both_kinds() {
+        let mount = apply("idmap=uids=0-1000-1#1-100000-65536;gids=0-2000-1").unwrap();
+        assert_eq!(
+            mount.uid_mappings,
+            [mapping(0, 1000, 1), mapping(1, 100000, 65536)]
+        );
+        assert_eq!(mount.gid_mappings, [mapping(0, 2000, 1)]);
+    }
+
+    #[test]
+    fn idmap_of_a_single_kind_is_used_for_both() {
+        let mount = apply("idmap=gids=0-1000-10").unwrap();
+        assert_eq!(mount.uid_mappings, [mapping(0, 1000, 10)]);
+        assert_eq!(mount.gid_mappings, mount.uid_mappings);
+    }
+
+    #[test]
+    fn invalid_idmaps_are_rejected() {
+        for option in [
+            "idmap=",
+            "idmap=uids",
+            "idmap=uids=0-1000",
+            "idmap=uids=0-1000-1-2",
+            "idmap=uids=0-1000-x",
+            "idmap=uids=0--1000-1",
+            "idmap=uids=0-1000-1#",
+            "idmap=users=0-1000-1",
+        ] {
+            let mut mount = cri::Mount::default();
+            let err = apply_idmap(&mut mount, option).expect_err(option);
+            assert_eq!(err.status(), StatusCode::BAD_REQUEST, "{option}");
+        }
+    }
+}